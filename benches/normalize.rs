@@ -0,0 +1,67 @@
+use std::hint::black_box;
+use std::time::Duration;
+
+use criterion::{BenchmarkId, Criterion};
+use frizbee::{Config, Matcher, Matching};
+use rand::prelude::*;
+use rand_distr::Alphanumeric;
+
+const SEED: u64 = 12345;
+const NUM_SAMPLES: usize = 100_000;
+const MEDIAN_LENGTH: usize = 64;
+
+/// Haystacks of random alphanumeric chars, where `unicode_percentage` of them contain a char
+/// with a full case folding
+fn generate_haystacks(unicode_percentage: f64) -> Vec<String> {
+    let mut rng = StdRng::seed_from_u64(SEED);
+    (0..NUM_SAMPLES)
+        .map(|_| {
+            let len = rng.random_range(MEDIAN_LENGTH / 2..MEDIAN_LENGTH * 3 / 2);
+            let mut haystack = (&mut rng)
+                .sample_iter(Alphanumeric)
+                .take(len)
+                .map(char::from)
+                .collect::<String>();
+            if rng.random_bool(unicode_percentage) {
+                haystack.insert(rng.random_range(0..len), 'ß');
+            }
+            haystack
+        })
+        .collect()
+}
+
+/// Matches with the default config, which folds the case of non-ASCII haystacks for lowercase
+/// needles. `xqzv` has no chars produced by a full case folding, unlike `deadbeef`
+fn bench(c: &mut Criterion) {
+    for (name, unicode_percentage) in [("ASCII", 0.0), ("Unicode 10%", 0.1)] {
+        let haystacks = generate_haystacks(unicode_percentage);
+        let mut group = c.benchmark_group(format!("Normalize/{name}"));
+        group.throughput(criterion::Throughput::Elements(NUM_SAMPLES as u64));
+
+        for needle in ["deadbeef", "xqzv"] {
+            for (config_name, config) in [
+                ("Fuzzy", Config::default()),
+                ("Substring", Config::default().matching(Matching::Substring)),
+            ] {
+                group.bench_with_input(
+                    BenchmarkId::new(config_name, needle),
+                    &haystacks,
+                    |b, haystacks| {
+                        let mut matcher = Matcher::new(needle, &config);
+                        b.iter(|| matcher.match_list(black_box(haystacks)))
+                    },
+                );
+            }
+        }
+        group.finish();
+    }
+}
+
+criterion::criterion_group! {
+    name = benches;
+    config = Criterion::default()
+        .warm_up_time(Duration::from_millis(200))
+        .measurement_time(Duration::from_secs(2));
+    targets = bench
+}
+criterion::criterion_main!(benches);
@@ -0,0 +1,146 @@
+//! Unicode classification of haystack chars for the capitalization and delimiter bonuses, used
+//! by the unicode paths of the fuzzy and literal matchers. Each char is classified by its
+//! general category, so the bonuses apply to every script, while the ASCII paths classify bytes
+//! directly
+
+mod table;
+
+use table::MARKS;
+
+/// Class of a haystack char, ordered so each class is a byte that SIMD kernels can compare
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub(crate) enum CharClass {
+    /// Anything that isn't a letter, number, combining mark or format char (e.g. punctuation,
+    /// symbols and whitespace)
+    Delimiter = 0,
+    /// Letters without case (e.g. CJK), numbers, combining marks and format chars
+    Word = 1,
+    Lower = 2,
+    Upper = 3,
+}
+
+impl CharClass {
+    #[inline(always)]
+    pub(crate) fn of(c: char) -> Self {
+        if c.is_ascii() {
+            return match c {
+                'A'..='Z' => CharClass::Upper,
+                'a'..='z' => CharClass::Lower,
+                '0'..='9' => CharClass::Word,
+                _ => CharClass::Delimiter,
+            };
+        }
+        if c.is_uppercase() {
+            CharClass::Upper
+        } else if c.is_lowercase() {
+            CharClass::Lower
+        } else if c.is_alphanumeric() || is_mark(c) {
+            CharClass::Word
+        } else {
+            CharClass::Delimiter
+        }
+    }
+
+    /// Class of the char starting at byte `start` of the haystack. Bytes that aren't valid UTF-8
+    /// are words, like any other non-ASCII byte on the ASCII paths
+    #[inline(always)]
+    pub(crate) fn at(haystack: &[u8], start: usize) -> Self {
+        let len = haystack[start..]
+            .iter()
+            .take(4)
+            .skip(1)
+            .take_while(|&&byte| is_continuation(byte))
+            .count()
+            + 1;
+        Self::of_bytes(&haystack[start..start + len])
+    }
+
+    /// Class of the char ending right before byte `end` of the haystack
+    #[inline(always)]
+    pub(crate) fn before(haystack: &[u8], end: usize) -> Self {
+        let start = (end.saturating_sub(4)..end)
+            .rev()
+            .find(|&idx| !is_continuation(haystack[idx]))
+            .unwrap_or(end - 1);
+        Self::of_bytes(&haystack[start..end])
+    }
+
+    #[inline(always)]
+    fn of_bytes(bytes: &[u8]) -> Self {
+        match std::str::from_utf8(bytes)
+            .ok()
+            .and_then(|s| s.chars().next())
+        {
+            Some(c) => Self::of(c),
+            None => CharClass::Word,
+        }
+    }
+}
+
+/// Fills `classes` with the class of each byte of the haystack, where every byte of a char gets
+/// the char's class
+pub(crate) fn classify_haystack(haystack: &[u8], classes: &mut Vec<u8>) {
+    classes.clear();
+    for chunk in haystack.utf8_chunks() {
+        for c in chunk.valid().chars() {
+            classes.extend(std::iter::repeat_n(CharClass::of(c) as u8, c.len_utf8()));
+        }
+        classes.extend(std::iter::repeat_n(
+            CharClass::Word as u8,
+            chunk.invalid().len(),
+        ));
+    }
+}
+
+#[inline(always)]
+fn is_continuation(byte: u8) -> bool {
+    byte & 0xC0 == 0x80
+}
+
+#[inline(always)]
+fn is_mark(c: char) -> bool {
+    let idx = MARKS.partition_point(|&(first, _)| first <= c);
+    idx > 0 && c <= MARKS[idx - 1].1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn marks_are_sorted() {
+        assert!(MARKS.iter().all(|&(first, last)| first <= last));
+        assert!(MARKS.windows(2).all(|pair| pair[0].1 < pair[1].0));
+    }
+
+    #[test]
+    fn classifies_every_script() {
+        assert_eq!(CharClass::of('É'), CharClass::Upper);
+        assert_eq!(CharClass::of('ф'), CharClass::Lower);
+        assert_eq!(CharClass::of('Ω'), CharClass::Upper);
+        assert_eq!(CharClass::of('日'), CharClass::Word);
+        assert_eq!(CharClass::of('٣'), CharClass::Word);
+        assert_eq!(CharClass::of('\u{301}'), CharClass::Word);
+        assert_eq!(CharClass::of('\u{200D}'), CharClass::Word);
+        assert_eq!(CharClass::of('—'), CharClass::Delimiter);
+        assert_eq!(CharClass::of('、'), CharClass::Delimiter);
+        assert_eq!(CharClass::of('\u{A0}'), CharClass::Delimiter);
+        assert_eq!(CharClass::of('😀'), CharClass::Delimiter);
+    }
+
+    #[test]
+    fn classifies_every_byte_of_a_char() {
+        let mut classes = vec![];
+        classify_haystack("aÉ—_".as_bytes(), &mut classes);
+        assert_eq!(classes, vec![2, 3, 3, 0, 0, 0, 0]);
+
+        let haystack = "aÉ—_".as_bytes();
+        assert_eq!(CharClass::at(haystack, 1), CharClass::Upper);
+        assert_eq!(CharClass::before(haystack, 6), CharClass::Delimiter);
+        assert_eq!(CharClass::before(haystack, 3), CharClass::Upper);
+        assert_eq!(CharClass::before(haystack, 1), CharClass::Lower);
+        // Invalid UTF-8 is never a delimiter
+        assert_eq!(CharClass::at(&[0xFF, b'a'], 0), CharClass::Word);
+    }
+}
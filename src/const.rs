@@ -8,3 +8,4 @@ pub const DELIMITER_BONUS: u16 = 4; // Bonus for matching _after_ a delimiter ch
 pub const CAPITALIZATION_BONUS: u16 = 4; // Bonus for matching a capital letter after a lowercase letter (e.g. "b" on "fooBar" will receive a bonus on "B")
 pub const MATCHING_CASE_BONUS: u16 = 4; // Bonus for matching the case of the needle (e.g. "WorLd" on "WoRld" will receive a bonus on "W", "o", "d")
 pub const EXACT_MATCH_BONUS: u16 = 8; // Bonus for matching the exact needle (e.g. "foo" on "foo" will receive the bonus)
+pub const BASENAME_BONUS: u16 = 24; // Bonus in path mode for matching entirely within the file name (e.g. "main" on "src/main.rs")
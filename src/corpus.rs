@@ -0,0 +1,565 @@
+//! Appendable list of haystacks, matched incrementally by a persistent pool of worker threads.
+//!
+//! Items pushed through a [`Corpus`] (or an [`Injector`] on another thread) are grouped into
+//! immutable blocks of up to 2048 items. Workers claim blocks in order, match them with the
+//! current [`Matcher`] and store each block's sorted run. A [`Snapshot`] merges the completed
+//! runs with [`crate::k_merge`], so the matches always cover a consistent set of items.
+//! Changing the patterns or config rematches every block from the start.
+
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::thread::{self, JoinHandle};
+
+use crate::k_merge::k_merge_matches_by_sort;
+use crate::matcher::CHUNK_SIZE;
+use crate::{Config, Match, Matcher, Pattern};
+
+/// Appendable list of haystacks, matched in the background as items arrive.
+///
+/// ```
+/// use frizbee::{Config, Corpus, Matcher};
+///
+/// let corpus = Corpus::new(Matcher::new("fbr", &Config::default()), 2);
+/// let injector = corpus.injector();
+/// std::thread::spawn(move || {
+///     injector.extend(["fooBar", "foo_bar", "prelude"]);
+/// })
+/// .join()
+/// .unwrap();
+///
+/// let snapshot = corpus.wait();
+/// assert_eq!(snapshot.item_count(), 3);
+/// assert_eq!(snapshot.matches().len(), 2);
+/// ```
+pub struct Corpus<T: AsRef<str> + Send + Sync + 'static> {
+    shared: Arc<Shared<T>>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+/// Cloneable handle for pushing items into a [`Corpus`] from other threads
+pub struct Injector<T: AsRef<str> + Send + Sync + 'static> {
+    shared: Arc<Shared<T>>,
+}
+
+/// Matches of a [`Corpus`] at a point in time, ordered according to [`Config::sort`]
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    matches: Arc<Vec<Match>>,
+    item_count: usize,
+    matched_item_count: usize,
+}
+
+impl Snapshot {
+    /// Matches of the items matched so far. Indices refer to the order items were pushed in
+    pub fn matches(&self) -> &[Match] {
+        &self.matches
+    }
+
+    /// Number of items pushed into the corpus
+    pub fn item_count(&self) -> usize {
+        self.item_count
+    }
+
+    /// Number of items covered by [`Snapshot::matches`]. Blocks may complete out of order, so
+    /// these are not necessarily the first items pushed
+    pub fn matched_item_count(&self) -> usize {
+        self.matched_item_count
+    }
+
+    /// Whether every pushed item has been matched
+    pub fn is_complete(&self) -> bool {
+        self.matched_item_count == self.item_count
+    }
+}
+
+struct Shared<T> {
+    state: Mutex<State<T>>,
+    /// Signalled when work is available or the workers should shut down
+    work: Condvar,
+    /// Signalled when a block finishes matching
+    progress: Condvar,
+}
+
+struct State<T> {
+    matcher: Matcher,
+    /// Incremented whenever the matcher changes, so that stale runs can be discarded
+    generation: u64,
+    /// Items not yet grouped into a block
+    pending: Vec<T>,
+    /// Immutable blocks of items, in push order
+    blocks: Vec<Block<T>>,
+    /// Next block to match with the current matcher
+    next_block: usize,
+    /// Blocks claimed by workers but not yet completed
+    in_flight: usize,
+    /// Sorted runs of matches, one per block completed since the last merge
+    runs: Vec<Vec<Match>>,
+    matched_item_count: usize,
+    /// Matches of every block merged so far, shared with the snapshots taken since
+    merged: Option<Arc<Vec<Match>>>,
+    shutdown: bool,
+}
+
+struct Block<T> {
+    start: usize,
+    items: Arc<[T]>,
+}
+
+impl<T> State<T> {
+    fn new(matcher: Matcher) -> Self {
+        Self {
+            matcher,
+            generation: 0,
+            pending: vec![],
+            blocks: vec![],
+            next_block: 0,
+            in_flight: 0,
+            runs: vec![],
+            matched_item_count: 0,
+            merged: None,
+            shutdown: false,
+        }
+    }
+
+    fn item_count(&self) -> usize {
+        self.blocks.last().map_or(0, |b| b.start + b.items.len()) + self.pending.len()
+    }
+
+    fn is_idle(&self) -> bool {
+        self.pending.is_empty() && self.next_block == self.blocks.len() && self.in_flight == 0
+    }
+
+    /// Claims the next block to match, grouping pending items into a new block when every
+    /// existing block has been claimed. While other blocks are in flight, items are only
+    /// grouped once a full block is pending, so that items pushed one at a time are batched
+    /// rather than matched and merged as many tiny blocks
+    fn claim(&mut self) -> Option<(u64, usize, Arc<[T]>)> {
+        if self.next_block == self.blocks.len() {
+            if self.pending.is_empty() || (self.pending.len() < CHUNK_SIZE && self.in_flight > 0) {
+                return None;
+            }
+            self.push_block();
+        }
+
+        let block = &self.blocks[self.next_block];
+        self.next_block += 1;
+        self.in_flight += 1;
+        Some((self.generation, block.start, block.items.clone()))
+    }
+
+    /// Groups up to a full block of pending items into a new block
+    fn push_block(&mut self) {
+        let len = self.pending.len().min(CHUNK_SIZE);
+        let start = self.item_count() - self.pending.len();
+        self.blocks.push(Block {
+            start,
+            items: self.pending.drain(..len).collect(),
+        });
+    }
+
+    fn reset(&mut self) {
+        self.generation += 1;
+        self.next_block = 0;
+        self.runs.clear();
+        self.matched_item_count = 0;
+        self.merged = None;
+    }
+}
+
+impl<T: AsRef<str> + Send + Sync + 'static> Corpus<T> {
+    /// Creates an empty corpus, matched with `matcher` on `threads` persistent worker
+    /// threads. The `threads` must be >0.
+    pub fn new(matcher: Matcher, threads: usize) -> Self {
+        assert!(threads > 0, "threads must be positive");
+
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State::new(matcher)),
+            work: Condvar::new(),
+            progress: Condvar::new(),
+        });
+        let workers = (0..threads)
+            .map(|_| {
+                let shared = shared.clone();
+                thread::spawn(move || shared.run_worker())
+            })
+            .collect();
+
+        Self { shared, workers }
+    }
+
+    /// Returns a handle for pushing items from other threads
+    pub fn injector(&self) -> Injector<T> {
+        Injector {
+            shared: self.shared.clone(),
+        }
+    }
+
+    /// Appends an item, which is matched in the background
+    pub fn push(&self, item: T) {
+        self.shared.extend([item]);
+    }
+
+    /// Appends items, which are matched in the background
+    pub fn extend(&self, items: impl IntoIterator<Item = T>) {
+        self.shared.extend(items);
+    }
+
+    /// Updates the matcher's patterns (see [`Matcher::set_patterns`]), rematching every item
+    /// when they changed
+    pub fn set_patterns(&self, patterns: &[Pattern]) {
+        self.shared.update_matcher(|matcher| {
+            let changed = matcher.query_ast().is_some() || matcher.patterns() != patterns;
+            matcher.set_patterns(patterns);
+            changed
+        });
+    }
+
+    /// Updates the matcher's config (see [`Matcher::set_config`]), rematching every item
+    /// when it changed
+    pub fn set_config(&self, config: Config) {
+        self.shared.update_matcher(|matcher| {
+            let changed = *matcher.config() != config;
+            matcher.set_config(config);
+            changed
+        });
+    }
+
+    /// Number of items pushed into the corpus
+    pub fn len(&self) -> usize {
+        self.shared.lock().item_count()
+    }
+
+    /// Whether no items have been pushed into the corpus
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Calls `f` with the item at `index`, as reported by [`Match::index`], or `None` if no
+    /// such item exists. The corpus isn't locked while `f` runs, so it may use the corpus
+    pub fn with_item<R>(&self, index: usize, f: impl FnOnce(Option<&T>) -> R) -> R {
+        let mut state = self.shared.lock();
+        if index >= state.item_count() {
+            drop(state);
+            return f(None);
+        }
+        // Pending items are grouped into blocks, so that they can be shared with `f`
+        if !state.pending.is_empty() && index >= state.item_count() - state.pending.len() {
+            while !state.pending.is_empty() {
+                state.push_block();
+            }
+            self.shared.work.notify_all();
+        }
+        let block = &state.blocks[state.blocks.partition_point(|b| b.start <= index) - 1];
+        let (items, offset) = (block.items.clone(), index - block.start);
+        drop(state);
+        f(Some(&items[offset]))
+    }
+
+    /// Returns the matches found so far without waiting for the workers
+    pub fn snapshot(&self) -> Snapshot {
+        let mut state = self.shared.lock();
+        self.shared.snapshot(&mut state)
+    }
+
+    /// Blocks until every pushed item has been matched, returning the complete matches. When
+    /// matching a block panics, its items are left out, so the snapshot isn't complete
+    pub fn wait(&self) -> Snapshot {
+        let mut state = self.shared.lock();
+        while !state.is_idle() {
+            state = self
+                .shared
+                .progress
+                .wait(state)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+        self.shared.snapshot(&mut state)
+    }
+}
+
+impl<T: AsRef<str> + Send + Sync + 'static> Drop for Corpus<T> {
+    fn drop(&mut self) {
+        self.shared.lock().shutdown = true;
+        self.shared.work.notify_all();
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl<T: AsRef<str> + Send + Sync + 'static> Injector<T> {
+    /// Appends an item, which is matched in the background
+    pub fn push(&self, item: T) {
+        self.shared.extend([item]);
+    }
+
+    /// Appends items, which are matched in the background
+    pub fn extend(&self, items: impl IntoIterator<Item = T>) {
+        self.shared.extend(items);
+    }
+}
+
+impl<T: AsRef<str> + Send + Sync + 'static> Clone for Injector<T> {
+    fn clone(&self) -> Self {
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<T: AsRef<str> + Send + Sync + 'static> Shared<T> {
+    fn lock(&self) -> MutexGuard<'_, State<T>> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn extend(&self, items: impl IntoIterator<Item = T>) {
+        let mut state = self.lock();
+        state.pending.extend(items);
+        assert!(
+            state.item_count() <= u32::MAX as usize,
+            "too many items in corpus, will overflow the u32 index"
+        );
+        drop(state);
+        self.work.notify_all();
+    }
+
+    fn update_matcher(&self, update: impl FnOnce(&mut Matcher) -> bool) {
+        let mut state = self.lock();
+        if update(&mut state.matcher) {
+            state.reset();
+            drop(state);
+            self.work.notify_all();
+        }
+    }
+
+    fn snapshot(&self, state: &mut State<T>) -> Snapshot {
+        let merged = match &state.merged {
+            Some(merged) if state.runs.is_empty() => merged.clone(),
+            _ => {
+                let mut runs = std::mem::take(&mut state.runs);
+                // The previous merge is a single sorted run, only copied if a snapshot still
+                // holds it
+                runs.extend(state.merged.take().map(Arc::unwrap_or_clone));
+                let merged = Arc::new(k_merge_matches_by_sort(runs, state.matcher.config().sort));
+                state.merged = Some(merged.clone());
+                merged
+            }
+        };
+        Snapshot {
+            matches: merged,
+            item_count: state.item_count(),
+            matched_item_count: state.matched_item_count,
+        }
+    }
+
+    fn run_worker(&self) {
+        // Each worker keeps its own matcher, refreshed whenever the generation changes
+        let mut local: Option<(u64, Matcher)> = None;
+        let mut state = self.lock();
+        loop {
+            if state.shutdown {
+                return;
+            }
+            let Some((generation, start, items)) = state.claim() else {
+                state = self
+                    .work
+                    .wait(state)
+                    .unwrap_or_else(PoisonError::into_inner);
+                continue;
+            };
+            if local.as_ref().is_none_or(|(g, _)| *g != generation) {
+                local = Some((generation, state.matcher.clone()));
+            }
+            drop(state);
+
+            // A panic while matching a block skips the block, leaving its items unmatched,
+            // rather than taking down the worker
+            let (_, matcher) = local.as_mut().unwrap();
+            let run = panic::catch_unwind(AssertUnwindSafe(|| {
+                // Matched as `&str`s, so the matcher isn't instantiated for every item type
+                let haystacks = items.iter().map(AsRef::as_ref).collect::<Vec<&str>>();
+                let mut run = vec![];
+                matcher.match_list_into(&haystacks, start as u32, &mut run);
+                matcher.sort_matches(&mut run);
+                run
+            }));
+
+            state = self.lock();
+            state.in_flight -= 1;
+            match run {
+                Ok(run) if state.generation == generation => {
+                    state.matched_item_count += items.len();
+                    state.runs.push(run);
+                }
+                Ok(_) => {}
+                // The matcher may have been left mid-update, so it's cloned again
+                Err(_) => local = None,
+            }
+            self.progress.notify_all();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SortStrategy;
+
+    fn items(count: usize) -> Vec<String> {
+        let dirs = ["corpus", "core_pipe_us", "crate", "lib"];
+        (0..count)
+            .map(|index| format!("src/{}/{index}.rs", dirs[index % dirs.len()]))
+            .collect()
+    }
+
+    #[test]
+    fn matches_items_pushed_from_other_threads() {
+        let items = items(10_000);
+        for sort in [SortStrategy::ScoreThenIndexAsc, SortStrategy::IndexDesc] {
+            let config = Config::default().sort(sort);
+            let corpus = Corpus::new(Matcher::new("corpus", &config), 3);
+
+            // A single injector keeps the push order deterministic
+            let injector = corpus.injector();
+            let pushed = items.clone();
+            thread::spawn(move || {
+                for batch in pushed.chunks(700) {
+                    injector.extend(batch.iter().cloned());
+                }
+            })
+            .join()
+            .unwrap();
+
+            let snapshot = corpus.wait();
+            assert!(snapshot.is_complete());
+            assert_eq!(snapshot.item_count(), items.len());
+            assert_eq!(
+                snapshot.matches(),
+                Matcher::new("corpus", &config).match_list(&items),
+                "sort={sort:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn rematches_when_patterns_change() {
+        let items = items(5000);
+        let config = Config::default();
+        let corpus = Corpus::new(Matcher::new("corpus", &config), 2);
+        corpus.extend(items.iter().cloned());
+        corpus.wait();
+
+        let patterns = Pattern::parse_query("corpus !pipe");
+        corpus.set_patterns(&patterns);
+        corpus.push("core_pipe_us.rs".to_string());
+        corpus.push("corpus.rs".to_string());
+
+        let mut all = items.clone();
+        all.extend(["core_pipe_us.rs".to_string(), "corpus.rs".to_string()]);
+        let snapshot = corpus.wait();
+        assert_eq!(snapshot.item_count(), all.len());
+        assert_eq!(
+            snapshot.matches(),
+            Matcher::from_patterns(&patterns, &config).match_list(&all)
+        );
+
+        corpus.set_config(config.clone().sort(SortStrategy::IndexAsc));
+        let snapshot = corpus.wait();
+        assert!(snapshot.matches().is_sorted_by_key(|m| m.index));
+    }
+
+    #[test]
+    fn snapshot_covers_matched_items() {
+        let corpus = Corpus::new(Matcher::new("corpus", &Config::default()), 1);
+        assert!(corpus.snapshot().is_complete());
+        assert!(corpus.is_empty());
+
+        corpus.extend(items(3000));
+        let snapshot = corpus.snapshot();
+        assert_eq!(snapshot.item_count(), 3000);
+        assert!(snapshot.matched_item_count() <= 3000);
+        assert!(snapshot.matches().is_sorted());
+    }
+
+    #[test]
+    fn batches_pending_items_while_blocks_are_in_flight() {
+        let mut state = State::new(Matcher::new("a", &Config::default()));
+        state.pending.push("a");
+        let (_, start, items) = state.claim().unwrap();
+        assert_eq!((start, items.len()), (0, 1));
+
+        // Items trickling in while the first block is matched wait for a full block
+        state.pending.extend(["b"; 10]);
+        assert!(state.claim().is_none());
+        state.pending.extend(["c"; CHUNK_SIZE]);
+        let (_, start, items) = state.claim().unwrap();
+        assert_eq!((start, items.len()), (1, CHUNK_SIZE));
+
+        // Once every block completes, the remainder is claimed as is
+        state.in_flight = 0;
+        let (_, start, items) = state.claim().unwrap();
+        assert_eq!((start, items.len()), (CHUNK_SIZE + 1, 10));
+        assert!(state.claim().is_none());
+    }
+
+    #[test]
+    fn workers_skip_blocks_whose_matching_panics() {
+        struct Item(&'static str);
+        impl AsRef<str> for Item {
+            fn as_ref(&self) -> &str {
+                assert_ne!(self.0, "boom", "matching panicked");
+                self.0
+            }
+        }
+
+        let corpus = Corpus::new(Matcher::new("ab", &Config::default()), 1);
+        corpus.extend([Item("ab"), Item("boom")]);
+        let snapshot = corpus.wait();
+        assert!(!snapshot.is_complete());
+        assert_eq!(snapshot.matched_item_count(), 0);
+
+        // The worker survives and keeps matching new blocks
+        corpus.extend([Item("xab"), Item("zz")]);
+        let snapshot = corpus.wait();
+        assert_eq!(snapshot.item_count(), 4);
+        assert_eq!(snapshot.matched_item_count(), 2);
+        assert_eq!(snapshot.matches().len(), 1);
+        assert_eq!(snapshot.matches()[0].index, 2);
+    }
+
+    #[test]
+    fn snapshots_share_the_merged_matches() {
+        let corpus = Corpus::new(Matcher::new("ab", &Config::default()), 1);
+        corpus.extend(["ab", "xab", "zz"]);
+        let first = corpus.wait();
+        let second = corpus.snapshot();
+        assert!(Arc::ptr_eq(&first.matches, &second.matches));
+
+        corpus.push("a_b");
+        let third = corpus.wait();
+        assert_eq!(first.matches().len(), 2);
+        assert_eq!(third.matches().len(), 3);
+    }
+
+    #[test]
+    fn with_item_reads_blocks_and_pending_items() {
+        let corpus = Corpus::new(Matcher::new("corpus", &Config::default()), 1);
+        corpus.extend(items(3000));
+        corpus.wait();
+        corpus.push("pushed".to_string());
+
+        assert_eq!(corpus.len(), 3001);
+        corpus.with_item(2049, |item| {
+            assert_eq!(item.unwrap(), "src/core_pipe_us/2049.rs")
+        });
+        corpus.with_item(3000, |item| assert!(item.is_some()));
+        corpus.with_item(3001, |item| assert!(item.is_none()));
+
+        // The corpus isn't locked while reading an item
+        corpus.with_item(0, |_| {
+            corpus.push("nested".to_string());
+            corpus.with_item(3001, |item| assert_eq!(item.unwrap(), "nested"));
+            assert_eq!(corpus.len(), 3002);
+        });
+        assert_eq!(corpus.wait().item_count(), 3002);
+    }
+}
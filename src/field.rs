@@ -0,0 +1,90 @@
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// A searchable field of an item, such as the label or detail of a completion item, matched
+/// with [`crate::Matcher::match_list_fields`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Field {
+    /// Name of the field, e.g. `label`
+    pub name: String,
+    /// Percentage applied to the field's score, e.g. 50 halves the score of matches on this
+    /// field. Defaults to 100
+    pub weight: u16,
+}
+
+impl Field {
+    /// Creates a field with a weight of 100, keeping its scores as is
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            weight: 100,
+        }
+    }
+
+    /// Sets the percentage applied to the field's score
+    pub fn weight(mut self, weight: u16) -> Self {
+        self.weight = weight;
+        self
+    }
+
+    /// Whether a pattern scoped to `name` (see [`crate::Pattern::field`]) targets this field
+    pub(crate) fn is_named(&self, name: &str) -> bool {
+        self.name.eq_ignore_ascii_case(name)
+    }
+
+    /// Applies the weight to a score from this field
+    pub(crate) fn apply_weight(&self, score: u16) -> u16 {
+        (score as u32 * self.weight as u32 / 100).min(u16::MAX as u32) as u16
+    }
+}
+
+/// Error for a pattern scoped to a field that isn't one of the matcher's fields (see
+/// [`crate::Matcher::check_fields`])
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownField {
+    /// Name of the field, as written in the pattern
+    pub field: String,
+    /// Names of the matcher's fields
+    pub known: Vec<String>,
+}
+
+impl fmt::Display for UnknownField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown field `{}`", self.field)?;
+        if !self.known.is_empty() {
+            write!(f, ", expected one of: {}", self.known.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for UnknownField {}
+
+/// Error for setting more fields than [`crate::Match::field`] can index (see
+/// [`crate::Matcher::set_fields`])
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TooManyFields {
+    /// Number of fields given
+    pub count: usize,
+}
+
+impl TooManyFields {
+    /// Max number of fields of a matcher
+    pub const MAX: usize = u16::MAX as usize + 1;
+}
+
+impl fmt::Display for TooManyFields {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "too many fields, expected at most {}: {}",
+            Self::MAX,
+            self.count
+        )
+    }
+}
+
+impl std::error::Error for TooManyFields {}
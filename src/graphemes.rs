@@ -0,0 +1,124 @@
+//! Extended grapheme clusters for [`crate::UnicodeMatching::Graphemes`], where a typo skips a
+//! whole grapheme of the needle and the indices highlight whole graphemes of the haystack, so
+//! neither splits emoji with modifiers, flags or combining sequences
+
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Needle char index at which the grapheme of each needle char ends
+pub(crate) fn char_ends(needle: &str) -> Vec<usize> {
+    let mut ends = Vec::with_capacity(needle.len());
+    for grapheme in needle.graphemes(true) {
+        let end = ends.len() + grapheme.chars().count();
+        ends.resize(end, end);
+    }
+    ends
+}
+
+/// Extends the indices, in reverse order, to every byte of the haystack graphemes they touch
+pub(crate) fn expand_indices(haystack: &str, indices: &mut Vec<u32>) {
+    if indices.is_empty() {
+        return;
+    }
+    let mut expanded = Vec::with_capacity(indices.len());
+    let mut remaining = indices.iter().copied().peekable();
+    for (start, grapheme) in haystack.grapheme_indices(true).rev() {
+        let (start, end) = (start as u32, (start + grapheme.len()) as u32);
+        let mut touched = false;
+        while let Some(&index) = remaining.peek()
+            && index >= start
+        {
+            touched |= index < end;
+            remaining.next();
+        }
+        if touched {
+            expanded.extend((start..end).rev());
+        }
+        if remaining.peek().is_none() {
+            break;
+        }
+    }
+    *indices = expanded;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Config, Matcher, Matching, UnicodeMatching};
+
+    fn indices(needle: &str, haystack: &str, config: &Config) -> Option<Vec<u32>> {
+        Matcher::new(needle, config)
+            .match_one_indices(haystack, 0)
+            .map(|m| m.indices)
+    }
+
+    #[test]
+    fn ends_chars_at_grapheme_boundaries() {
+        assert_eq!(char_ends("ab"), vec![1, 2]);
+        // "e" with a combining acute accent, then a thumbs up with a skin tone modifier
+        assert_eq!(char_ends("e\u{301}👍🏽x"), vec![2, 2, 4, 4, 5]);
+        // Regional indicators pair up into flags
+        assert_eq!(char_ends("🇫🇷🇩🇪"), vec![2, 2, 4, 4]);
+    }
+
+    #[test]
+    fn expands_indices_to_whole_graphemes() {
+        let haystack = "a👍🏽e\u{301}";
+        let mut indices = vec![10, 1];
+        expand_indices(haystack, &mut indices);
+        assert_eq!(indices, vec![11, 10, 9, 8, 7, 6, 5, 4, 3, 2, 1]);
+
+        let mut indices = vec![0];
+        expand_indices(haystack, &mut indices);
+        assert_eq!(indices, vec![0]);
+    }
+
+    #[test]
+    fn typos_skip_whole_graphemes() {
+        let graphemes = Config::default()
+            .unicode(UnicodeMatching::Graphemes)
+            .max_typos(Some(1));
+        let always = graphemes.clone().unicode(UnicodeMatching::Always);
+
+        // Skipping the thumbs up and its skin tone modifier is a single typo
+        assert!(
+            Matcher::new("a👍🏽b", &graphemes)
+                .match_one("ab", 0)
+                .is_some()
+        );
+        assert!(Matcher::new("a👍🏽b", &always).match_one("ab", 0).is_none());
+        assert_eq!(
+            indices("a👍🏽b", "ab", &graphemes),
+            indices("a👍🏽b", "ab", &always.clone().max_typos(Some(2)))
+        );
+        assert!(indices("a👍🏽b", "ab", &graphemes.clone().max_typos(Some(0))).is_none());
+        // Each grapheme still costs a typo
+        assert!(
+            Matcher::new("🇫🇷🇩🇪b", &graphemes)
+                .match_one("b", 0)
+                .is_none()
+        );
+        assert!(indices("🇫🇷🇩🇪b", "b", &graphemes).is_none());
+    }
+
+    #[test]
+    fn indices_highlight_whole_graphemes() {
+        let graphemes = Config::default().unicode(UnicodeMatching::Graphemes);
+        let always = Config::default().unicode(UnicodeMatching::Always);
+
+        assert_eq!(
+            indices("👍", "x👍🏽", &graphemes),
+            Some(vec![8, 7, 6, 5, 4, 3, 2, 1])
+        );
+        assert_eq!(indices("👍", "x👍🏽", &always), Some(vec![4, 3, 2, 1]));
+
+        let substring = graphemes.clone().matching(Matching::Substring);
+        assert_eq!(
+            indices("fe", "cafe\u{301}", &substring),
+            Some(vec![5, 4, 3, 2])
+        );
+        assert_eq!(
+            indices("fe", "cafe\u{301}", &always.matching(Matching::Substring)),
+            Some(vec![3, 2])
+        );
+    }
+}
@@ -1,7 +1,7 @@
 //! Merges multiple pre-sorted runs of [`crate::Match`]es into a single sorted [`Vec`]
 //! using the k-way merge algorithm specialized for [`crate::Match`]es.
 
-use crate::Match;
+use crate::{Match, SortStrategy};
 
 /// Ordering policy for merging. Implementations must define a total order
 /// consistent with the pre-sorted runs.
@@ -78,6 +78,18 @@ pub fn k_merge_matches_by_index_desc(runs: Vec<Vec<Match>>) -> Vec<Match> {
     k_merge_matches_by::<ByIndexDesc>(runs)
 }
 
+/// Merge according to the [`SortStrategy`], as used by [`crate::Config::sort`]
+///
+/// See [`k_merge_matches_by`] for docs.
+pub fn k_merge_matches_by_sort(runs: Vec<Vec<Match>>, sort: SortStrategy) -> Vec<Match> {
+    match sort {
+        SortStrategy::ScoreThenIndexAsc => k_merge_matches_by_score_then_index_asc(runs),
+        SortStrategy::ScoreThenIndexDesc => k_merge_matches_by_score_then_index_desc(runs),
+        SortStrategy::IndexAsc => k_merge_matches_by_index_asc(runs),
+        SortStrategy::IndexDesc => k_merge_matches_by_index_desc(runs),
+    }
+}
+
 /// Merges multiple pre-sorted runs of `Match`es into a single sorted `Vec`,
 /// using the ordering policy `O`.
 ///
@@ -179,6 +191,7 @@ mod tests {
             score,
             index,
             exact: false,
+            field: 0,
             #[cfg(feature = "match_end_col")]
             end_col: 0,
         }
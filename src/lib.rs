@@ -38,10 +38,16 @@
 //! control the matching mode:
 //!
 //! ```text
-//! fuzzy  substring  prefix    suffix    exact    negated (combines with others)
-//! foo    'foo       ^foo      foo$      ^foo$    !foo
+//! fuzzy  substring  word    prefix    suffix    exact    negated (combines with others)
+//! foo    'foo       'foo'   ^foo      foo$      ^foo$    !foo
 //! ```
 //!
+//! When matching items with multiple fields (see `Matcher::match_list_fields`), a prefix naming
+//! one of the fields scopes an atom to that field, e.g. `path:^src`, while any other prefix is
+//! part of the needle, e.g. `TODO:fix`. An atom starting with `=` is
+//! matched as a glob, e.g. `=*.rs` or `=src/**/mod.rs`. With the `regex` feature,
+//! an atom starting with `%` is matched as a regex, e.g. `%fo+b[a-z]r`.
+//!
 //! ```rust
 //! use frizbee::{Config, Matcher};
 //!
@@ -68,6 +74,22 @@
 //! let matches = matcher.match_list(&haystacks);
 //! ```
 //!
+//! # Example: using boolean queries
+//!
+//! `Query::parse` extends the query syntax with parentheses for grouping, and `!(...)` for
+//! excluding haystacks matching a group. Per-pattern config applies to the query's patterns
+//! via `Query::map_patterns`.
+//!
+//! ```rust
+//! use frizbee::{Config, Matcher, Query};
+//!
+//! let haystacks = ["src/main.rs", "src/test.rs", "lib/bench.rs", "docs/index.md"];
+//! let query = Query::parse("(^src | ^lib) rs$ !(test | bench)");
+//! let mut matcher = Matcher::from_query_ast(&query, &Config::default());
+//! let matches = matcher.match_list(&haystacks);
+//! assert_eq!(matches.len(), 1);
+//! ```
+//!
 //! # Example: using explicit `Pattern`s
 //!
 //! If query syntax is not a good fit, build patterns directly and pass them to
@@ -107,19 +129,29 @@ use std::cmp::Ordering;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
+mod char_class;
 mod r#const;
+mod corpus;
+mod field;
+#[cfg(feature = "graphemes")]
+mod graphemes;
 pub mod k_merge;
 mod literal;
 mod matcher;
+mod normalize;
 mod pattern;
 mod prefilter;
+mod query;
 mod smith_waterman;
 mod sort;
 
 use r#const::*;
 
-pub use matcher::Matcher;
+pub use corpus::{Corpus, Injector, Snapshot};
+pub use field::{Field, TooManyFields, UnknownField};
+pub use matcher::{CancellationToken, Cancelled, Matcher};
 pub use pattern::{Pattern, PatternConfig};
+pub use query::Query;
 pub use sort::radix_sort_matches;
 
 /// Iterator extension for fuzzy matching
@@ -146,6 +178,9 @@ pub struct Match {
     pub index: u32,
     /// Matched the needle exactly (e.g. "foo" on "foo")
     pub exact: bool,
+    /// Index of the [`Field`] contributing the most to the score, when matching multiple
+    /// fields per item (see [`Matcher::match_list_fields`]). Otherwise, always 0
+    pub field: u16,
     /// Column position (0-based haystack byte offset) where the best alignment ends.
     /// Only populated when the `match_end_col` feature is enabled.
     #[cfg(feature = "match_end_col")]
@@ -158,6 +193,7 @@ impl Match {
             score: 0,
             index: index as u32,
             exact: false,
+            field: 0,
             #[cfg(feature = "match_end_col")]
             end_col: 0,
         }
@@ -240,7 +276,13 @@ pub struct Config {
     /// Controls how case sensitivity/insensitivity is handled while matching.
     #[cfg_attr(feature = "serde", serde(default))]
     pub casing: CaseMatching,
-    /// Controls how unicode is handled while matching.
+    /// Controls the language rules used when ignoring case (see [`CaseLocale`]). Applies to the
+    /// fuzzy, path segments and literal (exact, prefix, suffix, substring, word) matching modes,
+    /// while glob and regex matching always fold case with the Unicode rules
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub case_locale: CaseLocale,
+    /// Controls how unicode is handled while matching. In glob and regex matching, it only
+    /// decides whether matched chars are classified by their unicode category for the bonuses
     #[cfg_attr(feature = "serde", serde(default))]
     pub unicode: UnicodeMatching,
     /// Selects the matching algorithm: fuzzy (Smith-Waterman) or one of the literal modes
@@ -255,6 +297,23 @@ pub struct Config {
     /// close attention to the documentation for each property, as small changes can lead to
     /// poor matching.
     pub scoring: Scoring,
+    /// Controls how the scores of an item's fields combine, when matching multiple fields per
+    /// item (see [`Matcher::match_list_fields`])
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub field_scoring: FieldScoring,
+    /// Haystacks scoring below this are filtered out, checked against the combined score when
+    /// matching multiple patterns or fields. Haystacks too short for a single pattern to ever
+    /// reach the score, even with every bonus, are skipped before scoring.
+    pub min_score: u16,
+    /// Scores haystacks as file paths when set, preferring matches in the file name (see
+    /// [`PathMode`]). Applies to the fuzzy and literal (exact, prefix, suffix, substring, word)
+    /// matching modes
+    pub path_mode: Option<PathMode>,
+    /// Controls how diacritics are handled while matching (see [`Normalization`]). Applies to
+    /// the fuzzy, path segments and literal (exact, prefix, suffix, substring, word) matching
+    /// modes, while glob and regex matching never normalize
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub normalization: Normalization,
 }
 
 impl Default for Config {
@@ -262,10 +321,15 @@ impl Default for Config {
         Config {
             max_typos: Some(0),
             casing: CaseMatching::Smart,
+            case_locale: CaseLocale::Unicode,
             unicode: UnicodeMatching::Smart,
             matching: Matching::Fuzzy,
             sort: SortStrategy::ScoreThenIndexAsc,
             scoring: Scoring::default(),
+            field_scoring: FieldScoring::Best,
+            min_score: 0,
+            path_mode: None,
+            normalization: Normalization::Never,
         }
     }
 }
@@ -289,6 +353,12 @@ impl Config {
         self
     }
 
+    /// Sets the case locale
+    pub fn case_locale(mut self, case_locale: CaseLocale) -> Self {
+        self.case_locale = case_locale;
+        self
+    }
+
     /// Sets the unicode mode
     pub fn unicode(mut self, unicode: UnicodeMatching) -> Self {
         self.unicode = unicode;
@@ -306,6 +376,99 @@ impl Config {
         self.scoring = scoring;
         self
     }
+
+    /// Sets how the scores of an item's fields combine
+    pub fn field_scoring(mut self, field_scoring: FieldScoring) -> Self {
+        self.field_scoring = field_scoring;
+        self
+    }
+
+    /// Sets the minimum score of a match
+    pub fn min_score(mut self, min_score: u16) -> Self {
+        self.min_score = min_score;
+        self
+    }
+
+    /// Sets the path mode
+    pub fn path_mode(mut self, path_mode: Option<PathMode>) -> Self {
+        self.path_mode = path_mode;
+        self
+    }
+
+    /// Sets the normalization mode
+    pub fn normalization(mut self, normalization: Normalization) -> Self {
+        self.normalization = normalization;
+        self
+    }
+
+    /// Max bonus earned by a match in path mode, on top of the scoring's bonuses
+    pub(crate) fn max_path_bonus(&self) -> u16 {
+        self.path_mode.map_or(0, |path| path.basename_bonus)
+    }
+}
+
+/// Path-aware scoring for file pickers, similar to fzf's `--scheme=path`
+///
+/// - Chars after a path separator earn the full [`Scoring::delimiter_bonus`], while chars after
+///   any other delimiter (e.g. `_`) earn half of it
+/// - Matches entirely within the file name (the final path segment) earn the
+///   [`PathMode::basename_bonus`], which shrinks with the length of the file name, so ties
+///   break toward shorter file names
+///
+/// For example, `main` ranks `src/main.rs` above `main_tests/foo/bar.rs` and
+/// `src/main_window.rs`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct PathMode {
+    /// Treat `\` as a path separator alongside `/`, for Windows paths
+    pub backslash: bool,
+    /// Bonus for matching the needle entirely within the file name, reduced by one for each
+    /// unmatched byte of the file name, down to half of the bonus
+    pub basename_bonus: u16,
+}
+
+impl Default for PathMode {
+    fn default() -> Self {
+        PathMode {
+            backslash: false,
+            basename_bonus: BASENAME_BONUS,
+        }
+    }
+}
+
+impl PathMode {
+    /// Path separator bytes, repeating `/` when backslashes are not separators
+    #[inline(always)]
+    pub(crate) fn separators(&self) -> (u8, u8) {
+        match self.backslash {
+            true => (b'/', b'\\'),
+            false => (b'/', b'/'),
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn is_separator(&self, byte: u8) -> bool {
+        byte == b'/' || (self.backslash && byte == b'\\')
+    }
+
+    /// Byte offset of the file name in the haystack, after the last path separator
+    #[inline(always)]
+    pub(crate) fn basename_start(&self, haystack: &[u8]) -> usize {
+        haystack
+            .iter()
+            .rposition(|&byte| self.is_separator(byte))
+            .map_or(0, |pos| pos + 1)
+    }
+
+    /// Bonus for a needle of `needle_len` bytes matched within a file name of `basename_len`
+    /// bytes
+    #[inline(always)]
+    pub(crate) fn basename_bonus_for(&self, basename_len: usize, needle_len: usize) -> u16 {
+        let unmatched = basename_len.saturating_sub(needle_len);
+        let penalty = unmatched.min((self.basename_bonus / 2) as usize) as u16;
+        self.basename_bonus - penalty
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
@@ -353,12 +516,25 @@ impl SortStrategy {
     }
 }
 
+/// How the scores of an item's fields combine into the item's score, for each pattern
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum FieldScoring {
+    /// Use the highest weighted score of any field
+    #[default]
+    Best,
+    /// Sum the weighted scores of every matching field
+    WeightedSum,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum CaseMatching {
-    /// Ignore case while matching.
+    /// Ignore case while matching. Chars whose case can't be flipped one to one are matched by
+    /// their full case folding, e.g. "strasse" matches "Straße"
     Ignore,
-    /// Ignore case unless the needle contains uppercase
+    /// Ignore case unless the needle contains uppercase, flipping the case of each char without
+    /// the full case folding of [`CaseMatching::Ignore`]
     #[default]
     Smart,
     /// Require matching bytes to have the same case
@@ -376,6 +552,19 @@ impl CaseMatching {
     }
 }
 
+/// Language rules for matching case-insensitively
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum CaseLocale {
+    /// Locale-independent Unicode case folding, where "i" and "I" are the same letter
+    #[default]
+    Unicode,
+    /// Turkish and Azeri case folding, where the dotted "i" pairs with "İ" and the dotless "ı"
+    /// with "I". Haystacks containing any of them are copied while matching, with full case
+    /// folding even for smart case (see [`CaseMatching::Ignore`])
+    Turkic,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum UnicodeMatching {
@@ -387,6 +576,11 @@ pub enum UnicodeMatching {
     /// Always use expensive unicode Smith Waterman for correctness across
     /// multi-byte unicode chars in the haystack
     Always,
+    /// Like [`UnicodeMatching::Always`], but a typo skips a whole extended grapheme cluster of
+    /// the needle (e.g. "👍🏽" or "🇫🇷") rather than one of its chars, and the indices highlight
+    /// every byte of each grapheme they touch (requires the `graphemes` feature)
+    #[cfg(feature = "graphemes")]
+    Graphemes,
 }
 
 impl UnicodeMatching {
@@ -396,8 +590,31 @@ impl UnicodeMatching {
             UnicodeMatching::Ignore => false,
             UnicodeMatching::Smart => !needle.is_ascii(),
             UnicodeMatching::Always => true,
+            #[cfg(feature = "graphemes")]
+            UnicodeMatching::Graphemes => true,
         }
     }
+
+    #[inline(always)]
+    pub(crate) fn is_graphemes(self) -> bool {
+        #[cfg(feature = "graphemes")]
+        return matches!(self, UnicodeMatching::Graphemes);
+        #[cfg(not(feature = "graphemes"))]
+        false
+    }
+}
+
+/// Controls how diacritics are handled while matching
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Normalization {
+    /// Match chars with diacritics only against themselves
+    #[default]
+    Never,
+    /// Strip the diacritics of Latin, Greek and Cyrillic letters in both the needle and the
+    /// haystack, by decomposing them (NFD) and dropping the combining marks, so "cafe" matches
+    /// "Café.md". Indices still point into the original haystack
+    Diacritics,
 }
 
 /// Selects the matching algorithm
@@ -407,6 +624,13 @@ impl UnicodeMatching {
 /// [`Matching::Prefix`] matches the haystack if it starts with the needle
 /// [`Matching::Suffix`] matches the haystack if it ends with the needle
 /// [`Matching::Substring`] matches the haystack if it contains the needle
+/// [`Matching::Word`] matches the haystack if it contains the needle between word boundaries
+/// [`Matching::Initials`] matches the haystack if each needle char starts one of its words
+/// [`Matching::PathSegments`] matches the haystack if each `/` separated needle segment fuzzy
+/// matches one of its path segments
+/// [`Matching::Glob`] matches the haystack, or its file name, against the needle as a glob
+/// `Matching::Regex` matches the haystack if it contains a match of the needle as a regex
+/// (requires the `regex` feature)
 ///
 /// Only the [`Matching::Fuzzy`] mode supports typos
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
@@ -424,6 +648,42 @@ pub enum Matching {
     /// The needle must appear somewhere in the haystack. When it appears more than once, the
     /// highest-scoring occurrence is used, preferring earlier matches on tie
     Substring,
+    /// Like [`Matching::Substring`], but the needle must start and end at word boundaries: the
+    /// start or end of the haystack, or a delimiter (see [`Scoring::delimiter_bonus`]), e.g.
+    /// `foo` matches `foo_bar` and `a/foo` but not `seafood`
+    Word,
+    /// Every needle char must match the start of a word, in order, e.g. `gtd` matches
+    /// `getTypeDefinition` and `go_to_definition`. A word starts at the start of the haystack,
+    /// at an uppercase letter after a lowercase letter, or after a delimiter. Each matched char
+    /// earns both the delimiter and capitalization bonuses, so
+    /// combining it with fuzzy matching in a [`crate::Query::Or`] ranks initials matches above
+    /// ordinary fuzzy matches. Follows the normalization, case locale and path mode, where
+    /// matching within the file name earns the [`PathMode::basename_bonus`]
+    Initials,
+    /// The needle is split on `/` and each of its segments must fuzzy match a distinct path
+    /// segment of the haystack, in order, e.g. `s/m/lib` matches `src/matcher/lib.rs`. Path
+    /// segments may be skipped, but those skipped between two matched segments cost the gap
+    /// open penalty. Each segment is scored like a [`Matching::Fuzzy`] match of the path
+    /// segment, without typos but with the same normalization, case and unicode settings, and
+    /// the haystack's score is their sum. Path segments are split on `/`, and `\` when
+    /// [`PathMode::backslash`] is set
+    PathSegments,
+    /// The needle is a glob which must match the whole haystack, where `*` matches any chars
+    /// apart from `/`, `**` matches any chars, `**/` matches any leading directories, `?`
+    /// matches a char apart from `/`, and `[a-z]` or `[!a-z]` matches a char in (or not in) the
+    /// class. A glob without a `/` matches the file name at any depth, e.g. `*.rs` matches
+    /// `src/main.rs`. Each char matched by a literal segment is scored like a
+    /// [`Matching::Substring`] match, while wildcards don't score. Diacritics are never
+    /// normalized, and case is folded with the Unicode rules regardless of [`CaseLocale`]
+    Glob,
+    /// The needle is a regular expression (see the [`regex`](https://docs.rs/regex) crate's
+    /// syntax) that must match somewhere in the haystack. Each matched char is scored like a
+    /// [`Matching::Substring`] match, using the highest-scoring match starting at any char, even
+    /// when it overlaps another. A needle that isn't a valid regex is matched literally instead.
+    /// Diacritics are never normalized, and case is folded with the Unicode rules regardless of
+    /// [`CaseLocale`]
+    #[cfg(feature = "regex")]
+    Regex,
 }
 
 impl Matching {
@@ -431,6 +691,29 @@ impl Matching {
     pub(crate) fn is_fuzzy(self) -> bool {
         matches!(self, Matching::Fuzzy)
     }
+
+    #[inline(always)]
+    pub(crate) fn is_initials(self) -> bool {
+        matches!(self, Matching::Initials)
+    }
+
+    #[inline(always)]
+    pub(crate) fn is_path_segments(self) -> bool {
+        matches!(self, Matching::PathSegments)
+    }
+
+    #[inline(always)]
+    pub(crate) fn is_glob(self) -> bool {
+        matches!(self, Matching::Glob)
+    }
+
+    #[inline(always)]
+    pub(crate) fn is_regex(self) -> bool {
+        #[cfg(feature = "regex")]
+        return matches!(self, Matching::Regex);
+        #[cfg(not(feature = "regex"))]
+        false
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -448,16 +731,36 @@ pub struct Scoring {
 
     /// Bonus for matching the first character of the haystack (e.g. "h" on "hello_world")
     pub prefix_bonus: u16,
+    /// Decay per byte of the prefix bonus for matches starting near, but not at, the start of
+    /// the haystack. A match whose first char is at byte `n` earns
+    /// `prefix_bonus - n * prefix_bonus_decay` (e.g. with a decay of 2, "foo" earns 10 on "xfoo"
+    /// and 4 on "xxxxfoo"). Disabled when 0, so only matches at the start earn the prefix bonus
+    pub prefix_bonus_decay: u16,
     /// Bonus for matching a capital letter after a lowercase letter
-    /// (e.g. "b" on "fooBar" will receive a bonus on "B")
+    /// (e.g. "b" on "fooBar" will receive a bonus on "B"). The unicode path applies it to the
+    /// letters of every script (e.g. "É" on "fooÉtat")
     pub capitalization_bonus: u16,
     /// Bonus for matching the case of the needle (e.g. "WorLd" on "WoRld" will receive a bonus on "W", "o", "d")
     pub matching_case_bonus: u16,
     /// Bonus for matching the exact needle (e.g. "foo" on "foo" will receive the bonus)
     pub exact_match_bonus: u16,
     /// Bonus for matching _after_ a delimiter character (e.g. "hw" on "hello_world",
-    /// will give a bonus on "w") if "_" is included in the delimiters string
+    /// will give a bonus on "w"). Every ASCII char that isn't a letter or digit is a delimiter,
+    /// unless [`Scoring::delimiters`] is set. The unicode path also treats every non-ASCII char
+    /// that isn't a letter, number or combining mark as a delimiter (e.g. "—" or "、")
     pub delimiter_bonus: u16,
+    /// Delimiter chars with the bonus for matching _after_ each of them, replacing the default
+    /// delimiters and [`Scoring::delimiter_bonus`]. For example,
+    /// `Some(vec![(b'/', 8), (b'_', 4), (b'-', 2)])` makes `/` outweigh `-`, while `.` is no
+    /// longer a delimiter, so `foo.bar` doesn't split into words. Delimiters should be ASCII.
+    /// [`PathMode`] doesn't rescale these bonuses
+    pub delimiters: Option<Vec<(u8, u16)>>,
+    /// Bonus for matching a char immediately after the previous matched char (e.g. "o" and "b"
+    /// in "foobar" with "fob" won't receive it, but "oob" will). Chars in a run of consecutive
+    /// matches also inherit the delimiter or capitalization bonus of the char starting the run,
+    /// and each receives the largest of its own bonus, this bonus and the inherited one.
+    /// Disabled when 0
+    pub consecutive_bonus: u16,
 }
 
 impl Default for Scoring {
@@ -469,10 +772,13 @@ impl Default for Scoring {
             gap_extend_penalty: GAP_EXTEND_PENALTY,
 
             prefix_bonus: PREFIX_BONUS,
+            prefix_bonus_decay: 0,
             capitalization_bonus: CAPITALIZATION_BONUS,
             matching_case_bonus: MATCHING_CASE_BONUS,
             exact_match_bonus: EXACT_MATCH_BONUS,
             delimiter_bonus: DELIMITER_BONUS,
+            delimiters: None,
+            consecutive_bonus: 0,
         }
     }
 }
@@ -486,20 +792,135 @@ impl Scoring {
 
     /// Max additional score that a needle character can receive, aside from the match score
     pub(crate) fn max_per_char_bonus(&self) -> u16 {
-        let bonus = self.delimiter_bonus.max(self.capitalization_bonus);
-        let amortized = bonus
-            .div_ceil(2)
-            .max(bonus.saturating_sub(self.gap_open_penalty));
-        amortized.saturating_add(self.matching_case_bonus)
+        self.amortized_char_bonus()
+            .saturating_add(self.matching_case_bonus)
     }
 
     /// Max bonus given to a score one time, aside from the prefix or exact bonuses
     pub(crate) fn max_one_time_bonus(&self) -> u16 {
-        let bonus = self.delimiter_bonus.max(self.capitalization_bonus);
-        let amortized = bonus
+        self.max_char_bonus() - self.amortized_char_bonus()
+    }
+
+    /// Largest delimiter, capitalization or consecutive bonus a single char can receive
+    fn max_char_bonus(&self) -> u16 {
+        self.max_delimiter_bonus()
+            .max(self.capitalization_bonus)
+            .max(self.consecutive_bonus)
+    }
+
+    /// Share of [`Self::max_char_bonus`] that every char of the needle can receive. Without the
+    /// consecutive bonus, two bonus chars must be separated by a gap, so at most half of them or
+    /// the bonus minus the gap open penalty. Runs of consecutive matches inherit the bonus
+    /// of the run start, so every char can receive the full bonus
+    fn amortized_char_bonus(&self) -> u16 {
+        let bonus = self.max_char_bonus();
+        if self.consecutive_bonus > 0 {
+            return bonus;
+        }
+        bonus
             .div_ceil(2)
-            .max(bonus.saturating_sub(self.gap_open_penalty));
-        bonus - amortized
+            .max(bonus.saturating_sub(self.gap_open_penalty))
+    }
+
+    /// Upper bound on the score of a needle of `needle_len` bytes under any matching mode,
+    /// where every char earns the largest bonus alongside the prefix and exact bonuses
+    pub(crate) fn max_score(&self, needle_len: usize) -> u16 {
+        (self.max_score_per_char() as usize)
+            .saturating_mul(needle_len)
+            .saturating_add(self.prefix_bonus as usize)
+            .saturating_add(self.exact_match_bonus as usize)
+            .min(u16::MAX as usize) as u16
+    }
+
+    /// Min haystack length in bytes that could reach `min_score`. Each matched char is at least
+    /// one byte and scores at most [`Self::max_score_per_char`], alongside the prefix and exact
+    /// bonuses, so shorter haystacks can never reach it.
+    pub(crate) fn min_haystack_len_for_score(&self, min_score: u16) -> usize {
+        let remaining = min_score
+            .saturating_sub(self.prefix_bonus)
+            .saturating_sub(self.exact_match_bonus);
+        match self.max_score_per_char() {
+            _ if remaining == 0 => 0,
+            0 => usize::MAX,
+            max_per_char => remaining.div_ceil(max_per_char) as usize,
+        }
+    }
+
+    /// Max score of a single matched char, with the largest bonus and the matching case bonus
+    pub(crate) fn max_score_per_char(&self) -> u16 {
+        self.match_score
+            .saturating_add(self.max_char_bonus())
+            .saturating_add(self.matching_case_bonus)
+    }
+
+    /// Prefix bonus for a match whose first char is at byte `pos` of the haystack, decaying by
+    /// [`Self::prefix_bonus_decay`] for each byte after the start
+    #[inline(always)]
+    pub(crate) fn prefix_bonus_at(&self, pos: usize) -> u16 {
+        match (pos, self.prefix_bonus_decay) {
+            (0, _) => self.prefix_bonus,
+            (_, 0) => 0,
+            (pos, decay) => {
+                let pos = pos.min(u16::MAX as usize) as u16;
+                self.prefix_bonus.saturating_sub(decay.saturating_mul(pos))
+            }
+        }
+    }
+
+    /// Largest bonus for matching after a delimiter
+    pub(crate) fn max_delimiter_bonus(&self) -> u16 {
+        match &self.delimiters {
+            Some(delimiters) => delimiters
+                .iter()
+                .map(|&(_, bonus)| bonus)
+                .max()
+                .unwrap_or(0),
+            None => self.delimiter_bonus,
+        }
+    }
+
+    /// Whether the byte is a delimiter: any ASCII byte that isn't a letter or digit, or one of
+    /// [`Self::delimiters`] when set
+    #[inline(always)]
+    pub(crate) fn is_delimiter(&self, byte: u8) -> bool {
+        match &self.delimiters {
+            Some(delimiters) => delimiters.iter().any(|&(delimiter, _)| delimiter == byte),
+            None => byte.is_ascii() && !byte.is_ascii_alphanumeric(),
+        }
+    }
+
+    /// Bonus for matching a non-delimiter char after the delimiter `prev`. When a delimiter is
+    /// listed more than once in [`Self::delimiters`], its largest bonus is used
+    #[inline(always)]
+    pub(crate) fn delimiter_bonus_after(&self, prev: u8, path_mode: Option<&PathMode>) -> u16 {
+        match &self.delimiters {
+            Some(delimiters) => delimiters
+                .iter()
+                .filter(|&&(delimiter, _)| delimiter == prev)
+                .map(|&(_, bonus)| bonus)
+                .max()
+                .unwrap_or(0),
+            None => {
+                let (delimiter_bonus, separator_bonus) = self.delimiter_bonuses(path_mode);
+                match path_mode.is_some_and(|path| path.is_separator(prev)) {
+                    true => delimiter_bonus + separator_bonus,
+                    false => delimiter_bonus,
+                }
+            }
+        }
+    }
+
+    /// Delimiter bonus for a char after any delimiter, and the additional bonus when the delimiter
+    /// is a path separator. In path mode, only path separators earn the full delimiter bonus
+    #[inline(always)]
+    pub(crate) fn delimiter_bonuses(&self, path_mode: Option<&PathMode>) -> (u16, u16) {
+        match path_mode {
+            Some(_) => {
+                let half = self.delimiter_bonus / 2;
+                (half, self.delimiter_bonus - half)
+            }
+            None => (self.delimiter_bonus, 0),
+        }
     }
 
     /// Panics if a needle of `needle_len` bytes could overflow the `u16` score
@@ -1,16 +1,22 @@
 use super::rank::rare_byte_offsets;
+use crate::char_class::CharClass;
+use crate::normalize::Normalizer;
 use crate::prefilter::algo::load_window;
 use crate::prefilter::backend::{Backend, BitMaskOps};
 use crate::prefilter::{UnicodeChar, case_needle, case_needle_unicode};
-use crate::{Config, Match, MatchIndices, Matching, Scoring};
+use crate::{Config, Match, MatchIndices, Matching, PathMode, Scoring};
 
-/// Literal matching: exact / prefix / suffix / substring
+/// Literal matching: exact / prefix / suffix / substring / word
 /// Specialized for one SIMD [`crate::prefilter::backend::Backend`] supporting both ASCII and Unicode
 /// Identical scoring to Smith-Waterman
 #[derive(Debug, Clone)]
 pub(crate) struct LiteralImpl<B: Backend> {
     mode: Matching,
     scoring: Scoring,
+    path_mode: Option<PathMode>,
+    normalizer: Normalizer,
+    /// Matches scoring below this are filtered out
+    min_score: u16,
     needle_len: usize,
     /// Per-byte `(original, opposite-case)` bytes for ASCII case-insensitive matching
     needle_ascii: Vec<(u8, u8)>,
@@ -31,9 +37,14 @@ impl<B: Backend> LiteralImpl<B> {
     /// The backend's target features must be enabled.
     #[inline(always)]
     pub(crate) unsafe fn new(needle: &str, config: &Config) -> Self {
-        Self::guard_against_score_overflow(needle.len(), &config.scoring);
-
         let case_sensitive = config.casing.respects_case_for(needle);
+        // Normalizing is idempotent, so this only changes the needles of the glob and regex
+        // seeds, which aren't normalized when compiling the pattern
+        let normalizer = Normalizer::for_config(config, needle);
+        let needle = normalizer.normalize_needle(needle);
+        let needle = needle.as_ref();
+        Self::guard_against_score_overflow(needle.len(), config);
+
         let unicode = config.unicode.respects_unicode_for(needle);
         let needle_ascii = case_needle(needle.as_bytes(), case_sensitive);
         let needle_unicode = case_needle_unicode(needle, case_sensitive);
@@ -67,6 +78,9 @@ impl<B: Backend> LiteralImpl<B> {
         Self {
             mode: config.matching,
             scoring: config.scoring.clone(),
+            path_mode: config.path_mode,
+            normalizer,
+            min_score: config.min_score,
             needle_len: needle.len(),
             needle_ascii,
             needle_unicode,
@@ -77,6 +91,11 @@ impl<B: Backend> LiteralImpl<B> {
         }
     }
 
+    /// Updates the score below which matches are filtered out
+    pub(crate) fn set_min_score(&mut self, min_score: u16) {
+        self.min_score = min_score;
+    }
+
     pub(crate) fn is_available() -> bool {
         B::is_available()
     }
@@ -102,16 +121,23 @@ impl<B: Backend> LiteralImpl<B> {
         index: u32,
     ) -> Option<Match> {
         let haystack = haystack.as_ref().as_bytes();
+        let normalized = self.normalizer.normalize_haystack(haystack);
+        let haystack = normalized.as_ref().map_or(haystack, |h| h.as_bytes());
         let (pos, score) = unsafe { self.find::<UNICODE>(haystack) }?;
         let exact = pos == 0 && self.needle_len == haystack.len();
+        #[cfg(feature = "match_end_col")]
+        let end_col = (pos + self.needle_len).saturating_sub(1) as u32;
+        #[cfg(feature = "match_end_col")]
+        let end_col = normalized
+            .as_ref()
+            .map_or(end_col, |h| h.original_index(end_col));
         Some(Match {
             index,
             score,
             exact,
+            field: 0,
             #[cfg(feature = "match_end_col")]
-            end_col: (pos + self.needle_len)
-                .saturating_sub(1)
-                .min(u16::MAX as usize) as u16,
+            end_col: end_col.min(u16::MAX as u32) as u16,
         })
     }
 
@@ -137,15 +163,20 @@ impl<B: Backend> LiteralImpl<B> {
         haystack: H,
         index: u32,
     ) -> Option<MatchIndices> {
-        let haystack = haystack.as_ref().as_bytes();
+        let original = haystack.as_ref().as_bytes();
+        let normalized = self.normalizer.normalize_haystack(original);
+        let haystack = normalized.as_ref().map_or(original, |h| h.as_bytes());
         let (pos, score) = unsafe { self.find::<UNICODE>(haystack) }?;
         let exact = pos == 0 && self.needle_len == haystack.len();
         // Every byte of the matched run is a matched index, but add in reverse order to match
         // the fuzzy matcher implementation
-        let indices = (pos..pos + self.needle_len)
+        let mut indices = (pos..pos + self.needle_len)
             .rev()
             .map(|i| i as u32)
             .collect();
+        if let Some(normalized) = &normalized {
+            normalized.original_indices(original, &mut indices);
+        }
         Some(MatchIndices {
             index,
             score,
@@ -177,80 +208,118 @@ impl<B: Backend> LiteralImpl<B> {
         true
     }
 
-    /// Score contribution of a single matched scalar whose start byte is at haystack index `start`.
-    /// `matched_exact_case` is true when the haystack scalar equals the needle's original case.
+    /// Scores a contiguous match at byte `pos`, summing one [`score_scalar`] per needle
+    /// scalar: a byte on the ASCII path or a codepoint on the unicode path. Every scalar after
+    /// the first continues the run of consecutive matches. In path mode, a match at or after
+    /// `basename_start` earns the basename bonus
     #[inline(always)]
-    fn score_scalar(&self, haystack: &[u8], start: usize, matched_exact_case: bool) -> u16 {
-        let s = &self.scoring;
-        let mut score = s.match_score;
-        if matched_exact_case {
-            score += s.matching_case_bonus;
-        }
-        if start == 0 {
-            score += s.prefix_bonus;
-        } else {
-            let byte = haystack[start];
-            let prev = haystack[start - 1];
-            if byte.is_ascii_uppercase() && prev.is_ascii_lowercase() {
-                score += s.capitalization_bonus;
-            }
-            if is_delimiter(prev) && !is_delimiter(byte) {
-                score += s.delimiter_bonus;
-            }
-        }
-        score
-    }
-
-    /// Scores a contiguous match at byte `pos`, summing one [`Self::score_scalar`] per needle
-    /// scalar: a byte on the ASCII path or a codepoint on the unicode path
-    #[inline(always)]
-    fn score_at<const UNICODE: bool>(&self, haystack: &[u8], pos: usize) -> u16 {
+    fn score_at<const UNICODE: bool>(
+        &self,
+        haystack: &[u8],
+        pos: usize,
+        basename_start: usize,
+    ) -> u16 {
+        let path_mode = self.path_mode.as_ref();
         let mut score = 0u16;
+        let mut run_bonus = None;
         if UNICODE {
             let mut start = pos;
             for c in &self.needle_unicode {
                 let matched_exact_case = haystack[start..start + c.len] == c.chars[..c.len];
-                score += self.score_scalar(haystack, start, matched_exact_case);
+                score += score_scalar::<true>(
+                    &self.scoring,
+                    path_mode,
+                    haystack,
+                    start,
+                    matched_exact_case,
+                    &mut run_bonus,
+                );
                 start += c.len;
             }
         } else {
             for (k, &(orig, _)) in self.needle_ascii.iter().enumerate() {
                 let start = pos + k;
-                score += self.score_scalar(haystack, start, haystack[start] == orig);
+                score += score_scalar::<false>(
+                    &self.scoring,
+                    path_mode,
+                    haystack,
+                    start,
+                    haystack[start] == orig,
+                    &mut run_bonus,
+                );
             }
         }
 
+        if pos > 0 {
+            score += self.scoring.prefix_bonus_at(pos);
+        }
         if pos == 0 && self.needle_len == haystack.len() {
             score += self.scoring.exact_match_bonus;
         }
+        if let Some(path_mode) = path_mode.filter(|_| pos >= basename_start) {
+            score += path_mode.basename_bonus_for(haystack.len() - basename_start, self.needle_len);
+        }
         score
     }
 
     /// Returns the matched byte position (start) if the haystack matches under the configured mode
-    /// as well as the score.
+    /// as well as the score, when it reaches the min score.
     /// For substring, it checks all positions to find the best-score, preferring earlier matches
     /// when tied.
     #[inline(always)]
-    unsafe fn find<const UNICODE: bool>(&self, haystack: &[u8]) -> Option<(usize, u16)> {
+    pub(super) unsafe fn find<const UNICODE: bool>(&self, haystack: &[u8]) -> Option<(usize, u16)> {
         let needle_len = self.needle_len;
         if haystack.len() < needle_len {
             return None;
         }
 
-        match self.mode {
+        let basename_start = self
+            .path_mode
+            .map_or(0, |path_mode| path_mode.basename_start(haystack));
+        let found = match self.mode {
             Matching::Fuzzy => unreachable!("fuzzy matching does not use the literal backend"),
             Matching::Exact => (haystack.len() == needle_len
                 && self.matches_at::<UNICODE>(haystack, 0))
-            .then(|| (0, self.score_at::<UNICODE>(haystack, 0))),
+            .then(|| (0, self.score_at::<UNICODE>(haystack, 0, basename_start))),
             Matching::Prefix => self
                 .matches_at::<UNICODE>(haystack, 0)
-                .then(|| (0, self.score_at::<UNICODE>(haystack, 0))),
+                .then(|| (0, self.score_at::<UNICODE>(haystack, 0, basename_start))),
             Matching::Suffix => {
                 let pos = haystack.len() - needle_len;
                 self.matches_at::<UNICODE>(haystack, pos)
-                    .then(|| (pos, self.score_at::<UNICODE>(haystack, pos)))
+                    .then(|| (pos, self.score_at::<UNICODE>(haystack, pos, basename_start)))
+            }
+            Matching::Substring | Matching::Word => unsafe {
+                self.find_substring::<UNICODE>(haystack, basename_start)
+            },
+            Matching::Glob => unreachable!("glob matching does not use the literal backend"),
+            Matching::Initials => {
+                unreachable!("initials matching does not use the literal backend")
+            }
+            Matching::PathSegments => {
+                unreachable!("path segments matching does not use the literal backend")
+            }
+            #[cfg(feature = "regex")]
+            Matching::Regex => unreachable!("regex matching does not use the literal backend"),
+        };
+        found.filter(|&(_, score)| score >= self.min_score)
+    }
+
+    /// Whether the match at byte `pos` starts and ends at word boundaries: the start or end of
+    /// the haystack, or a delimiter, as classified for the delimiter bonus
+    #[inline(always)]
+    fn is_word_at<const UNICODE: bool>(&self, haystack: &[u8], pos: usize) -> bool {
+        let end = pos + self.needle_len;
+        match UNICODE && self.scoring.delimiters.is_none() {
+            true => {
+                (pos == 0 || CharClass::before(haystack, pos) == CharClass::Delimiter)
+                    && (end == haystack.len()
+                        || CharClass::at(haystack, end) == CharClass::Delimiter)
+            }
+            false => {
+                (pos == 0 || self.scoring.is_delimiter(haystack[pos - 1]))
+                    && (end == haystack.len() || self.scoring.is_delimiter(haystack[end]))
             }
-            Matching::Substring => unsafe { self.find_substring::<UNICODE>(haystack) },
         }
     }
 
@@ -259,7 +328,11 @@ impl<B: Backend> LiteralImpl<B> {
     /// Scan the string for both seed bytes (the two rarest bytes from the needle), and on a match,
     /// perform a scalar scan for the rest of the needle.
     #[inline(always)]
-    unsafe fn find_substring<const UNICODE: bool>(&self, haystack: &[u8]) -> Option<(usize, u16)> {
+    unsafe fn find_substring<const UNICODE: bool>(
+        &self,
+        haystack: &[u8],
+        basename_start: usize,
+    ) -> Option<(usize, u16)> {
         let len = haystack.len();
         let needle_len = self.needle_len;
         debug_assert!(needle_len > 0, "empty needles are handled by the caller");
@@ -300,8 +373,10 @@ impl<B: Backend> LiteralImpl<B> {
                 let pos = start + unsafe { B::first_hit_pos(hits) };
                 hits = hits.clear_through_lowest(hits);
                 // We've verified the seeds but we have to check the rest of the needle matches now
-                if (!UNICODE && needle_len <= 2) || self.matches_at::<UNICODE>(haystack, pos) {
-                    let score = self.score_at::<UNICODE>(haystack, pos);
+                if ((!UNICODE && needle_len <= 2) || self.matches_at::<UNICODE>(haystack, pos))
+                    && (self.mode != Matching::Word || self.is_word_at::<UNICODE>(haystack, pos))
+                {
+                    let score = self.score_at::<UNICODE>(haystack, pos, basename_start);
                     if best.is_none_or(|(_, best_score)| score > best_score) {
                         best = Some((pos, score));
                     }
@@ -313,18 +388,100 @@ impl<B: Backend> LiteralImpl<B> {
     }
 
     #[inline(always)]
-    fn guard_against_score_overflow(needle_len: usize, scoring: &Scoring) {
-        // Without gaps, a matched character earns at most one of the capitalization or delimiter
-        // bonuses, plus the case bonus, on top of `match_score`.
+    fn guard_against_score_overflow(needle_len: usize, config: &Config) {
+        // Without gaps, a matched character earns at most one of the capitalization, delimiter or
+        // consecutive bonuses, plus the case bonus, on top of `match_score`.
+        let scoring = &config.scoring;
         let max_bonus_per_char = scoring
             .capitalization_bonus
-            .max(scoring.delimiter_bonus)
+            .max(scoring.max_delimiter_bonus())
+            .max(scoring.consecutive_bonus)
             .saturating_add(scoring.matching_case_bonus);
-        scoring.guard_against_score_overflow(needle_len, max_bonus_per_char, 0);
+        scoring.guard_against_score_overflow(
+            needle_len,
+            max_bonus_per_char,
+            config.max_path_bonus(),
+        );
     }
 }
 
+/// Score contribution of a single matched scalar whose start byte is at haystack index `start`.
+/// `matched_exact_case` is true when the haystack scalar equals the needle's original case.
+///
+/// `run_bonus` holds the largest [`char_bonus`] of the run of consecutive matches ending right
+/// before the scalar, or `None` when the scalar starts a run. A scalar continuing a run receives
+/// the largest of its own bonus, [`Scoring::consecutive_bonus`] and the run's bonus, like the
+/// Smith-Waterman kernels, and `run_bonus` is updated to include the scalar
 #[inline(always)]
-fn is_delimiter(byte: u8) -> bool {
-    byte <= 127 && !byte.is_ascii_alphanumeric()
+pub(super) fn score_scalar<const UNICODE: bool>(
+    scoring: &Scoring,
+    path_mode: Option<&PathMode>,
+    haystack: &[u8],
+    start: usize,
+    matched_exact_case: bool,
+    run_bonus: &mut Option<u16>,
+) -> u16 {
+    let mut score = scoring.match_score;
+    if matched_exact_case {
+        score += scoring.matching_case_bonus;
+    }
+    if start == 0 {
+        score += scoring.prefix_bonus;
+    }
+    let char_bonus = char_bonus::<UNICODE>(scoring, path_mode, haystack, start);
+    score += char_bonus;
+    *run_bonus = match *run_bonus {
+        Some(run) if scoring.consecutive_bonus > 0 => {
+            score += scoring
+                .consecutive_bonus
+                .max(run)
+                .saturating_sub(char_bonus);
+            Some(run.max(char_bonus))
+        }
+        _ => Some(char_bonus),
+    };
+    score
+}
+
+/// Capitalization and delimiter bonuses of the scalar whose start byte is at haystack index
+/// `start`, or 0 at the start of the haystack, which earns the prefix bonus instead. The
+/// unicode path classifies the scalar and its neighbour by their general category (see
+/// [`CharClass`]) for the capitalization bonus and the default delimiters, like the unicode
+/// Smith-Waterman kernel, while the ASCII path classifies bytes
+#[inline(always)]
+pub(super) fn char_bonus<const UNICODE: bool>(
+    scoring: &Scoring,
+    path_mode: Option<&PathMode>,
+    haystack: &[u8],
+    start: usize,
+) -> u16 {
+    let mut bonus = 0;
+    if start > 0 {
+        let byte = haystack[start];
+        let prev = haystack[start - 1];
+        let (is_capitalized, is_after_delimiter) = match UNICODE {
+            true => {
+                let class = CharClass::at(haystack, start);
+                let prev_class = CharClass::before(haystack, start);
+                (
+                    class == CharClass::Upper && prev_class == CharClass::Lower,
+                    match scoring.delimiters {
+                        Some(_) => scoring.is_delimiter(prev) && !scoring.is_delimiter(byte),
+                        None => prev_class == CharClass::Delimiter && class != CharClass::Delimiter,
+                    },
+                )
+            }
+            false => (
+                byte.is_ascii_uppercase() && prev.is_ascii_lowercase(),
+                scoring.is_delimiter(prev) && !scoring.is_delimiter(byte),
+            ),
+        };
+        if is_capitalized {
+            bonus += scoring.capitalization_bonus;
+        }
+        if is_after_delimiter {
+            bonus += scoring.delimiter_bonus_after(prev, path_mode);
+        }
+    }
+    bonus
 }
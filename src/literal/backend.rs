@@ -1,8 +1,15 @@
-//! Target-feature-specific instantiations of the literal matcher, mirroring
-//! `src/matcher/backend.rs`. Each backend attaches its `#[target_feature]` to the [`Specialized`]
-//! methods, which forward to the `#[inline(always)]` helpers on [`LiteralImpl`].
+//! Target-feature-specific instantiations of the literal, glob, initials, path segments and
+//! regex matchers, mirroring `src/matcher/backend.rs`. Each backend attaches its
+//! `#[target_feature]` to the [`Specialized`] methods, which forward to the `#[inline(always)]`
+//! helpers on [`LiteralImpl`], [`GlobImpl`], [`InitialsImpl`], [`PathSegmentsImpl`] or
+//! `RegexImpl`.
 
 use super::algo::LiteralImpl;
+use super::glob::GlobImpl;
+use super::initials::InitialsImpl;
+use super::path_segments::PathSegmentsImpl;
+#[cfg(feature = "regex")]
+use super::regex::RegexImpl;
 use crate::matcher::algo::Specialized;
 use crate::{Config, Match, MatchIndices};
 
@@ -22,11 +29,53 @@ pub(crate) type LiteralSSE = LiteralImpl<PrefilterSSEBackend>;
 pub(crate) type LiteralNEON = LiteralImpl<PrefilterNEONBackend>;
 pub(crate) type LiteralScalar = LiteralImpl<PrefilterScalarBackend>;
 
-/// Implements [`Specialized`] for one literal backend. `TYPOS` is ignored (literal matching has no
-/// typo tolerance); `UNICODE` selects the byte-level ASCII path or the per-codepoint unicode path.
+#[cfg(target_arch = "x86_64")]
+pub(crate) type GlobAVX512 = GlobImpl<PrefilterAVX512Backend>;
+#[cfg(target_arch = "x86_64")]
+pub(crate) type GlobAVX = GlobImpl<PrefilterAVXBackend>;
+#[cfg(target_arch = "x86_64")]
+pub(crate) type GlobSSE = GlobImpl<PrefilterSSEBackend>;
+#[cfg(target_arch = "aarch64")]
+pub(crate) type GlobNEON = GlobImpl<PrefilterNEONBackend>;
+pub(crate) type GlobScalar = GlobImpl<PrefilterScalarBackend>;
+
+#[cfg(target_arch = "x86_64")]
+pub(crate) type InitialsAVX512 = InitialsImpl<PrefilterAVX512Backend>;
+#[cfg(target_arch = "x86_64")]
+pub(crate) type InitialsAVX = InitialsImpl<PrefilterAVXBackend>;
+#[cfg(target_arch = "x86_64")]
+pub(crate) type InitialsSSE = InitialsImpl<PrefilterSSEBackend>;
+#[cfg(target_arch = "aarch64")]
+pub(crate) type InitialsNEON = InitialsImpl<PrefilterNEONBackend>;
+pub(crate) type InitialsScalar = InitialsImpl<PrefilterScalarBackend>;
+
+#[cfg(target_arch = "x86_64")]
+pub(crate) type PathSegmentsAVX512 = PathSegmentsImpl<PrefilterAVX512Backend>;
+#[cfg(target_arch = "x86_64")]
+pub(crate) type PathSegmentsAVX = PathSegmentsImpl<PrefilterAVXBackend>;
+#[cfg(target_arch = "x86_64")]
+pub(crate) type PathSegmentsSSE = PathSegmentsImpl<PrefilterSSEBackend>;
+#[cfg(target_arch = "aarch64")]
+pub(crate) type PathSegmentsNEON = PathSegmentsImpl<PrefilterNEONBackend>;
+pub(crate) type PathSegmentsScalar = PathSegmentsImpl<PrefilterScalarBackend>;
+
+#[cfg(all(feature = "regex", target_arch = "x86_64"))]
+pub(crate) type RegexAVX512 = RegexImpl<PrefilterAVX512Backend>;
+#[cfg(all(feature = "regex", target_arch = "x86_64"))]
+pub(crate) type RegexAVX = RegexImpl<PrefilterAVXBackend>;
+#[cfg(all(feature = "regex", target_arch = "x86_64"))]
+pub(crate) type RegexSSE = RegexImpl<PrefilterSSEBackend>;
+#[cfg(all(feature = "regex", target_arch = "aarch64"))]
+pub(crate) type RegexNEON = RegexImpl<PrefilterNEONBackend>;
+#[cfg(feature = "regex")]
+pub(crate) type RegexScalar = RegexImpl<PrefilterScalarBackend>;
+
+/// Implements [`Specialized`] for one literal, glob, initials, path segments or regex backend.
+/// `TYPOS` is ignored (literal matching has no typo tolerance); `UNICODE` selects the
+/// byte-level ASCII path or the per-codepoint unicode path of [`LiteralImpl`].
 macro_rules! impl_specialized_literal {
-    ($backend:ty $(, target_feature = $feature:literal)?) => {
-        impl Specialized for LiteralImpl<$backend> {
+    ($impl:ty $(, target_feature = $feature:literal)?) => {
+        impl Specialized for $impl {
             #[inline]
             $(#[target_feature(enable = $feature)])?
             unsafe fn build(needle: &str, config: &Config) -> Self {
@@ -74,18 +123,62 @@ macro_rules! impl_specialized_literal {
     };
 }
 
+#[cfg(target_arch = "x86_64")]
+impl_specialized_literal!(LiteralAVX512, target_feature = "avx512f,avx512bw,bmi1,bmi2");
+#[cfg(target_arch = "x86_64")]
+impl_specialized_literal!(LiteralAVX, target_feature = "avx2");
+#[cfg(target_arch = "x86_64")]
+impl_specialized_literal!(LiteralSSE, target_feature = "sse2");
+#[cfg(target_arch = "aarch64")]
+impl_specialized_literal!(LiteralNEON, target_feature = "neon");
+impl_specialized_literal!(LiteralScalar);
+
+#[cfg(target_arch = "x86_64")]
+impl_specialized_literal!(GlobAVX512, target_feature = "avx512f,avx512bw,bmi1,bmi2");
+#[cfg(target_arch = "x86_64")]
+impl_specialized_literal!(GlobAVX, target_feature = "avx2");
+#[cfg(target_arch = "x86_64")]
+impl_specialized_literal!(GlobSSE, target_feature = "sse2");
+#[cfg(target_arch = "aarch64")]
+impl_specialized_literal!(GlobNEON, target_feature = "neon");
+impl_specialized_literal!(GlobScalar);
+
 #[cfg(target_arch = "x86_64")]
 impl_specialized_literal!(
-    PrefilterAVX512Backend,
+    InitialsAVX512,
     target_feature = "avx512f,avx512bw,bmi1,bmi2"
 );
 #[cfg(target_arch = "x86_64")]
-impl_specialized_literal!(PrefilterAVXBackend, target_feature = "avx2");
+impl_specialized_literal!(InitialsAVX, target_feature = "avx2");
 #[cfg(target_arch = "x86_64")]
-impl_specialized_literal!(PrefilterSSEBackend, target_feature = "sse2");
+impl_specialized_literal!(InitialsSSE, target_feature = "sse2");
 #[cfg(target_arch = "aarch64")]
-impl_specialized_literal!(PrefilterNEONBackend, target_feature = "neon");
-impl_specialized_literal!(PrefilterScalarBackend);
+impl_specialized_literal!(InitialsNEON, target_feature = "neon");
+impl_specialized_literal!(InitialsScalar);
+
+#[cfg(target_arch = "x86_64")]
+impl_specialized_literal!(
+    PathSegmentsAVX512,
+    target_feature = "avx512f,avx512bw,bmi1,bmi2"
+);
+#[cfg(target_arch = "x86_64")]
+impl_specialized_literal!(PathSegmentsAVX, target_feature = "avx2");
+#[cfg(target_arch = "x86_64")]
+impl_specialized_literal!(PathSegmentsSSE, target_feature = "sse2");
+#[cfg(target_arch = "aarch64")]
+impl_specialized_literal!(PathSegmentsNEON, target_feature = "neon");
+impl_specialized_literal!(PathSegmentsScalar);
+
+#[cfg(all(feature = "regex", target_arch = "x86_64"))]
+impl_specialized_literal!(RegexAVX512, target_feature = "avx512f,avx512bw,bmi1,bmi2");
+#[cfg(all(feature = "regex", target_arch = "x86_64"))]
+impl_specialized_literal!(RegexAVX, target_feature = "avx2");
+#[cfg(all(feature = "regex", target_arch = "x86_64"))]
+impl_specialized_literal!(RegexSSE, target_feature = "sse2");
+#[cfg(all(feature = "regex", target_arch = "aarch64"))]
+impl_specialized_literal!(RegexNEON, target_feature = "neon");
+#[cfg(feature = "regex")]
+impl_specialized_literal!(RegexScalar);
 
 #[cfg(test)]
 mod backend_parity {
@@ -93,9 +186,9 @@ mod backend_parity {
     use crate::{Config, Matching, SortStrategy};
 
     /// Runs one needle/haystack through a specialized literal backend, returning the observable
-    /// result of both `match_one` and `match_one_indices`. `UNICODE` is chosen the same way the real
-    /// dispatch chooses it (`respects_unicode_for`), so non-ASCII needles exercise the codepoint
-    /// path on every backend.
+    /// result of both `match_one` and `match_one_indices`. `UNICODE` is chosen the same way the
+    /// real dispatch chooses it (`respects_unicode_for`), so non-ASCII needles exercise the
+    /// codepoint path on every backend.
     #[allow(clippy::type_complexity)]
     unsafe fn probe<T: Specialized>(
         needle: &str,
@@ -143,8 +236,9 @@ mod backend_parity {
             ("bar", "xxxxxxxxxxxxxxxxxxxxxxxxxxxxxxbar"),
             ("ba", "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaba"),
             ("foobar", "foobatefoobarfoobar"),
-            // Unicode codepoint path with case folding: mixed-case occurrences, script mixes, and
-            // the Cherokee hybrid (E1 8E A0 / EA AD B0) that per-byte matching would wrongly accept.
+            // Unicode codepoint path with case folding: mixed-case occurrences, script mixes,
+            // and the Cherokee hybrid (E1 8E A0 / EA AD B0) that per-byte matching would wrongly
+            // accept.
             ("é", "xÉyéZÉ"),
             ("café", "un CAFÉ, deux cafés"),
             ("Ꭰ", "\u{1b70}Ꭰꭰ\u{1b70}"),
@@ -158,6 +252,7 @@ mod backend_parity {
                 Matching::Prefix,
                 Matching::Suffix,
                 Matching::Substring,
+                Matching::Word,
             ] {
                 let config = Config::default()
                     .matching(matching)
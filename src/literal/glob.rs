@@ -0,0 +1,559 @@
+use super::algo::{LiteralImpl, char_bonus, score_scalar};
+use crate::prefilter::backend::Backend;
+use crate::prefilter::opposite_case;
+use crate::{
+    CaseLocale, CaseMatching, Config, Match, MatchIndices, Matching, Normalization, Scoring,
+    UnicodeMatching,
+};
+
+/// Marks that the remaining tokens can't match the remaining haystack
+const NO_MATCH: i32 = -1;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    /// Literal char and its opposite case, or the char itself when respecting case
+    Char(char, char),
+    /// `?`, any char apart from `/`
+    Any,
+    /// `[abc]`, `[a-z]` or `[!a-z]`, any char apart from `/` in (or not in, when negated) the
+    /// inclusive ranges
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+    /// `*`, any chars apart from `/`
+    Star,
+    /// `**`, any chars
+    GlobStar,
+    /// `**/` at the start of a path segment, nothing or any chars ending with `/`
+    DirStar,
+}
+
+/// Glob matching: the needle is a glob which must match the whole haystack, or its file name
+/// when the glob has no `/`
+/// The glob's longest literal segment is scanned for before matching the glob itself
+/// Scores each char matched by a literal segment like [`LiteralImpl`], while wildcards don't score
+#[derive(Debug, Clone)]
+pub(crate) struct GlobImpl<B: Backend> {
+    tokens: Vec<Token>,
+    scoring: Scoring,
+    /// Matches scoring below this are filtered out
+    min_score: u16,
+    case_sensitive: bool,
+    /// Whether matched chars are classified by their unicode category for the bonuses, as
+    /// decided by [`Config::unicode`] for the needle
+    unicode: bool,
+    /// Number of literal chars when the glob has no wildcards apart from `**/`, in which case
+    /// a haystack with as many chars is an exact match
+    literal_len: Option<usize>,
+    /// Substring matcher for the longest literal segment of the glob, if any. Haystacks without
+    /// the segment are skipped without matching the glob
+    seed: Option<LiteralImpl<B>>,
+    /// Whether the seed uses the unicode path of [`LiteralImpl`]
+    seed_unicode: bool,
+    /// Byte offsets and chars of the current haystack
+    chars: Vec<(usize, char)>,
+    /// Best score of matching `tokens[t..]` against `chars[i..]` at `t * (chars.len() + 1) + i`,
+    /// or [`NO_MATCH`]
+    table: Vec<i32>,
+}
+
+impl<B: Backend> GlobImpl<B> {
+    /// # Safety
+    /// The backend's target features must be enabled.
+    #[inline(always)]
+    pub(crate) unsafe fn new(needle: &str, config: &Config) -> Self {
+        let case_sensitive = config.casing.respects_case_for(needle);
+        let tokens = parse_glob(needle, case_sensitive);
+
+        let literal_len = tokens
+            .iter()
+            .all(|token| matches!(token, Token::Char(..) | Token::DirStar))
+            .then(|| {
+                let chars = tokens.iter().filter(|t| matches!(t, Token::Char(..)));
+                chars.count()
+            });
+
+        // Seed on the longest run of literal chars. Both the seed and the glob flip case with
+        // `opposite_case`, so the seed never rejects a haystack the glob would match
+        let seed = tokens
+            .chunk_by(|a, b| matches!((a, b), (Token::Char(..), Token::Char(..))))
+            .filter(|run| matches!(run[0], Token::Char(..)))
+            .max_by_key(|run| run.len())
+            .map(|run| {
+                let mut seed = String::new();
+                for token in run {
+                    let Token::Char(c, _) = *token else {
+                        unreachable!()
+                    };
+                    if seed.len() + c.len_utf8() > config.scoring.max_needle_len() {
+                        break;
+                    }
+                    seed.push(c);
+                }
+                seed
+            })
+            .filter(|seed| !seed.is_empty());
+        let seed_config = Config {
+            matching: Matching::Substring,
+            casing: match case_sensitive {
+                true => CaseMatching::Respect,
+                false => CaseMatching::Ignore,
+            },
+            unicode: UnicodeMatching::Smart,
+            min_score: 0,
+            case_locale: CaseLocale::Unicode,
+            normalization: Normalization::Never,
+            ..config.clone()
+        };
+        let seed_unicode = seed.as_ref().is_some_and(|seed| !seed.is_ascii());
+        let seed = seed.map(|seed| unsafe { LiteralImpl::new(&seed, &seed_config) });
+
+        Self {
+            tokens,
+            scoring: config.scoring.clone(),
+            min_score: config.min_score,
+            case_sensitive,
+            unicode: config.unicode.respects_unicode_for(needle),
+            literal_len,
+            seed,
+            seed_unicode,
+            chars: vec![],
+            table: vec![],
+        }
+    }
+
+    /// Updates the score below which matches are filtered out
+    pub(crate) fn set_min_score(&mut self, min_score: u16) {
+        self.min_score = min_score;
+    }
+
+    pub(crate) fn is_available() -> bool {
+        B::is_available()
+    }
+
+    /// `UNICODE` is ignored, as the glob always matches whole codepoints
+    #[inline(always)]
+    pub(super) unsafe fn match_list_impl<const UNICODE: bool, H: AsRef<str>>(
+        &mut self,
+        haystacks: &[H],
+        haystack_index_offset: u32,
+        matches: &mut Vec<Match>,
+    ) {
+        for (index, haystack) in (haystack_index_offset..).zip(haystacks.iter()) {
+            if let Some(m) = unsafe { self.match_one_impl::<UNICODE, &H>(haystack, index) } {
+                matches.push(m);
+            }
+        }
+    }
+
+    #[inline(always)]
+    pub(super) unsafe fn match_one_impl<const UNICODE: bool, H: AsRef<str>>(
+        &mut self,
+        haystack: H,
+        index: u32,
+    ) -> Option<Match> {
+        let haystack = haystack.as_ref();
+        let (score, exact) = unsafe { self.find(haystack) }?;
+        Some(Match {
+            index,
+            score,
+            exact,
+            field: 0,
+            #[cfg(feature = "match_end_col")]
+            end_col: self
+                .indices()
+                .first()
+                .map_or(0, |&i| i.min(u16::MAX as u32) as u16),
+        })
+    }
+
+    #[inline(always)]
+    pub(super) unsafe fn match_list_indices_impl<const UNICODE: bool, H: AsRef<str>>(
+        &mut self,
+        haystacks: &[H],
+    ) -> Vec<MatchIndices> {
+        let mut matches = vec![];
+        for (index, haystack) in haystacks.iter().enumerate() {
+            if let Some(m) =
+                unsafe { self.match_one_indices_impl::<UNICODE, &H>(haystack, index as u32) }
+            {
+                matches.push(m);
+            }
+        }
+        matches
+    }
+
+    #[inline(always)]
+    pub(super) unsafe fn match_one_indices_impl<const UNICODE: bool, H: AsRef<str>>(
+        &mut self,
+        haystack: H,
+        index: u32,
+    ) -> Option<MatchIndices> {
+        let haystack = haystack.as_ref();
+        let (score, exact) = unsafe { self.find(haystack) }?;
+        Some(MatchIndices {
+            index,
+            score,
+            exact,
+            indices: self.indices(),
+        })
+    }
+
+    /// Matches the glob against the haystack, filling the table for [`Self::indices`].
+    /// Returns the score of the highest-scoring match and whether it's exact, when it reaches
+    /// the min score
+    #[inline(always)]
+    unsafe fn find(&mut self, haystack: &str) -> Option<(u16, bool)> {
+        if let Some(seed) = &self.seed {
+            let bytes = haystack.as_bytes();
+            match self.seed_unicode {
+                true => unsafe { seed.find::<true>(bytes) },
+                false => unsafe { seed.find::<false>(bytes) },
+            }?;
+        }
+
+        self.chars.clear();
+        self.chars.extend(haystack.char_indices());
+        let bytes = haystack.as_bytes();
+        let chars = &self.chars;
+        let n = chars.len();
+        let width = n + 1;
+        self.table.clear();
+        self.table.resize(width * (self.tokens.len() + 1), NO_MATCH);
+        // Every token matched and every char consumed
+        self.table[self.tokens.len() * width + n] = 0;
+
+        let (scoring, unicode) = (&self.scoring, self.unicode);
+        let char_bonus = |start| match unicode {
+            true => char_bonus::<true>(scoring, None, bytes, start),
+            false => char_bonus::<false>(scoring, None, bytes, start),
+        };
+        let score_char = |start, exact_case, run_bonus: &mut Option<u16>| match unicode {
+            true => score_scalar::<true>(scoring, None, bytes, start, exact_case, run_bonus),
+            false => score_scalar::<false>(scoring, None, bytes, start, exact_case, run_bonus),
+        };
+
+        // Fill from the last token and char backwards, so each cell only depends on filled cells
+        for (t, token) in self.tokens.iter().enumerate().rev() {
+            let (row, next) = self.table[t * width..].split_at_mut(width);
+            // Literal chars are consumed one after another, so a char continues the run of
+            // consecutive matches of the literal chars before it
+            let run_len = self.tokens[..t]
+                .iter()
+                .rev()
+                .take_while(|token| matches!(token, Token::Char(..)))
+                .count();
+            // Best score after consuming any chars ending with `/`, for `**/`
+            let mut after_dir = NO_MATCH;
+            for i in (0..=n).rev() {
+                let c = chars.get(i).map(|&(_, c)| c);
+                let consume = |matches: bool| match matches {
+                    true => next[i + 1],
+                    false => NO_MATCH,
+                };
+                row[i] =
+                    match token {
+                        Token::Char(orig, flipped) => match c {
+                            Some(c) if (c == *orig || c == *flipped) && next[i + 1] != NO_MATCH => {
+                                let start = chars[i].0;
+                                let mut run_bonus = i
+                                    .checked_sub(run_len)
+                                    .filter(|_| run_len > 0 && self.scoring.consecutive_bonus > 0)
+                                    .map(|run_start| {
+                                        chars[run_start..i]
+                                            .iter()
+                                            .map(|&(start, _)| char_bonus(start))
+                                            .max()
+                                            .unwrap_or(0)
+                                    });
+                                let score = score_char(start, c == *orig, &mut run_bonus);
+                                next[i + 1] + score as i32
+                            }
+                            _ => NO_MATCH,
+                        },
+                        Token::Any => consume(c.is_some_and(|c| c != '/')),
+                        Token::Class { negated, ranges } => consume(c.is_some_and(|c| {
+                            matches_class(c, *negated, ranges, self.case_sensitive)
+                        })),
+                        Token::Star => match c {
+                            Some(c) if c != '/' => next[i].max(row[i + 1]),
+                            _ => next[i],
+                        },
+                        Token::GlobStar => match c {
+                            Some(_) => next[i].max(row[i + 1]),
+                            None => next[i],
+                        },
+                        Token::DirStar => {
+                            if c == Some('/') {
+                                after_dir = after_dir.max(next[i + 1]);
+                            }
+                            next[i].max(after_dir)
+                        }
+                    };
+            }
+        }
+
+        let score = self.table[0];
+        if score == NO_MATCH {
+            return None;
+        }
+        let exact = n > 0 && self.literal_len == Some(n);
+        let mut score = score.min(u16::MAX as i32) as u16;
+        if exact {
+            score = score.saturating_add(self.scoring.exact_match_bonus);
+        }
+        (score >= self.min_score).then_some((score, exact))
+    }
+
+    /// Byte indices of the chars matched by literal segments in the highest-scoring match,
+    /// preferring earlier matches of the literal segments when tied. Must follow a successful
+    /// [`Self::find`] on the same haystack
+    fn indices(&self) -> Vec<u32> {
+        let n = self.chars.len();
+        let width = n + 1;
+        let mut indices = vec![];
+        let mut i = 0;
+        for (t, token) in self.tokens.iter().enumerate() {
+            let score = self.table[t * width + i];
+            let next = &self.table[(t + 1) * width..(t + 2) * width];
+            match token {
+                Token::Char(..) => {
+                    let (start, c) = self.chars[i];
+                    indices.extend((start..start + c.len_utf8()).map(|i| i as u32));
+                    i += 1;
+                }
+                Token::Any | Token::Class { .. } => i += 1,
+                Token::Star | Token::GlobStar | Token::DirStar => {
+                    let mut j = i;
+                    loop {
+                        let can_end = match token {
+                            Token::DirStar => j == i || self.chars[j - 1].1 == '/',
+                            _ => true,
+                        };
+                        if can_end && next[j] == score {
+                            break;
+                        }
+                        debug_assert!(j < n, "glob table has no path for the score");
+                        j += 1;
+                    }
+                    i = j;
+                }
+            }
+        }
+        debug_assert_eq!(i, n);
+
+        // Reversed to match the fuzzy matcher implementation
+        indices.reverse();
+        indices
+    }
+}
+
+fn matches_class(c: char, negated: bool, ranges: &[(char, char)], case_sensitive: bool) -> bool {
+    if c == '/' {
+        return false;
+    }
+    let flipped = opposite_case(c, case_sensitive);
+    let in_ranges = ranges
+        .iter()
+        .any(|&(start, end)| (start..=end).contains(&c) || (start..=end).contains(&flipped));
+    negated != in_ranges
+}
+
+/// Parses a glob into its tokens. Globs without a `/` match the file name at any depth, like
+/// `*.rs` in a `.gitignore`, so they start with an implicit `**/`
+fn parse_glob(glob: &str, case_sensitive: bool) -> Vec<Token> {
+    let chars = glob.chars().collect::<Vec<_>>();
+    let literal = |c: char| Token::Char(c, opposite_case(c, case_sensitive));
+
+    let mut tokens = vec![];
+    if !chars.contains(&'/') {
+        tokens.push(Token::DirStar);
+    }
+    let mut i = 0;
+    while i < chars.len() {
+        let token = match chars[i] {
+            '\\' if i + 1 < chars.len() => {
+                i += 1;
+                literal(chars[i])
+            }
+            '?' => Token::Any,
+            '*' if chars.get(i + 1) == Some(&'*') => {
+                let segment_start = i == 0 || chars[i - 1] == '/';
+                i += 1;
+                if segment_start && chars.get(i + 1) == Some(&'/') {
+                    i += 1;
+                    Token::DirStar
+                } else {
+                    Token::GlobStar
+                }
+            }
+            '*' => Token::Star,
+            '[' => match parse_class(&chars[i + 1..]) {
+                Some((class, len)) => {
+                    i += len;
+                    class
+                }
+                // An unclosed `[` is matched literally
+                None => literal('['),
+            },
+            c => literal(c),
+        };
+        tokens.push(token);
+        i += 1;
+    }
+    tokens
+}
+
+/// Parses the chars following a `[`, returning the class and the number of chars consumed,
+/// including the closing `]`
+fn parse_class(chars: &[char]) -> Option<(Token, usize)> {
+    let negated = matches!(chars.first(), Some('!' | '^'));
+    let start = negated as usize;
+    let mut ranges = vec![];
+    let mut i = start;
+    loop {
+        let &c = chars.get(i)?;
+        // A `]` right after the `[` is part of the class
+        if c == ']' && i > start {
+            break;
+        }
+        match (chars.get(i + 1), chars.get(i + 2)) {
+            (Some('-'), Some(&end)) if end != ']' => {
+                ranges.push((c, end));
+                i += 3;
+            }
+            _ => {
+                ranges.push((c, c));
+                i += 1;
+            }
+        }
+    }
+    Some((Token::Class { negated, ranges }, i + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::r#const::*;
+    use crate::{CaseMatching, Config, Matcher, Matching, Scoring, SortStrategy, UnicodeMatching};
+
+    const CHAR_SCORE: u16 = MATCH_SCORE + MATCHING_CASE_BONUS;
+
+    fn config() -> Config {
+        Config::default().sort(SortStrategy::IndexAsc)
+    }
+
+    fn indices(query: &str, config: &Config, haystacks: &[&str]) -> Vec<u32> {
+        Matcher::from_query(query, config)
+            .match_list(haystacks)
+            .iter()
+            .map(|m| m.index)
+            .collect()
+    }
+
+    const PATHS: [&str; 8] = [
+        "main.rs",
+        "src/main.rs",
+        "src/matcher/mod.rs",
+        "src/mod.rs",
+        "README.md",
+        "src/lib.rs.bak",
+        "tests/a/b/mod.rs",
+        "Cargo.toml",
+    ];
+
+    #[test]
+    fn globs_without_slash_match_file_names() {
+        assert_eq!(indices("=*.rs", &config(), &PATHS), vec![0, 1, 2, 3, 6]);
+        assert_eq!(indices("=mod.rs", &config(), &PATHS), vec![2, 3, 6]);
+        assert_eq!(indices("=????.rs", &config(), &PATHS), vec![0, 1]);
+        assert_eq!(indices("=[c-m]*.[!r]*", &config(), &PATHS), vec![5, 7]);
+    }
+
+    #[test]
+    fn globs_with_slash_match_whole_paths() {
+        assert_eq!(indices("=src/*.rs", &config(), &PATHS), vec![1, 3]);
+        assert_eq!(indices("=src/**/mod.rs", &config(), &PATHS), vec![2, 3]);
+        assert_eq!(indices("=**/mod.rs", &config(), &PATHS), vec![2, 3, 6]);
+        assert_eq!(indices("=src/**", &config(), &PATHS), vec![1, 2, 3, 5]);
+        assert_eq!(indices("=tests/**/b/*", &config(), &PATHS), vec![6]);
+    }
+
+    #[test]
+    fn globs_combine_with_other_atoms() {
+        assert_eq!(indices("=*.rs !=**/mod.rs", &config(), &PATHS), vec![0, 1]);
+        assert_eq!(indices("=src/** mn", &config(), &PATHS), vec![1]);
+        assert_eq!(indices("=*.md | =*.toml", &config(), &PATHS), vec![4, 7]);
+    }
+
+    #[test]
+    fn globs_respect_casing() {
+        let haystacks = ["README.md", "readme.md", "Readme.MD"];
+        assert_eq!(indices("=readme.*", &config(), &haystacks), vec![0, 1, 2]);
+        assert_eq!(indices("=README.*", &config(), &haystacks), vec![0]);
+        assert_eq!(
+            indices("=[r]eadme.md", &config(), &haystacks),
+            vec![0, 1, 2]
+        );
+        let respect = config().casing(CaseMatching::Respect);
+        assert_eq!(indices("=readme.*", &respect, &haystacks), vec![1]);
+    }
+
+    #[test]
+    fn escaped_and_unclosed_wildcards_are_literal() {
+        let haystacks = ["a*b", "axb", "[ab", "a"];
+        assert_eq!(indices("=a\\*b", &config(), &haystacks), vec![0]);
+        assert_eq!(indices("=[ab", &config(), &haystacks), vec![2]);
+        assert_eq!(indices("=[]a]", &config(), &haystacks), vec![3]);
+    }
+
+    #[test]
+    fn literal_segments_are_scored_and_highlighted() {
+        let matcher = |glob: &str| Matcher::new(glob, &config().matching(Matching::Glob));
+
+        let matches = matcher("src/*.rs").match_list_indices(&["src/main.rs"]);
+        assert_eq!(matches[0].indices, vec![10, 9, 8, 3, 2, 1, 0]);
+        assert_eq!(
+            matches[0].score,
+            7 * CHAR_SCORE + PREFIX_BONUS + DELIMITER_BONUS
+        );
+        assert!(!matches[0].exact);
+
+        // Unicode chars highlight each of their bytes
+        let matches = matcher("*é").match_list_indices(&["dir/café"]);
+        assert_eq!(matches[0].indices, vec![8, 7]);
+
+        // Unicode globs classify chars by their unicode category, so `É` is capitalized
+        let score = |unicode| {
+            let config = config().matching(Matching::Glob).unicode(unicode);
+            Matcher::new("*École", &config).match_list(&["fooÉcole"])[0].score
+        };
+        assert_eq!(
+            score(UnicodeMatching::Smart),
+            score(UnicodeMatching::Ignore) + CAPITALIZATION_BONUS
+        );
+
+        // Globs without wildcards are exact when matching the whole haystack
+        let matches = matcher("main.rs").match_list(&["main.rs", "src/main.rs"]);
+        assert_eq!(
+            matches[0].score,
+            7 * CHAR_SCORE + PREFIX_BONUS + DELIMITER_BONUS + EXACT_MATCH_BONUS
+        );
+        assert!(matches[0].exact);
+        assert_eq!(matches[1].score, 7 * CHAR_SCORE + 2 * DELIMITER_BONUS);
+        assert!(!matches[1].exact);
+
+        // Chars continuing a literal segment earn the consecutive bonus, while the first char
+        // after a wildcard starts a new run
+        let scoring = Scoring {
+            consecutive_bonus: 8,
+            ..Scoring::default()
+        };
+        let config = config().matching(Matching::Glob).scoring(scoring);
+        let matches = Matcher::new("src/*.rs", &config).match_list(&["src/main.rs"]);
+        assert_eq!(
+            matches[0].score,
+            7 * CHAR_SCORE + PREFIX_BONUS + DELIMITER_BONUS + 3 * 8 + (8 - DELIMITER_BONUS) + 8
+        );
+    }
+}
@@ -0,0 +1,420 @@
+use crate::normalize::{NormalizedHaystack, Normalizer};
+use crate::prefilter::algo::Prefilter;
+use crate::prefilter::backend::Backend;
+use crate::prefilter::opposite_case;
+use crate::{Config, Match, MatchIndices, PathMode, Scoring};
+
+/// Initials matching: every needle char must match the start of a word in the haystack, in
+/// order, e.g. `gtd` on `getTypeDefinition` or `go_to_definition`
+/// Haystacks that don't contain the needle in order are skipped by the fuzzy [`Prefilter`]
+/// Word starts use the same classification as the prefix, capitalization and delimiter bonuses
+/// Haystacks are normalized like [`super::LiteralImpl`], following [`Config::normalization`] and
+/// [`Config::case_locale`]
+#[derive(Debug, Clone)]
+pub(crate) struct InitialsImpl<B: Backend> {
+    /// Per-char `(original, opposite-case)` needle. Only ASCII chars are flipped on the ASCII
+    /// path, like the prefilter
+    needle: Vec<(char, char)>,
+    /// Length of the needle in bytes
+    needle_len: usize,
+    scoring: Scoring,
+    path_mode: Option<PathMode>,
+    normalizer: Normalizer,
+    /// Matches scoring below this are filtered out
+    min_score: u16,
+    prefilter: Prefilter<B>,
+}
+
+impl<B: Backend> InitialsImpl<B> {
+    /// # Safety
+    /// The backend's target features must be enabled.
+    #[inline(always)]
+    pub(crate) unsafe fn new(needle: &str, config: &Config) -> Self {
+        let case_sensitive = config.casing.respects_case_for(needle);
+        let unicode = config.unicode.respects_unicode_for(needle);
+        // Already normalized when compiling the pattern
+        let normalizer = Normalizer::for_config(config, needle);
+        let needle_chars = needle
+            .chars()
+            .map(|c| match unicode || c.is_ascii() {
+                true => (c, opposite_case(c, case_sensitive)),
+                false => (c, c),
+            })
+            .collect();
+
+        Self {
+            needle: needle_chars,
+            needle_len: needle.len(),
+            scoring: config.scoring.clone(),
+            path_mode: config.path_mode,
+            normalizer,
+            min_score: config.min_score,
+            prefilter: unsafe { Prefilter::new(needle, case_sensitive, false) },
+        }
+    }
+
+    /// Updates the score below which matches are filtered out
+    pub(crate) fn set_min_score(&mut self, min_score: u16) {
+        self.min_score = min_score;
+    }
+
+    pub(crate) fn is_available() -> bool {
+        B::is_available()
+    }
+
+    #[inline(always)]
+    pub(super) unsafe fn match_list_impl<const UNICODE: bool, H: AsRef<str>>(
+        &self,
+        haystacks: &[H],
+        haystack_index_offset: u32,
+        matches: &mut Vec<Match>,
+    ) {
+        for (index, haystack) in (haystack_index_offset..).zip(haystacks.iter()) {
+            if let Some(m) = unsafe { self.match_one_impl::<UNICODE, &H>(haystack, index) } {
+                matches.push(m);
+            }
+        }
+    }
+
+    #[inline(always)]
+    pub(super) unsafe fn match_one_impl<const UNICODE: bool, H: AsRef<str>>(
+        &self,
+        haystack: H,
+        index: u32,
+    ) -> Option<Match> {
+        let original = haystack.as_ref();
+        let normalized = self.normalizer.normalize_haystack(original.as_bytes());
+        let haystack = normalized.as_ref().map_or(original, |h| h.as_str());
+        let word_start = |pos| self.is_word_start(original.as_bytes(), normalized.as_ref(), pos);
+        let mut end = 0;
+        let (score, exact) = unsafe {
+            self.find::<UNICODE>(haystack, word_start, |start, len| end = start + len - 1)
+        }?;
+        #[cfg(feature = "match_end_col")]
+        let end = normalized
+            .as_ref()
+            .map_or(end, |h| h.original_index(end as u32) as usize);
+        #[cfg(not(feature = "match_end_col"))]
+        let _ = end;
+        Some(Match {
+            index,
+            score,
+            exact,
+            field: 0,
+            #[cfg(feature = "match_end_col")]
+            end_col: end.min(u16::MAX as usize) as u16,
+        })
+    }
+
+    #[inline(always)]
+    pub(super) unsafe fn match_list_indices_impl<const UNICODE: bool, H: AsRef<str>>(
+        &self,
+        haystacks: &[H],
+    ) -> Vec<MatchIndices> {
+        let mut matches = vec![];
+        for (index, haystack) in haystacks.iter().enumerate() {
+            if let Some(m) =
+                unsafe { self.match_one_indices_impl::<UNICODE, &H>(haystack, index as u32) }
+            {
+                matches.push(m);
+            }
+        }
+        matches
+    }
+
+    #[inline(always)]
+    pub(super) unsafe fn match_one_indices_impl<const UNICODE: bool, H: AsRef<str>>(
+        &self,
+        haystack: H,
+        index: u32,
+    ) -> Option<MatchIndices> {
+        let original = haystack.as_ref();
+        let normalized = self.normalizer.normalize_haystack(original.as_bytes());
+        let haystack = normalized.as_ref().map_or(original, |h| h.as_str());
+        let word_start = |pos| self.is_word_start(original.as_bytes(), normalized.as_ref(), pos);
+        let mut indices = vec![];
+        let (score, exact) = unsafe {
+            self.find::<UNICODE>(haystack, word_start, |start, len| {
+                indices.extend((start..start + len).map(|i| i as u32))
+            })
+        }?;
+        // Reversed to match the fuzzy matcher implementation
+        indices.reverse();
+        if let Some(normalized) = &normalized {
+            normalized.original_indices(original.as_bytes(), &mut indices);
+        }
+        Some(MatchIndices {
+            index,
+            score,
+            exact,
+            indices,
+        })
+    }
+
+    /// Matches each needle char against the earliest word start (per `word_start`) after the
+    /// previous match, calling `on_match` with the byte offset and length of each matched char.
+    /// Returns the score and whether every haystack byte matched, when it reaches the min score.
+    /// In path mode, the file name is also matched on its own with the basename bonus, keeping
+    /// the best score
+    #[inline(always)]
+    unsafe fn find<const UNICODE: bool>(
+        &self,
+        haystack: &str,
+        word_start: impl Fn(usize) -> bool + Copy,
+        on_match: impl FnMut(usize, usize),
+    ) -> Option<(u16, bool)> {
+        let bytes = haystack.as_bytes();
+        let (matched, _, _) = match UNICODE {
+            true => unsafe { self.prefilter.match_haystack_unicode(bytes) },
+            false => unsafe { self.prefilter.match_haystack(bytes) },
+        };
+        if !matched {
+            return None;
+        }
+
+        let (start, bonus) = match self.path_mode {
+            None => (0, 0),
+            Some(path_mode) => {
+                let basename_start = path_mode.basename_start(bytes);
+                let bonus =
+                    path_mode.basename_bonus_for(haystack.len() - basename_start, self.needle_len);
+                let in_basename = self.match_from(haystack, basename_start, word_start, |_, _| {});
+                let in_path = (basename_start > 0)
+                    .then(|| self.match_from(haystack, 0, word_start, |_, _| {}))
+                    .flatten();
+                match (in_basename, in_path) {
+                    (Some((score, _)), Some((path_score, _)))
+                        if path_score > score.saturating_add(bonus) =>
+                    {
+                        (0, 0)
+                    }
+                    (None, _) => (0, 0),
+                    _ => (basename_start, bonus),
+                }
+            }
+        };
+        let (score, exact) = self.match_from(haystack, start, word_start, on_match)?;
+        let score = score.saturating_add(bonus);
+        (score >= self.min_score).then_some((score, exact))
+    }
+
+    /// Matches the needle against the word starts from byte `start` onwards (see
+    /// [`Self::find`]), without the min score
+    #[inline(always)]
+    fn match_from(
+        &self,
+        haystack: &str,
+        start: usize,
+        word_start: impl Fn(usize) -> bool,
+        mut on_match: impl FnMut(usize, usize),
+    ) -> Option<(u16, bool)> {
+        let mut needle = self.needle.iter().peekable();
+        let mut score = 0u16;
+        let mut matched_len = 0;
+        for (offset, c) in haystack[start..].char_indices() {
+            let pos = start + offset;
+            let Some(&&(orig, flipped)) = needle.peek() else {
+                break;
+            };
+            if (c != orig && c != flipped) || !word_start(pos) {
+                continue;
+            }
+            needle.next();
+            score = score.saturating_add(self.score_char(pos, c == orig));
+            matched_len += c.len_utf8();
+            on_match(pos, c.len_utf8());
+        }
+        if needle.peek().is_some() {
+            return None;
+        }
+
+        let exact = matched_len == haystack.len();
+        if exact {
+            score = score.saturating_add(self.scoring.exact_match_bonus);
+        }
+        Some((score, exact))
+    }
+
+    /// Whether the char at byte `pos` of the matched haystack starts a word. Word starts of a
+    /// normalized haystack are those of the `original` haystack, so case folding doesn't hide
+    /// camel case words, and a char expanding into several chars (e.g. "ß" into "ss") only
+    /// starts a word with its first one
+    #[inline(always)]
+    fn is_word_start(
+        &self,
+        original: &[u8],
+        normalized: Option<&NormalizedHaystack>,
+        pos: usize,
+    ) -> bool {
+        let Some(normalized) = normalized else {
+            return is_word_start(&self.scoring, original, pos);
+        };
+        let original_pos = normalized.original_index(pos as u32);
+        (pos == 0 || normalized.original_index(pos as u32 - 1) != original_pos)
+            && is_word_start(&self.scoring, original, original_pos as usize)
+    }
+
+    /// Score of a needle char matched at the word start `pos`. Every word start other than
+    /// the prefix earns both the delimiter and capitalization bonuses, so that an initials match
+    /// outscores a contiguous fuzzy match of the same needle
+    #[inline(always)]
+    fn score_char(&self, pos: usize, matched_exact_case: bool) -> u16 {
+        let s = &self.scoring;
+        let mut score = s.match_score;
+        if matched_exact_case {
+            score += s.matching_case_bonus;
+        }
+        score
+            + match pos {
+                0 => s.prefix_bonus,
+                _ => s.max_delimiter_bonus() + s.capitalization_bonus,
+            }
+    }
+}
+
+/// Whether the char starting at byte `pos` starts a word: the start of the haystack, an
+/// uppercase letter after a lowercase letter, or a non-delimiter after a delimiter
+#[inline(always)]
+fn is_word_start(scoring: &Scoring, haystack: &[u8], pos: usize) -> bool {
+    if pos == 0 {
+        return true;
+    }
+    let (byte, prev) = (haystack[pos], haystack[pos - 1]);
+    (byte.is_ascii_uppercase() && prev.is_ascii_lowercase())
+        || (scoring.is_delimiter(prev) && !scoring.is_delimiter(byte))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::r#const::*;
+    use crate::{
+        CaseLocale, CaseMatching, Config, Matcher, Matching, Normalization, PathMode, Pattern,
+        PatternConfig, Query, SortStrategy,
+    };
+
+    fn config() -> Config {
+        Config::default()
+            .matching(Matching::Initials)
+            .sort(SortStrategy::IndexAsc)
+    }
+
+    fn indices(needle: &str, haystacks: &[&str]) -> Vec<u32> {
+        Matcher::new(needle, &config())
+            .match_list(haystacks)
+            .iter()
+            .map(|m| m.index)
+            .collect()
+    }
+
+    #[test]
+    fn needle_chars_match_word_starts() {
+        let haystacks = [
+            "getTypeDefinition",
+            "go_to_definition",
+            "GoToDefinition",
+            "gotdef",
+            "get-the/data.rs",
+            "getType",
+            "dgt",
+        ];
+        assert_eq!(indices("gtd", &haystacks), vec![0, 1, 2, 4]);
+        assert_eq!(indices("GTD", &haystacks), vec![2]);
+        assert_eq!(indices("td", &haystacks), vec![0, 1, 2, 4]);
+    }
+
+    #[test]
+    fn unicode_word_starts() {
+        let haystacks = ["école_données", "écoleDonnées", "éd"];
+        assert_eq!(indices("éd", &haystacks), vec![0, 1]);
+    }
+
+    #[test]
+    fn normalized_haystacks() {
+        let haystacks = ["Café Fleur", "Cafe_Fleur", "cafeFleur", "caféfleur"];
+        let config = config().normalization(Normalization::Diacritics);
+        let matches = Matcher::new("cf", &config).match_list_indices(&haystacks);
+        assert_eq!(
+            matches.iter().map(|m| m.index).collect::<Vec<_>>(),
+            vec![0, 1, 2]
+        );
+        assert_eq!(matches[0].indices, vec![6, 0]);
+
+        // Folding the case keeps the camel case words of the original haystack
+        let config = config.casing(CaseMatching::Ignore);
+        let haystacks = ["straßeEcole", "strasseEcole", "sßx"];
+        let matches = Matcher::new("se", &config).match_list_indices(&haystacks);
+        assert_eq!(
+            matches.iter().map(|m| m.index).collect::<Vec<_>>(),
+            vec![0, 1]
+        );
+        assert_eq!(matches[0].indices, vec![7, 0]);
+        // Only the first char of "ß" expanding into "ss" starts a word
+        assert!(Matcher::new("ss", &config).match_list(&["x_ß"]).is_empty());
+    }
+
+    #[test]
+    fn turkic_case_folding() {
+        let config = config().case_locale(CaseLocale::Turkic);
+        let haystacks = ["İstanbul_Ankara", "Istanbul_Ankara"];
+        assert_eq!(
+            Matcher::new("ia", &config)
+                .match_list(&haystacks)
+                .iter()
+                .map(|m| m.index)
+                .collect::<Vec<_>>(),
+            vec![0]
+        );
+    }
+
+    #[test]
+    fn path_mode_prefers_file_names() {
+        let config = config().path_mode(Some(PathMode::default()));
+        let haystacks = ["main_tests/foo/bar.rs", "src/main_window.rs"];
+        let matches = Matcher::new("mw", &config).match_list_indices(&haystacks);
+        assert_eq!(matches[0].index, 1);
+        assert_eq!(matches[0].indices, vec![9, 4]);
+        let no_path_mode = config.clone().path_mode(None);
+        let unboosted = Matcher::new("mw", &no_path_mode).match_list(&haystacks[1..2]);
+        assert!(matches[0].score > unboosted[0].score);
+        // Matches spanning path segments earn no basename bonus
+        let matches = Matcher::new("mfb", &config).match_list(&haystacks[..1]);
+        assert_eq!(
+            matches[0].score,
+            Matcher::new("mfb", &no_path_mode).match_list(&haystacks[..1])[0].score
+        );
+    }
+
+    #[test]
+    fn indices_highlight_initials() {
+        let matches = Matcher::new("gtd", &config()).match_list_indices(&["go_to_definition"]);
+        assert_eq!(matches[0].indices, vec![6, 3, 0]);
+        assert_eq!(
+            matches[0].score,
+            3 * (MATCH_SCORE + MATCHING_CASE_BONUS)
+                + PREFIX_BONUS
+                + 2 * (DELIMITER_BONUS + CAPITALIZATION_BONUS)
+        );
+    }
+
+    #[test]
+    fn initials_outrank_fuzzy_matches() {
+        // Initials and fuzzy alternatives of the same needle, keeping the best score
+        let query = Query::Or(vec![
+            Pattern::new(
+                "gtd",
+                PatternConfig::default().matching(Some(Matching::Initials)),
+            )
+            .into(),
+            Pattern::from("gtd").into(),
+        ]);
+        let haystacks = ["gtdx", "getTypeDefinition", "xgtd"];
+        let matches = Matcher::from_query_ast(&query, &Config::default()).match_list(&haystacks);
+        assert_eq!(matches[0].index, 1);
+
+        let fuzzy = Matcher::new("gtd", &Config::default()).match_list(&haystacks);
+        let initials = Matcher::new("gtd", &config()).match_list(&haystacks);
+        let fuzzy_score = fuzzy.iter().find(|m| m.index == 1).unwrap().score;
+        assert!(initials[0].score > fuzzy_score);
+        assert!(matches[0].score > fuzzy.iter().map(|m| m.score).max().unwrap());
+    }
+}
@@ -1,4 +1,5 @@
-//! Literal matching: exact / prefix / suffix / substring
+//! Literal matching: exact / prefix / suffix / substring / word, glob, initials and path
+//! segments matching, and regex matching with the `regex` feature
 //!
 //! Unlike the fuzzy [`crate::matcher`], literal matching requires the needle to appear as a
 //! *contiguous* run of characters. The only step that benefits from SIMD is finding *where*
@@ -8,14 +9,24 @@
 
 mod algo;
 mod backend;
+mod glob;
+mod initials;
+mod path_segments;
 mod rank;
+#[cfg(feature = "regex")]
+mod regex;
 
 pub(crate) use backend::*;
+#[cfg(feature = "regex")]
+pub(crate) use regex::regex_match_len;
 
 #[cfg(test)]
 mod tests {
     use crate::r#const::*;
-    use crate::{CaseMatching, Config, Match, Matcher, Matching, SortStrategy};
+    use crate::{
+        CaseMatching, Config, Match, Matcher, Matching, Normalization, PathMode, Scoring,
+        SortStrategy,
+    };
 
     const CHAR_SCORE: u16 = MATCH_SCORE + MATCHING_CASE_BONUS;
 
@@ -91,6 +102,97 @@ mod tests {
         );
     }
 
+    #[test]
+    fn word_requires_boundaries() {
+        let haystacks = [
+            "seafood",
+            "foo",
+            "foo_bar",
+            "a/foo.rs",
+            "foobar",
+            "café_foo",
+            "éfoo",
+        ];
+        assert_eq!(
+            scores(Matching::Word, "foo", &haystacks)
+                .iter()
+                .map(|m| m.0)
+                .collect::<Vec<_>>(),
+            vec![1, 2, 3, 5]
+        );
+        // Non-ASCII chars are word chars on the unicode path too
+        assert_eq!(
+            scores(Matching::Word, "é", &["café", "é-b", "aéb"])
+                .iter()
+                .map(|m| m.0)
+                .collect::<Vec<_>>(),
+            vec![1]
+        );
+    }
+
+    #[test]
+    fn word_skips_occurrences_inside_words() {
+        // The first occurrence is inside a word, so the later one is used
+        let matches =
+            Matcher::new("foo", &config(Matching::Word)).match_list_indices(&["foobar foo"]);
+        assert_eq!(matches[0].indices, vec![9, 8, 7]);
+        assert_eq!(
+            get_score("foo", "foo_bar"),
+            scores(Matching::Word, "foo", &["foo_bar"])[0].1
+        );
+    }
+
+    #[test]
+    fn diacritics_normalization_reports_original_indices() {
+        let config = |matching| config(matching).normalization(Normalization::Diacritics);
+        let matches = Matcher::new("creme", &config(Matching::Substring))
+            .match_list_indices(&["Crème Brûlée", "creme"]);
+        assert_eq!(matches[0].indices, vec![5, 4, 3, 2, 1, 0]);
+        assert_eq!(matches[1].indices, vec![4, 3, 2, 1, 0]);
+
+        let matches =
+            Matcher::new("crème brûlée", &config(Matching::Exact)).match_list(&["Creme Brulee"]);
+        assert!(matches[0].exact);
+
+        // Glob matching isn't normalized
+        let matches = Matcher::new("cafe*", &config(Matching::Glob)).match_list(&["café.md"]);
+        assert!(matches.is_empty());
+    }
+
+    #[test]
+    fn full_case_folding_matches_expansions() {
+        let config = |matching| config(matching).casing(CaseMatching::Ignore);
+        let matches = Matcher::new("strasse", &config(Matching::Substring))
+            .match_list_indices(&["Hauptstraße 1"]);
+        assert_eq!(matches[0].indices, vec![11, 10, 9, 8, 7, 6, 5]);
+
+        let matches = Matcher::new("STRASSE", &config(Matching::Exact)).match_list(&["straße"]);
+        assert!(matches[0].exact);
+        let matches = Matcher::new("ὈΔΥΣΣΕΎΣ", &config(Matching::Exact)).match_list(&["ὀδυσσεύς"]);
+        assert!(matches[0].exact);
+    }
+
+    #[test]
+    fn unicode_boundary_bonuses_apply_to_every_script() {
+        // Non-ASCII delimiters, compared to a non-delimiter of the same byte length
+        assert_eq!(
+            get_score("ф", "а、ф"),
+            get_score("ф", "а日ф") + DELIMITER_BONUS
+        );
+        // Non-ASCII uppercase letters after lowercase letters
+        assert_eq!(
+            get_score_case("É", "ыÉ", CaseMatching::Respect),
+            get_score_case("É", "ЫÉ", CaseMatching::Respect).map(|s| s + CAPITALIZATION_BONUS)
+        );
+        // Word boundaries follow the same classification
+        let matches = Matcher::new("файл", &config(Matching::Word)).match_list(&[
+            "путь—файл",
+            "путьфайл",
+            "путь\u{301}файл",
+        ]);
+        assert_eq!(matches.iter().map(|m| m.index).collect::<Vec<_>>(), vec![0]);
+    }
+
     #[test]
     fn exact_and_prefix_scores_match_fuzzy() {
         // For matches anchored at position 0, the literal score equals the fuzzy score.
@@ -114,6 +216,63 @@ mod tests {
         assert_eq!(exact, fuzzy);
     }
 
+    #[test]
+    fn path_mode_scores_match_fuzzy() {
+        let config = |matching| config(matching).path_mode(Some(PathMode::default()));
+        let haystacks = [
+            "main_tests/foo/bar.rs",
+            "src/main_window.rs",
+            "src/main.rs",
+            "main",
+        ];
+        let fuzzy = Matcher::new("main", &config(Matching::Fuzzy)).match_list(&haystacks);
+        let substring = Matcher::new("main", &config(Matching::Substring)).match_list(&haystacks);
+        assert_eq!(substring, fuzzy);
+        assert!(
+            substring
+                .iter()
+                .zip(&fuzzy)
+                .all(|(a, b)| a.score == b.score)
+        );
+
+        // A later occurrence in the file name beats an earlier one with the prefix bonus
+        let matches =
+            Matcher::new("ab", &config(Matching::Substring)).match_list_indices(&["ab/x_ab"]);
+        assert_eq!(matches[0].indices, vec![6, 5]);
+
+        let matches = Matcher::new("b", &config(Matching::Substring)).match_list(&["a/b", "a_b"]);
+        assert!(matches[0].score > matches[1].score);
+    }
+
+    #[test]
+    fn consecutive_bonus_scores_match_fuzzy() {
+        let config = |matching| {
+            config(matching).scoring(Scoring {
+                consecutive_bonus: 8,
+                ..Scoring::default()
+            })
+        };
+        for (needle, haystacks) in [
+            ("foo", ["xfoo", "foo", "x_foo", "xFoo"]),
+            ("bar", ["foo_bar", "fooBar", "bar", "xbar"]),
+            ("fóo", ["xfóo", "fóo", "x_fóo", "日fóo"]),
+        ] {
+            let fuzzy = Matcher::new(needle, &config(Matching::Fuzzy)).match_list(&haystacks);
+            let substring =
+                Matcher::new(needle, &config(Matching::Substring)).match_list(&haystacks);
+            assert_eq!(substring.len(), haystacks.len());
+            assert_eq!(
+                substring.iter().map(|m| m.score).collect::<Vec<_>>(),
+                fuzzy.iter().map(|m| m.score).collect::<Vec<_>>(),
+                "{needle:?} on {haystacks:?}"
+            );
+        }
+        assert_eq!(
+            Matcher::new("foo", &config(Matching::Substring)).match_list(&["xfoo"])[0].score,
+            Matcher::new("foo", &Config::default()).match_list(&["xfoo"])[0].score + 2 * 8
+        );
+    }
+
     #[test]
     fn test_score_multibyte_needle() {
         // Interior "bar" in "foobar": three matched bytes, no prefix or delimiter.
@@ -341,12 +500,13 @@ mod tests {
     }
 
     #[test]
-    fn unicode_length_changing_fold_is_case_sensitive() {
-        // 'ß' folds to "SS" (a length change), so it is treated case-sensitively: it matches only
-        // itself, never "SS"/"ss".
+    fn unicode_length_changing_fold_uses_full_case_folding() {
+        // 'ß' flips to "SS" (a length change), so it's matched by its full case folding "ss"
+        // instead, which only applies when ignoring case
         assert!(get_score_case("ß", "ß", CaseMatching::Ignore).is_some());
-        assert_eq!(get_score_case("ß", "SS", CaseMatching::Ignore), None);
-        assert_eq!(get_score_case("ß", "ss", CaseMatching::Ignore), None);
+        assert!(get_score_case("ß", "SS", CaseMatching::Ignore).is_some());
+        assert!(get_score_case("ß", "ss", CaseMatching::Ignore).is_some());
+        assert_eq!(get_score_case("ß", "ss", CaseMatching::Respect), None);
     }
 
     #[test]
@@ -0,0 +1,319 @@
+use std::marker::PhantomData;
+
+use crate::prefilter::backend::Backend;
+use crate::{CaseMatching, Config, Match, MatchIndices, Matcher, Matching, PathMode, Scoring};
+
+/// Path segment matching: the needle is split on `/` and each of its segments must fuzzy match
+/// a distinct haystack path segment, in order, e.g. `s/m/lib` on `src/matcher/lib.rs`
+/// Each needle segment is scored by a [`Matching::Fuzzy`] [`Matcher`], so the normalization,
+/// case locale and unicode settings apply to every segment
+#[derive(Debug, Clone)]
+pub(crate) struct PathSegmentsImpl<B: Backend> {
+    /// Fuzzy matchers of the non-empty needle segments, in order
+    segments: Vec<Matcher>,
+    scoring: Scoring,
+    path_mode: Option<PathMode>,
+    /// Matches scoring below this are filtered out
+    min_score: u16,
+    backend: PhantomData<B>,
+}
+
+/// Best alignment of the needle segments up to one matched in a given haystack segment
+#[derive(Debug, Clone)]
+struct Cell {
+    score: u16,
+    /// Haystack segment matched by the previous needle segment
+    prev: usize,
+    /// Whether this needle segment equals the haystack segment
+    exact: bool,
+    /// Byte offsets in the haystack of the chars matched by this needle segment
+    indices: Vec<u32>,
+}
+
+impl<B: Backend> PathSegmentsImpl<B> {
+    /// # Safety
+    /// The backend's target features must be enabled.
+    #[inline(always)]
+    pub(crate) unsafe fn new(needle: &str, config: &Config) -> Self {
+        // Smart case applies to the whole needle, rather than to each segment
+        let casing = match config.casing.respects_case_for(needle) {
+            true => CaseMatching::Respect,
+            false => CaseMatching::Ignore,
+        };
+        // Path segments can't contain separators, so the path mode's bonuses don't apply, and
+        // the exact match bonus is earned once by the whole haystack
+        let segment_config = config
+            .clone()
+            .matching(Matching::Fuzzy)
+            .max_typos(Some(0))
+            .casing(casing)
+            .min_score(0)
+            .path_mode(None)
+            .scoring(Scoring {
+                exact_match_bonus: 0,
+                ..config.scoring.clone()
+            });
+        let segments = needle
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .map(|segment| Matcher::new(segment, &segment_config))
+            .collect();
+
+        Self {
+            segments,
+            scoring: config.scoring.clone(),
+            path_mode: config.path_mode,
+            min_score: config.min_score,
+            backend: PhantomData,
+        }
+    }
+
+    /// Updates the score below which matches are filtered out
+    pub(crate) fn set_min_score(&mut self, min_score: u16) {
+        self.min_score = min_score;
+    }
+
+    pub(crate) fn is_available() -> bool {
+        B::is_available()
+    }
+
+    #[inline(always)]
+    pub(super) unsafe fn match_list_impl<const UNICODE: bool, H: AsRef<str>>(
+        &mut self,
+        haystacks: &[H],
+        haystack_index_offset: u32,
+        matches: &mut Vec<Match>,
+    ) {
+        for (index, haystack) in (haystack_index_offset..).zip(haystacks.iter()) {
+            if let Some(m) = unsafe { self.match_one_impl::<UNICODE, &H>(haystack, index) } {
+                matches.push(m);
+            }
+        }
+    }
+
+    #[inline(always)]
+    pub(super) unsafe fn match_one_impl<const UNICODE: bool, H: AsRef<str>>(
+        &mut self,
+        haystack: H,
+        index: u32,
+    ) -> Option<Match> {
+        let (score, exact, indices) = unsafe { self.find::<UNICODE>(haystack.as_ref()) }?;
+        #[cfg(not(feature = "match_end_col"))]
+        let _ = indices;
+        Some(Match {
+            index,
+            score,
+            exact,
+            field: 0,
+            #[cfg(feature = "match_end_col")]
+            end_col: indices
+                .first()
+                .map_or(0, |&i| i.min(u16::MAX as u32) as u16),
+        })
+    }
+
+    #[inline(always)]
+    pub(super) unsafe fn match_list_indices_impl<const UNICODE: bool, H: AsRef<str>>(
+        &mut self,
+        haystacks: &[H],
+    ) -> Vec<MatchIndices> {
+        let mut matches = vec![];
+        for (index, haystack) in haystacks.iter().enumerate() {
+            if let Some(m) =
+                unsafe { self.match_one_indices_impl::<UNICODE, &H>(haystack, index as u32) }
+            {
+                matches.push(m);
+            }
+        }
+        matches
+    }
+
+    #[inline(always)]
+    pub(super) unsafe fn match_one_indices_impl<const UNICODE: bool, H: AsRef<str>>(
+        &mut self,
+        haystack: H,
+        index: u32,
+    ) -> Option<MatchIndices> {
+        let (score, exact, indices) = unsafe { self.find::<UNICODE>(haystack.as_ref()) }?;
+        Some(MatchIndices {
+            index,
+            score,
+            exact,
+            indices,
+        })
+    }
+
+    /// Aligns the needle segments with the haystack segments, maximizing the sum of their fuzzy
+    /// scores. Haystack segments skipped between two matched segments cost the gap open penalty.
+    /// Returns the score, whether every haystack byte apart from the separators matched, and
+    /// the matched byte offsets in reverse order, when it reaches the min score
+    #[inline(always)]
+    unsafe fn find<const UNICODE: bool>(
+        &mut self,
+        haystack: &str,
+    ) -> Option<(u16, bool, Vec<u32>)> {
+        // Every needle segment must match the haystack as a whole, before aligning the segments
+        for segment in &mut self.segments {
+            segment.match_one(haystack, 0)?;
+        }
+
+        // The separators are ASCII, so each haystack segment is valid UTF-8
+        let (separator, alt_separator) = self.path_mode.unwrap_or_default().separators();
+        let haystack_segments = haystack
+            .split([separator as char, alt_separator as char])
+            .scan(0, |start, segment| {
+                let offset = *start;
+                *start += segment.len() + 1;
+                Some((offset, segment))
+            })
+            .collect::<Vec<_>>();
+        let (needle_len, haystack_len) = (self.segments.len(), haystack_segments.len());
+        if needle_len > haystack_len {
+            return None;
+        }
+
+        // cells[n * haystack_len + h]: needle segment `n` matched in haystack segment `h`
+        let mut cells: Vec<Option<Cell>> = vec![None; needle_len * haystack_len];
+        for (n, segment) in self.segments.iter_mut().enumerate() {
+            // Leave a haystack segment for each of the remaining needle segments
+            for h in n..haystack_len - (needle_len - 1 - n) {
+                let prev = match n {
+                    0 => Some((0, 0)),
+                    _ => (n - 1..h)
+                        .filter_map(|prev| {
+                            let cell = cells[(n - 1) * haystack_len + prev].as_ref()?;
+                            let gap_penalty = match prev + 1 < h {
+                                true => self.scoring.gap_open_penalty,
+                                false => 0,
+                            };
+                            Some((cell.score.saturating_sub(gap_penalty), prev))
+                        })
+                        .reduce(|best, other| if other.0 > best.0 { other } else { best }),
+                };
+                let Some((prev_score, prev)) = prev else {
+                    continue;
+                };
+
+                let (offset, haystack_segment) = haystack_segments[h];
+                let Some(m) = segment.match_one_indices(haystack_segment, 0) else {
+                    continue;
+                };
+                let mut indices = m.indices;
+                indices.sort_unstable();
+                cells[n * haystack_len + h] = Some(Cell {
+                    score: prev_score.saturating_add(m.score),
+                    prev,
+                    exact: m.exact,
+                    indices: indices.iter().map(|&i| i + offset as u32).collect(),
+                });
+            }
+        }
+
+        let Some(last) = self.segments.len().checked_sub(1) else {
+            return (self.min_score == 0).then_some((0, false, vec![]));
+        };
+        let (mut h, mut score) = (0..haystack_len)
+            .filter_map(|h| Some((h, cells[last * haystack_len + h].as_ref()?.score)))
+            .reduce(|best, other| if other.1 > best.1 { other } else { best })?;
+
+        let mut indices = vec![];
+        let mut exact = needle_len == haystack_len;
+        for n in (0..needle_len).rev() {
+            let cell = cells[n * haystack_len + h].as_ref()?;
+            exact &= cell.exact;
+            indices.extend(cell.indices.iter().rev());
+            h = cell.prev;
+        }
+        if exact {
+            score = score.saturating_add(self.scoring.exact_match_bonus);
+        }
+        (score >= self.min_score).then_some((score, exact, indices))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Config, Matcher, Matching, Normalization, PathMode, SortStrategy};
+
+    fn config() -> Config {
+        Config::default()
+            .matching(Matching::PathSegments)
+            .sort(SortStrategy::IndexAsc)
+    }
+
+    fn indices(needle: &str, haystacks: &[&str], config: &Config) -> Vec<u32> {
+        Matcher::new(needle, config)
+            .match_list(haystacks)
+            .iter()
+            .map(|m| m.index)
+            .collect()
+    }
+
+    #[test]
+    fn needle_segments_match_ordered_path_segments() {
+        let haystacks = [
+            "src/matcher/lib.rs",
+            "src/lib.rs",
+            "lib/matcher/src.rs",
+            "src/foo/matcher/lib.rs",
+            "sml.rs",
+            "src/matcher_lib.rs",
+        ];
+        assert_eq!(indices("s/m/lib", &haystacks, &config()), vec![0, 3]);
+        assert_eq!(indices("m/lib", &haystacks, &config()), vec![0, 3]);
+        assert_eq!(indices("/lib/", &haystacks, &config()), vec![0, 1, 2, 3, 5]);
+    }
+
+    #[test]
+    fn indices_span_the_haystack() {
+        let matches =
+            Matcher::new("s/m/lib", &config()).match_list_indices(&["src/matcher/lib.rs"]);
+        assert_eq!(matches[0].indices, vec![14, 13, 12, 4, 0]);
+        assert!(!matches[0].exact);
+
+        let matches = Matcher::new("src/lib.rs", &config()).match_list_indices(&["src/lib.rs"]);
+        assert!(matches[0].exact);
+    }
+
+    #[test]
+    fn skipped_segments_cost_a_gap() {
+        let config = config().sort(SortStrategy::ScoreThenIndexAsc);
+        let haystacks = ["src/foo/matcher/lib.rs", "src/matcher/lib.rs"];
+        assert_eq!(indices("s/m/lib", &haystacks, &config), vec![1, 0]);
+    }
+
+    #[test]
+    fn segments_score_like_fuzzy() {
+        let fuzzy = |needle, haystack| {
+            Matcher::new(needle, &Config::default()).match_list(&[haystack])[0].score
+        };
+        let matches = Matcher::new("src/mtch", &config()).match_list(&["src/matcher.rs"]);
+        assert_eq!(
+            matches[0].score,
+            fuzzy("src", "src") - Config::default().scoring.exact_match_bonus
+                + fuzzy("mtch", "matcher.rs")
+        );
+    }
+
+    #[test]
+    fn segments_respect_normalization() {
+        let haystacks = ["Café/menü.md"];
+        assert!(indices("cafe/menu", &haystacks, &config()).is_empty());
+
+        let config = config().normalization(Normalization::Diacritics);
+        let matches = Matcher::new("cafe/menu", &config).match_list_indices(&haystacks);
+        assert_eq!(matches[0].indices, vec![10, 9, 8, 7, 6, 4, 3, 2, 1, 0]);
+    }
+
+    #[test]
+    fn backslash_separators_in_path_mode() {
+        let haystacks = ["src\\matcher\\lib.rs"];
+        assert!(indices("s/m/lib", &haystacks, &config()).is_empty());
+
+        let config = config().path_mode(Some(PathMode {
+            backslash: true,
+            ..PathMode::default()
+        }));
+        assert_eq!(indices("s/m/lib", &haystacks, &config), vec![0]);
+    }
+}
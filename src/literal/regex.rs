@@ -0,0 +1,419 @@
+use std::ops::Range;
+
+use ::regex::{Regex, RegexBuilder};
+use regex_syntax::ParserBuilder;
+use regex_syntax::hir::literal::{ExtractKind, Extractor};
+
+use super::algo::{LiteralImpl, score_scalar};
+use crate::prefilter::backend::Backend;
+use crate::{
+    CaseLocale, CaseMatching, Config, Match, MatchIndices, Matching, Normalization, Scoring,
+};
+
+/// Regex matching: the needle is a regular expression which must match somewhere in the haystack
+/// The regex's literal prefix is scanned for before running the regex itself
+/// Scores each matched char like [`LiteralImpl`], using the highest-scoring match
+#[derive(Debug, Clone)]
+pub(crate) struct RegexImpl<B: Backend> {
+    regex: Regex,
+    scoring: Scoring,
+    /// Matches scoring below this are filtered out
+    min_score: u16,
+    /// Whether the regex respects case, in which case every matched char gets the
+    /// matching case bonus
+    case_sensitive: bool,
+    /// Whether the regex can match the empty string, in which case matching haystacks score 0
+    /// without indices, like an empty needle
+    empty_match: bool,
+    /// Whether matched chars are classified by their unicode category for the bonuses, as
+    /// decided by [`Config::unicode`] for the needle
+    unicode: bool,
+    /// Substring matcher for the literal prefix shared by every match of the regex, if any.
+    /// Haystacks without the prefix are skipped without running the regex
+    seed: Option<LiteralImpl<B>>,
+    /// Whether the seed uses the unicode path of [`LiteralImpl`]
+    seed_unicode: bool,
+}
+
+impl<B: Backend> RegexImpl<B> {
+    /// # Safety
+    /// The backend's target features must be enabled.
+    #[inline(always)]
+    pub(crate) unsafe fn new(needle: &str, config: &Config) -> Self {
+        let case_sensitive = config.casing.respects_case_for(needle);
+        let build = |pattern: &str| {
+            RegexBuilder::new(pattern)
+                .case_insensitive(!case_sensitive)
+                .build()
+        };
+        // Fall back to matching invalid regexes literally, since they're typically incomplete
+        // queries while typing
+        let regex = build(needle)
+            .or_else(|_| build(&::regex::escape(needle)))
+            .expect("escaped regex should be valid");
+
+        let seed_config = Config {
+            matching: Matching::Substring,
+            casing: match case_sensitive {
+                true => CaseMatching::Respect,
+                false => CaseMatching::Ignore,
+            },
+            min_score: 0,
+            case_locale: CaseLocale::Unicode,
+            normalization: Normalization::Never,
+            ..config.clone()
+        };
+        let seed = literal_prefix(regex.as_str(), case_sensitive, &config.scoring);
+        let seed_unicode = seed
+            .as_ref()
+            .is_some_and(|seed| seed_config.unicode.respects_unicode_for(seed));
+        let seed = seed.map(|seed| unsafe { LiteralImpl::new(&seed, &seed_config) });
+
+        Self {
+            regex,
+            scoring: config.scoring.clone(),
+            min_score: config.min_score,
+            case_sensitive,
+            empty_match: regex_match_len(needle, case_sensitive).0 == Some(0),
+            unicode: config.unicode.respects_unicode_for(needle),
+            seed,
+            seed_unicode,
+        }
+    }
+
+    /// Updates the score below which matches are filtered out
+    pub(crate) fn set_min_score(&mut self, min_score: u16) {
+        self.min_score = min_score;
+    }
+
+    pub(crate) fn is_available() -> bool {
+        B::is_available()
+    }
+
+    /// `UNICODE` is ignored, as the regex always matches whole codepoints
+    #[inline(always)]
+    pub(super) unsafe fn match_list_impl<const UNICODE: bool, H: AsRef<str>>(
+        &self,
+        haystacks: &[H],
+        haystack_index_offset: u32,
+        matches: &mut Vec<Match>,
+    ) {
+        for (index, haystack) in (haystack_index_offset..).zip(haystacks.iter()) {
+            if let Some(m) = unsafe { self.match_one_impl::<UNICODE, &H>(haystack, index) } {
+                matches.push(m);
+            }
+        }
+    }
+
+    #[inline(always)]
+    pub(super) unsafe fn match_one_impl<const UNICODE: bool, H: AsRef<str>>(
+        &self,
+        haystack: H,
+        index: u32,
+    ) -> Option<Match> {
+        let haystack = haystack.as_ref();
+        let (span, score) = unsafe { self.find(haystack) }?;
+        Some(Match {
+            index,
+            score,
+            exact: is_exact(haystack, &span),
+            field: 0,
+            #[cfg(feature = "match_end_col")]
+            end_col: span.end.saturating_sub(1).min(u16::MAX as usize) as u16,
+        })
+    }
+
+    #[inline(always)]
+    pub(super) unsafe fn match_list_indices_impl<const UNICODE: bool, H: AsRef<str>>(
+        &self,
+        haystacks: &[H],
+    ) -> Vec<MatchIndices> {
+        let mut matches = vec![];
+        for (index, haystack) in haystacks.iter().enumerate() {
+            if let Some(m) =
+                unsafe { self.match_one_indices_impl::<UNICODE, &H>(haystack, index as u32) }
+            {
+                matches.push(m);
+            }
+        }
+        matches
+    }
+
+    #[inline(always)]
+    pub(super) unsafe fn match_one_indices_impl<const UNICODE: bool, H: AsRef<str>>(
+        &self,
+        haystack: H,
+        index: u32,
+    ) -> Option<MatchIndices> {
+        let haystack = haystack.as_ref();
+        let (span, score) = unsafe { self.find(haystack) }?;
+        Some(MatchIndices {
+            index,
+            score,
+            exact: is_exact(haystack, &span),
+            // Reversed to match the fuzzy matcher implementation
+            indices: span.clone().rev().map(|i| i as u32).collect(),
+        })
+    }
+
+    /// Returns the byte range of the highest-scoring regex match starting at any char, including
+    /// matches overlapping another, preferring earlier matches when tied, as well as its score,
+    /// when it reaches the min score. Regexes which can match the empty string return an empty
+    /// range scoring 0 instead
+    #[inline(always)]
+    unsafe fn find(&self, haystack: &str) -> Option<(Range<usize>, u16)> {
+        if let Some(seed) = &self.seed {
+            let bytes = haystack.as_bytes();
+            match self.seed_unicode {
+                true => unsafe { seed.find::<true>(bytes) },
+                false => unsafe { seed.find::<false>(bytes) },
+            }?;
+        }
+        if self.empty_match {
+            return (self.regex.is_match(haystack) && self.min_score == 0).then_some((0..0, 0));
+        }
+
+        // Searches again from each char after the start of the previous match, rather than
+        // iterating the non-overlapping matches, which would skip matches overlapping an
+        // earlier one (e.g. `aa` for `.a` in `x_aa`)
+        let mut best: Option<(Range<usize>, u16)> = None;
+        let mut start = 0;
+        while let Some(m) = self.regex.find_at(haystack, start) {
+            let score = self.score(haystack, m.range());
+            if best
+                .as_ref()
+                .is_none_or(|&(_, best_score)| score > best_score)
+            {
+                best = Some((m.range(), score));
+            }
+            match haystack[m.start()..].chars().next() {
+                Some(c) => start = m.start() + c.len_utf8(),
+                None => break,
+            }
+        }
+        best.filter(|&(_, score)| score >= self.min_score)
+    }
+
+    /// Scores a match spanning `span`, summing one [`score_scalar`] per matched char
+    #[inline(always)]
+    fn score(&self, haystack: &str, span: Range<usize>) -> u16 {
+        let bytes = haystack.as_bytes();
+        let mut score = 0u16;
+        let mut run_bonus = None;
+        for (offset, c) in haystack[span.clone()].char_indices() {
+            // Without a needle char to compare against, a case-insensitive regex is treated as
+            // lowercase, like a lowercase needle with smart case
+            let matched_exact_case = self.case_sensitive || !c.is_uppercase();
+            let start = span.start + offset;
+            let char_score = match self.unicode {
+                true => score_scalar::<true>(
+                    &self.scoring,
+                    None,
+                    bytes,
+                    start,
+                    matched_exact_case,
+                    &mut run_bonus,
+                ),
+                false => score_scalar::<false>(
+                    &self.scoring,
+                    None,
+                    bytes,
+                    start,
+                    matched_exact_case,
+                    &mut run_bonus,
+                ),
+            };
+            score = score.saturating_add(char_score);
+        }
+
+        if span.start > 0 {
+            score = score.saturating_add(self.scoring.prefix_bonus_at(span.start));
+        }
+        if is_exact(haystack, &span) {
+            score = score.saturating_add(self.scoring.exact_match_bonus);
+        }
+        score
+    }
+}
+
+#[inline(always)]
+fn is_exact(haystack: &str, span: &Range<usize>) -> bool {
+    !haystack.is_empty() && span.start == 0 && span.end == haystack.len()
+}
+
+/// Min and max length in bytes of the regex's matches, where the max is `None` when unbounded.
+/// Invalid regexes are matched literally, like in [`RegexImpl::new`]
+pub(crate) fn regex_match_len(
+    needle: &str,
+    case_sensitive: bool,
+) -> (Option<usize>, Option<usize>) {
+    let parser = || {
+        ParserBuilder::new()
+            .case_insensitive(!case_sensitive)
+            .build()
+    };
+    let hir = match parser().parse(needle) {
+        Ok(hir) => hir,
+        Err(_) => parser()
+            .parse(&::regex::escape(needle))
+            .expect("escaped regex should be valid"),
+    };
+    (
+        hir.properties().minimum_len(),
+        hir.properties().maximum_len(),
+    )
+}
+
+/// Extracts the literal prefix shared by every match of the regex, if any. When matching
+/// case-insensitively, the prefix stops at the first char whose case variants aren't all ASCII
+/// (e.g. `k` also matches the Kelvin sign), since the seed only flips the case of ASCII chars
+fn literal_prefix(pattern: &str, case_sensitive: bool, scoring: &Scoring) -> Option<String> {
+    let hir = ParserBuilder::new().build().parse(pattern).ok()?;
+    let literals = Extractor::new().kind(ExtractKind::Prefix).extract(&hir);
+    let prefix = literals.longest_common_prefix()?;
+    let prefix = match std::str::from_utf8(prefix) {
+        Ok(prefix) => prefix,
+        Err(err) => std::str::from_utf8(&prefix[..err.valid_up_to()]).unwrap(),
+    };
+
+    let mut seed = String::new();
+    for c in prefix.chars() {
+        let ascii_only = c.is_ascii() && !matches!(c.to_ascii_lowercase(), 'k' | 's');
+        if (!case_sensitive && !ascii_only) || seed.len() + c.len_utf8() > scoring.max_needle_len()
+        {
+            break;
+        }
+        seed.push(c);
+    }
+    (!seed.is_empty()).then_some(seed)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{CaseMatching, Config, Matcher, Matching, Scoring, SortStrategy};
+
+    fn config() -> Config {
+        Config::default().sort(SortStrategy::IndexAsc)
+    }
+
+    fn indices(query: &str, config: &Config, haystacks: &[&str]) -> Vec<u32> {
+        Matcher::from_query(query, config)
+            .match_list(haystacks)
+            .iter()
+            .map(|m| m.index)
+            .collect()
+    }
+
+    #[test]
+    fn matches_regex_anywhere() {
+        let haystacks = ["foobar", "xfoooobzr", "fobar", "fbar", "FOOBAR"];
+        assert_eq!(
+            indices("%fo+b[a-z]r", &config(), &haystacks),
+            vec![0, 1, 2, 4]
+        );
+        assert_eq!(indices("%^fo+bar$", &config(), &haystacks), vec![0, 2, 4]);
+        assert_eq!(indices("!%[0-9]", &config(), &["a1", "b", "c2"]), vec![1]);
+    }
+
+    #[test]
+    fn scores_like_substring() {
+        for haystack in [
+            "bar",
+            "foobar",
+            "foo_bar",
+            "fooBar",
+            "FOOBAR",
+            "bar_xbar",
+            "é_bar",
+            "barÉcole",
+            "bar_école",
+        ] {
+            // Unicode needles classify the haystack's chars by their unicode category
+            for needle in ["bar", "Bar", "École", "école"] {
+                let substring = Config::default().matching(Matching::Substring);
+                let regex = Config::default().matching(Matching::Regex);
+                let score = |config: &Config| {
+                    Matcher::new(needle, config)
+                        .match_list(&[haystack])
+                        .first()
+                        .map(|m| (m.score, m.exact))
+                };
+                assert_eq!(
+                    score(&regex),
+                    score(&substring),
+                    "needle: {needle:?}, haystack: {haystack:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn indices_span_the_best_match() {
+        let matches = Matcher::from_query("%a.c", &config()).match_list_indices(&["xabc_adc"]);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].indices, vec![7, 6, 5]);
+    }
+
+    #[test]
+    fn indices_span_the_best_overlapping_match() {
+        let config = config().scoring(Scoring {
+            consecutive_bonus: 6,
+            ..Scoring::default()
+        });
+        let indices = |query: &str, haystack: &str| {
+            Matcher::from_query(query, &config).match_list_indices(&[haystack])[0]
+                .indices
+                .clone()
+        };
+        // `aa` overlaps the leftmost match `_a`, yet scores higher with the consecutive bonus
+        assert_eq!(indices("%.a", "x_aa"), vec![3, 2]);
+        assert_eq!(indices("%.é", "x_éé"), vec![5, 4, 3, 2]);
+    }
+
+    #[test]
+    fn seed_respects_case_folding() {
+        let haystacks = ["Kelvin", "\u{212a}elvin", "kelvi", "xxKELVINxx", "ſelvin"];
+        // `k` and `s` fold to non-ASCII chars, so they can't be part of a case-insensitive seed
+        assert_eq!(indices("%kelvin", &config(), &haystacks), vec![0, 1, 3]);
+        assert_eq!(indices("%selvin", &config(), &haystacks), vec![4]);
+        let respect = config().casing(CaseMatching::Respect);
+        assert_eq!(indices("%Kelvin", &respect, &haystacks), vec![0]);
+    }
+
+    #[test]
+    fn empty_match_regex_matches_like_empty_needle() {
+        let haystacks = ["foo", "bar", "xfoo"];
+        let matches = |query: &str| Matcher::from_query(query, &config()).match_list(&haystacks);
+        for query in ["%a*", "%x?", "%^"] {
+            assert_eq!(matches(query), matches(""), "query: {query:?}");
+        }
+        assert_eq!(matches("foo %a*"), matches("foo"));
+        // Keeps the OR-group, where the other alternatives may score higher
+        assert_eq!(indices("foo | %x?", &config(), &haystacks), vec![0, 1, 2]);
+        let matches = Matcher::from_query("%o*", &config()).match_list_indices(&["foo"]);
+        assert_eq!((matches[0].score, matches[0].indices.len()), (0, 0));
+    }
+
+    #[test]
+    fn anchored_empty_match_regex_filters() {
+        let haystacks = ["aaa", "bbb", "", "foo bar"];
+        assert_eq!(indices("%^a*$", &config(), &haystacks), vec![0, 2]);
+        assert_eq!(indices("%^$", &config(), &haystacks), vec![2]);
+        assert_eq!(indices("%^$ | %^b*$", &config(), &haystacks), vec![1, 2]);
+        assert_eq!(indices("!%^a*$", &config(), &haystacks), vec![1, 3]);
+    }
+
+    #[test]
+    fn negated_empty_match_regex_filters_everything() {
+        let haystacks = ["aaa", "bbb", "", "foo bar"];
+        assert!(indices("!%x?", &config(), &haystacks).is_empty());
+        assert!(indices("!%a*", &config(), &haystacks).is_empty());
+        assert!(indices("foo !%x?", &config(), &haystacks).is_empty());
+    }
+
+    #[test]
+    fn invalid_regex_matches_literally() {
+        let haystacks = ["foo(bar", "foobar"];
+        assert_eq!(indices("%foo(", &config(), &haystacks), vec![0]);
+    }
+}
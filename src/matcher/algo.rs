@@ -1,4 +1,5 @@
-use crate::prefilter::{Kernel as PrefilterKernel, Window};
+use crate::normalize::Normalizer;
+use crate::prefilter::{Kernel as PrefilterKernel, Window, typo_unit_count, typo_unit_ends};
 use crate::smith_waterman::Kernel as SmithWatermanKernel;
 use crate::{Config, Match, MatchIndices};
 
@@ -43,7 +44,15 @@ pub(crate) trait Specialized: Sized {
 pub struct MatcherImpl<P: PrefilterKernel, S: SmithWatermanKernel> {
     needle: String,
     config: Config,
+    /// Haystacks shorter than this can't match within the max typos, or can't reach the min
+    /// score (see [`Self::set_min_score`])
     min_haystack_len: usize,
+    /// Typo units of the needle (see [`typo_unit_count`])
+    typo_units: usize,
+    /// Whether a typo may skip several needle chars (see [`typo_unit_ends`]), which only the
+    /// many typos prefilter supports
+    multi_char_typo_units: bool,
+    normalizer: Normalizer,
     prefilter: P,
     smith_waterman: S,
 }
@@ -56,20 +65,45 @@ where
     #[inline(always)]
     pub fn new(needle: &str, config: &Config) -> Self {
         let case_sensitive = config.casing.respects_case_for(needle);
-        let matcher = Self {
+        let graphemes = config.unicode.is_graphemes();
+        let typo_unit_ends = typo_unit_ends(needle, graphemes);
+        let typo_units = typo_unit_count(needle, &typo_unit_ends);
+        let mut matcher = Self {
             needle: needle.to_string(),
             config: config.clone(),
-            min_haystack_len: config
-                .max_typos
-                .map(|max| needle.chars().count().saturating_sub(max as usize))
-                .unwrap_or(0),
-            prefilter: P::new(needle, case_sensitive),
-            smith_waterman: S::new(needle, &config.scoring, case_sensitive),
+            min_haystack_len: 0,
+            typo_units,
+            multi_char_typo_units: !typo_unit_ends.is_empty(),
+            normalizer: Normalizer::for_config(config, needle),
+            prefilter: P::new(needle, case_sensitive, graphemes),
+            smith_waterman: S::new(
+                needle,
+                &config.scoring,
+                case_sensitive,
+                config.path_mode,
+                graphemes,
+            ),
         };
+        matcher.set_min_score(config.min_score);
         matcher.guard_against_score_overflow();
         matcher
     }
 
+    /// Updates the score below which matches are filtered out, skipping the haystacks too
+    /// short to reach it before prefiltering
+    pub(super) fn set_min_score(&mut self, min_score: u16) {
+        let config = &mut self.config;
+        config.min_score = min_score;
+        // Every typo unit left after the typos has at least one byte
+        self.min_haystack_len = config
+            .max_typos
+            .map(|max| self.typo_units.saturating_sub(max as usize))
+            .unwrap_or(0)
+            .max(config.scoring.min_haystack_len_for_score(
+                config.min_score.saturating_sub(config.max_path_bonus()),
+            ));
+    }
+
     pub fn is_available() -> bool {
         P::is_available() && S::is_available()
     }
@@ -84,20 +118,14 @@ where
         let max_typos = self.max_typos_runtime::<TYPOS>();
         for (index, haystack_str) in (haystack_index_offset..).zip(haystacks.iter()) {
             let haystack = haystack_str.as_ref().as_bytes();
-            let original_len = haystack.len();
-            if original_len >= self.min_haystack_len {
-                let (matched, start_pos, end_pos) =
-                    self.prefilter_haystack::<TYPOS, UNICODE>(haystack, max_typos);
-                if matched {
-                    let (trimmed, start_pos, include_exact) =
-                        trim_haystack(haystack, start_pos, end_pos);
-                    matches.push(self.smith_waterman_one::<UNICODE>(
-                        trimmed,
-                        index,
-                        start_pos,
-                        include_exact,
-                    ));
-                }
+            if haystack.len() < self.min_haystack_len {
+                continue;
+            }
+            let Some(m) = self.score_one::<TYPOS, UNICODE>(haystack, index, max_typos) else {
+                continue;
+            };
+            if m.score >= self.config.min_score {
+                matches.push(m);
             }
         }
     }
@@ -115,19 +143,12 @@ where
     ) -> Option<Match> {
         let haystack = haystack.as_ref().as_bytes();
         let max_typos = self.max_typos_runtime::<TYPOS>();
-        let original_len = haystack.len();
-        if original_len < self.min_haystack_len {
-            return None;
-        }
-
-        let (matched, start_pos, end_pos) =
-            self.prefilter_haystack::<TYPOS, UNICODE>(haystack, max_typos);
-        if !matched {
+        if haystack.len() < self.min_haystack_len {
             return None;
         }
 
-        let (trimmed, start_pos, include_exact) = trim_haystack(haystack, start_pos, end_pos);
-        Some(self.smith_waterman_one::<UNICODE>(trimmed, index, start_pos, include_exact))
+        self.score_one::<TYPOS, UNICODE>(haystack, index, max_typos)
+            .filter(|m| m.score >= self.config.min_score)
     }
 
     /// Single-haystack path for `Matcher::match_iter_indices`, mirroring
@@ -142,30 +163,12 @@ where
     ) -> Option<MatchIndices> {
         let haystack = haystack.as_ref().as_bytes();
         let max_typos = self.max_typos_runtime::<TYPOS>();
-        let max_typos_opt = if TYPOS == NO_PREFILTER {
-            None
-        } else {
-            Some(max_typos)
-        };
-        let original_len = haystack.len();
-        if original_len < self.min_haystack_len {
-            return None;
-        }
-
-        let (matched, start_pos, end_pos) =
-            self.prefilter_haystack::<TYPOS, UNICODE>(haystack, max_typos);
-        if !matched {
+        if haystack.len() < self.min_haystack_len {
             return None;
         }
 
-        let (trimmed, start_pos, include_exact) = trim_haystack(haystack, start_pos, end_pos);
-        Some(self.smith_waterman_indices_one::<UNICODE>(
-            trimmed,
-            start_pos,
-            index,
-            include_exact,
-            max_typos_opt,
-        ))
+        self.score_one_indices::<TYPOS, UNICODE>(haystack, index, max_typos)
+            .filter(|m| m.score >= self.config.min_score)
     }
 
     #[inline(always)]
@@ -178,6 +181,9 @@ where
             NO_PREFILTER => (true, 0, haystack.len()),
             0 if UNICODE => self.prefilter.match_haystack_unicode(haystack),
             0 => self.prefilter.match_haystack(haystack),
+            1 | 2 if UNICODE && self.multi_char_typo_units => self
+                .prefilter
+                .match_haystack_unicode_many_typos(haystack, max_typos),
             1 if UNICODE => self.prefilter.match_haystack_unicode_1_typo(haystack),
             1 => self.prefilter.match_haystack_1_typo(haystack),
             2 if UNICODE => self.prefilter.match_haystack_unicode_2_typos(haystack),
@@ -198,32 +204,134 @@ where
         haystacks: &[H],
     ) -> Vec<MatchIndices> {
         let max_typos = self.max_typos_runtime::<TYPOS>();
+        let mut matches = vec![];
+        for (index, haystack_str) in haystacks.iter().enumerate() {
+            let haystack = haystack_str.as_ref().as_bytes();
+            if haystack.len() < self.min_haystack_len {
+                continue;
+            }
+            let Some(m) =
+                self.score_one_indices::<TYPOS, UNICODE>(haystack, index as u32, max_typos)
+            else {
+                continue;
+            };
+            if m.score >= self.config.min_score {
+                matches.push(m);
+            }
+        }
+        matches
+    }
+
+    /// Prefilters and scores a haystack, trimmed to the prefilter's window. In path mode, the
+    /// file name is also scored on its own with the basename bonus, keeping the best score
+    #[inline(always)]
+    fn score_one<const TYPOS: u16, const UNICODE: bool>(
+        &mut self,
+        haystack: &[u8],
+        index: u32,
+        max_typos: u16,
+    ) -> Option<Match> {
+        let normalized = self.normalizer.normalize_haystack(haystack);
+        let haystack = normalized.as_ref().map_or(haystack, |h| h.as_bytes());
+        let mut best: Option<Match> = None;
+        for (window_start, bonus) in self.path_windows(haystack).into_iter().flatten() {
+            let window = &haystack[window_start..];
+            let (matched, start_pos, end_pos) =
+                self.prefilter_haystack::<TYPOS, UNICODE>(window, max_typos);
+            if !matched {
+                break;
+            }
+
+            let (trimmed, start_pos, include_exact) =
+                trim_haystack::<UNICODE>(window, start_pos, end_pos);
+            let mut m = self.smith_waterman_one::<UNICODE>(
+                trimmed,
+                index,
+                window_start + start_pos,
+                include_exact && window_start == 0,
+            );
+            if window_start > 0 && m.score == 0 {
+                break;
+            }
+            m.score = m.score.saturating_add(bonus);
+            if best.is_none_or(|best| m.score > best.score) {
+                best = Some(m);
+            }
+        }
+        #[cfg(feature = "match_end_col")]
+        if let (Some(normalized), Some(m)) = (&normalized, &mut best)
+            && (m.end_col as usize) < normalized.as_bytes().len()
+        {
+            m.end_col = normalized
+                .original_index(m.end_col.into())
+                .min(u16::MAX as u32) as u16;
+        }
+        best
+    }
+
+    /// Like [`Self::score_one`], but includes the indices of the matched chars
+    #[inline(always)]
+    fn score_one_indices<const TYPOS: u16, const UNICODE: bool>(
+        &mut self,
+        haystack: &[u8],
+        index: u32,
+        max_typos: u16,
+    ) -> Option<MatchIndices> {
         let max_typos_opt = if TYPOS == NO_PREFILTER {
             None
         } else {
             Some(max_typos)
         };
-        let mut matches = vec![];
-        for (index, haystack_str) in haystacks.iter().enumerate() {
-            let haystack = haystack_str.as_ref().as_bytes();
-            let original_len = haystack.len();
-            if original_len >= self.min_haystack_len {
-                let (matched, start_pos, end_pos) =
-                    self.prefilter_haystack::<TYPOS, UNICODE>(haystack, max_typos);
-                if matched {
-                    let (trimmed, start_pos, include_exact) =
-                        trim_haystack(haystack, start_pos, end_pos);
-                    matches.push(self.smith_waterman_indices_one::<UNICODE>(
-                        trimmed,
-                        start_pos,
-                        index as u32,
-                        include_exact,
-                        max_typos_opt,
-                    ));
-                }
+        let original = haystack;
+        let normalized = self.normalizer.normalize_haystack(original);
+        let haystack = normalized.as_ref().map_or(original, |h| h.as_bytes());
+        let mut best: Option<MatchIndices> = None;
+        for (window_start, bonus) in self.path_windows(haystack).into_iter().flatten() {
+            let window = &haystack[window_start..];
+            let (matched, start_pos, end_pos) =
+                self.prefilter_haystack::<TYPOS, UNICODE>(window, max_typos);
+            if !matched {
+                break;
+            }
+
+            let (trimmed, start_pos, include_exact) =
+                trim_haystack::<UNICODE>(window, start_pos, end_pos);
+            let mut m = self.smith_waterman_indices_one::<UNICODE>(
+                trimmed,
+                window_start + start_pos,
+                index,
+                include_exact && window_start == 0,
+                max_typos_opt,
+            );
+            if window_start > 0 && m.score == 0 {
+                break;
+            }
+            m.score = m.score.saturating_add(bonus);
+            if best.as_ref().is_none_or(|best| m.score > best.score) {
+                best = Some(m);
             }
         }
-        matches
+        if let (Some(normalized), Some(m)) = (&normalized, &mut best) {
+            normalized.original_indices(original, &mut m.indices);
+        }
+        best
+    }
+
+    /// Byte offsets of the windows of the haystack to score, with the bonus for matching within
+    /// them. In path mode, a haystack with directories also scores its file name on its own,
+    /// starting from the separator so the file name's first char earns the delimiter bonus
+    #[inline(always)]
+    fn path_windows(&self, haystack: &[u8]) -> [Option<(usize, u16)>; 2] {
+        let Some(path_mode) = self.config.path_mode else {
+            return [Some((0, 0)), None];
+        };
+        let basename_start = path_mode.basename_start(haystack);
+        let bonus =
+            path_mode.basename_bonus_for(haystack.len() - basename_start, self.needle.len());
+        match basename_start {
+            0 => [Some((0, bonus)), None],
+            _ => [Some((0, 0)), Some((basename_start - 1, bonus))],
+        }
     }
 
     #[inline(always)]
@@ -254,6 +362,7 @@ where
             index,
             score,
             exact,
+            field: 0,
             #[cfg(feature = "match_end_col")]
             end_col: self
                 .smith_waterman
@@ -320,7 +429,9 @@ where
         scoring.guard_against_score_overflow(
             needle_len,
             scoring.max_per_char_bonus(),
-            scoring.max_one_time_bonus(),
+            scoring
+                .max_one_time_bonus()
+                .saturating_add(self.config.max_path_bonus()),
         );
     }
 }
@@ -329,17 +440,31 @@ where
 /// start position, and whether the window covers the full haystack (making it eligible for
 /// the exact match bonus)
 #[inline(always)]
-fn trim_haystack(haystack: &[u8], start_pos: usize, end_pos: usize) -> (&[u8], usize, bool) {
-    // substract 1 so that we add the delimiter bonus from the first char
-    // otherwise, we would never see it in the smith waterman
-    let start_pos = start_pos.saturating_sub(1);
+fn trim_haystack<const UNICODE: bool>(
+    haystack: &[u8],
+    start_pos: usize,
+    end_pos: usize,
+) -> (&[u8], usize, bool) {
+    // keep the char before the window so that we add the delimiter and capitalization bonuses
+    // from the first char, otherwise, we would never see it in the smith waterman. The unicode
+    // path classifies whole chars, so it needs every byte of a multi-byte char
+    let start_pos = match UNICODE {
+        true => (start_pos.saturating_sub(4)..start_pos)
+            .rev()
+            .find(|&idx| haystack[idx] & 0xC0 != 0x80)
+            .unwrap_or(start_pos.saturating_sub(1)),
+        false => start_pos.saturating_sub(1),
+    };
     let include_exact = start_pos == 0 && end_pos == haystack.len();
     (&haystack[start_pos..end_pos], start_pos, include_exact)
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{Config, Matcher, Scoring, SortStrategy};
+    use crate::{
+        CaseMatching, Config, Matcher, Matching, Normalization, PathMode, Pattern, Query, Scoring,
+        SortStrategy, UnicodeMatching,
+    };
 
     #[test]
     fn all_zero_scoring_does_not_divide_by_zero() {
@@ -349,10 +474,13 @@ mod tests {
             gap_open_penalty: 0,
             gap_extend_penalty: 0,
             prefix_bonus: 0,
+            prefix_bonus_decay: 0,
             capitalization_bonus: 0,
             matching_case_bonus: 0,
             exact_match_bonus: 0,
             delimiter_bonus: 0,
+            delimiters: None,
+            consecutive_bonus: 0,
         });
         Matcher::new("foo", &config).match_list(&["foobar"]);
     }
@@ -432,14 +560,366 @@ mod tests {
             gap_open_penalty: 0,
             gap_extend_penalty: 0,
             prefix_bonus: 0,
+            prefix_bonus_decay: 0,
             matching_case_bonus: 0,
             exact_match_bonus: 0,
             delimiter_bonus: 0,
+            delimiters: None,
+            consecutive_bonus: 0,
         });
         let matches = Matcher::new("BBBB", &config).match_list(&["aBaBaBaB"]);
         assert_eq!(matches[0].score, 4 * (40 + 40));
     }
 
+    #[test]
+    fn min_score_matches_filtering_results() {
+        let haystacks = [
+            "abc",
+            "xabc",
+            "a_b_c",
+            "ABC",
+            "aBc",
+            "alpha/beta/abc",
+            "xxaxxbxxc",
+            "ab",
+            "abcabc",
+            "ab_c",
+            "bc",
+        ];
+        for matching in [Matching::Fuzzy, Matching::Prefix, Matching::Substring] {
+            for max_typos in [None, Some(0), Some(1)] {
+                let config = Config::default()
+                    .matching(matching)
+                    .max_typos(max_typos)
+                    .sort(SortStrategy::IndexAsc);
+                let all = Matcher::new("abc", &config).match_list(&haystacks);
+                for min_score in [0, 1, 40, 60, 80, 100, 200] {
+                    let expected = all
+                        .iter()
+                        .filter(|m| m.score >= min_score)
+                        .copied()
+                        .collect::<Vec<_>>();
+                    let mut matcher = Matcher::new("abc", &config.clone().min_score(min_score));
+                    assert_eq!(
+                        matcher.match_list(&haystacks),
+                        expected,
+                        "matching={matching:?}, max_typos={max_typos:?}, min_score={min_score}"
+                    );
+                    let indices = matcher.match_list_indices(&haystacks);
+                    assert!(
+                        indices
+                            .iter()
+                            .map(|m| m.index)
+                            .eq(expected.iter().map(|m| m.index))
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn min_score_skips_short_haystacks() {
+        let config = Config::default();
+        let score = Matcher::new("ab", &config).match_list(&["ab"])[0].score;
+        let scoring = &config.scoring;
+        // The exact match scores as high as any 2 byte haystack can
+        assert_eq!(scoring.min_haystack_len_for_score(score), 2);
+        assert_eq!(scoring.min_haystack_len_for_score(0), 0);
+
+        let matches = Matcher::new("ab", &config.min_score(score)).match_list(&["ab", "xab"]);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].index, 0);
+    }
+
+    #[test]
+    fn pattern_min_score_overrides_config() {
+        let haystacks = ["foo_bar", "fxoxo"];
+        let config = Config::default().sort(SortStrategy::IndexAsc);
+        let weak = Matcher::new("foo", &config).match_list(&haystacks)[1].score;
+
+        let patterns = [Pattern::from("foo").min_score(Some(weak + 1))];
+        let matches = Matcher::from_patterns(&patterns, &config).match_list(&haystacks);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].index, 0);
+
+        let patterns = [Pattern::from("foo").min_score(Some(0))];
+        let config = config.min_score(u16::MAX);
+        let matches = Matcher::from_patterns(&patterns, &config).match_list(&haystacks);
+        assert_eq!(matches.len(), 2);
+    }
+
+    #[test]
+    fn min_score_applies_to_combined_score() {
+        let haystacks = ["foo_bar", "xfoo ybar", "foo baz", "fbar", "foo bar qux"];
+        let config = Config::default().sort(SortStrategy::IndexAsc);
+        let all = Matcher::from_query("foo bar", &config).match_list(&haystacks);
+        assert_eq!(
+            all.iter().map(|m| m.score).collect::<Vec<_>>(),
+            [112, 96, 112]
+        );
+        let matches = Matcher::from_query("foo bar", &config.clone().min_score(96))
+            .match_list(&haystacks[..2]);
+        assert_eq!(matches.len(), 2);
+
+        for query in ["foo bar", "foo | fbar bar", "foo !(baz qux) bar"] {
+            let all = Matcher::from_query_ast(&Query::parse(query), &config).match_list(&haystacks);
+            for min_score in [0, 50, 96, 100, 112, 113] {
+                let expected = all
+                    .iter()
+                    .filter(|m| m.score >= min_score)
+                    .copied()
+                    .collect::<Vec<_>>();
+                let config = config.clone().min_score(min_score);
+                let mut matcher = Matcher::from_query_ast(&Query::parse(query), &config);
+                assert_eq!(
+                    matcher.match_list(&haystacks),
+                    expected,
+                    "query={query:?}, min_score={min_score}"
+                );
+                assert!(matcher.match_iter(haystacks).eq(expected.iter().copied()));
+                let indices = matcher.match_list_indices(&haystacks);
+                assert!(
+                    indices
+                        .iter()
+                        .map(|m| m.index)
+                        .eq(expected.iter().map(|m| m.index))
+                );
+            }
+        }
+    }
+
+    /// Pads the haystack past the max Smith-Waterman haystack length, so that it's scored by
+    /// the greedy fallback
+    fn past_max_haystack_len(haystack: &str) -> String {
+        format!("{haystack}{}", "x".repeat(1100))
+    }
+
+    #[test]
+    fn scoring_variants_match_on_every_path() {
+        use crate::r#const::{DELIMITER_BONUS, MATCH_SCORE, MATCHING_CASE_BONUS, PREFIX_BONUS};
+
+        let delimiters = Scoring {
+            delimiters: Some(vec![(b'/', 8), (b'-', 2)]),
+            ..Scoring::default()
+        };
+        let decay = Scoring {
+            prefix_bonus_decay: 2,
+            ..Scoring::default()
+        };
+        let consecutive = |consecutive_bonus| Scoring {
+            consecutive_bonus,
+            ..Scoring::default()
+        };
+        let char_score = MATCH_SCORE + MATCHING_CASE_BONUS;
+        // The needle matches the end of each haystack
+        let cases = [
+            // Each delimiter earns its own bonus, and unlisted delimiters none
+            (&delimiters, "b", "a/b", char_score + 8),
+            (&delimiters, "b", "a-b", char_score + 2),
+            (&delimiters, "b", "a_b", char_score),
+            (&delimiters, "b", "a.b", char_score),
+            // The prefix bonus decays with the distance from the start of the haystack
+            (&decay, "foo", "xfoo", 3 * char_score + PREFIX_BONUS - 2),
+            (&decay, "foo", "xxxxxxxxfoo", 3 * char_score),
+            // The run inherits the delimiter bonus of "b" when it's larger than the consecutive
+            // bonus
+            (
+                &consecutive(6),
+                "bcd",
+                "x_bcd",
+                3 * char_score + DELIMITER_BONUS + 2 * 6,
+            ),
+            (
+                &consecutive(2),
+                "bcd",
+                "x_bcd",
+                3 * char_score + 3 * DELIMITER_BONUS,
+            ),
+        ];
+        for (scoring, needle, haystack, expected) in cases {
+            let unicode = |s: &str| format!("{}é", &s[..s.len() - 1]);
+            for (needle, haystack) in [
+                (needle.to_string(), haystack.to_string()),
+                (unicode(needle), unicode(haystack)),
+                (needle.to_string(), past_max_haystack_len(haystack)),
+            ] {
+                for matching in [Matching::Fuzzy, Matching::Substring] {
+                    let config = Config::default()
+                        .matching(matching)
+                        .scoring(scoring.clone())
+                        .max_typos(None);
+                    let matches = Matcher::new(&needle, &config).match_list(&[&haystack]);
+                    assert_eq!(
+                        matches[0].score, expected,
+                        "{needle} on {haystack} {matching:?} with {scoring:?}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn prefix_bonus_decays_with_distance() {
+        let scoring = Scoring {
+            prefix_bonus_decay: 2,
+            ..Scoring::default()
+        };
+        let order = |scoring: &Scoring| {
+            let config = Config::default()
+                .scoring(scoring.clone())
+                .sort(SortStrategy::ScoreThenIndexAsc);
+            Matcher::new("foo", &config)
+                .match_list(&["xxxxxxxxfoo", "xfoo"])
+                .iter()
+                .map(|m| m.index)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(order(&Scoring::default()), vec![0, 1]);
+        assert_eq!(order(&scoring), vec![1, 0]);
+    }
+
+    #[test]
+    fn consecutive_bonus_prefers_tight_matches() {
+        let scoring = Scoring {
+            consecutive_bonus: 6,
+            ..Scoring::default()
+        };
+        let order = |scoring: &Scoring| {
+            let config = Config::default()
+                .scoring(scoring.clone())
+                .sort(SortStrategy::ScoreThenIndexAsc);
+            Matcher::new("abc", &config)
+                .match_list(&["x_a_b_c", "xxxxabc"])
+                .iter()
+                .map(|m| m.index)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(order(&Scoring::default()), vec![0, 1]);
+        assert_eq!(order(&scoring), vec![1, 0]);
+    }
+
+    #[test]
+    fn diacritics_normalization_reports_original_indices() {
+        assert!(
+            Matcher::new("cafe", &Config::default())
+                .match_list(&["Café.md"])
+                .is_empty()
+        );
+
+        let config = Config::default().normalization(Normalization::Diacritics);
+        for max_typos in [Some(0), Some(1), None] {
+            for unicode in [UnicodeMatching::Smart, UnicodeMatching::Always] {
+                let config = config.clone().max_typos(max_typos).unicode(unicode);
+                let matches = Matcher::new("cafe", &config).match_list_indices(&["Café.md"]);
+                assert_eq!(matches[0].indices, vec![4, 3, 2, 1, 0], "{config:?}");
+
+                let matches = Matcher::new("cafe", &config).match_list(&["cafe\u{301}"]);
+                assert!(matches[0].exact, "{config:?}");
+
+                // The needle is normalized too, matching haystacks with or without diacritics
+                let matches = Matcher::new("ελλάδα", &config).match_list(&["ελλαδα", "ελλάδα"]);
+                assert_eq!(matches.len(), 2, "{config:?}");
+                assert_eq!(matches[0].score, matches[1].score);
+            }
+        }
+    }
+
+    #[test]
+    fn full_case_folding_matches_expansions() {
+        let config = Config::default().casing(CaseMatching::Ignore);
+        for max_typos in [Some(0), Some(1), None] {
+            for unicode in [UnicodeMatching::Smart, UnicodeMatching::Always] {
+                let config = config.clone().max_typos(max_typos).unicode(unicode);
+                let score = |needle: &str, haystack: &str| {
+                    Matcher::new(needle, &config).match_list(&[haystack])[0].score
+                };
+
+                // Both "s" of the folded "ß" highlight both of its bytes once
+                let matches = Matcher::new("strasse", &config).match_list_indices(&["Straße"]);
+                assert_eq!(matches[0].indices, vec![6, 5, 4, 3, 2, 1, 0], "{config:?}");
+                assert_eq!(score("straße", "STRASSE"), score("strasse", "STRASSE"));
+
+                // Greek final sigma and the Kelvin sign
+                assert_eq!(score("ΟΔΥΣΣΕΥΣ", "οδυσσευς"), score("ΟΔΥΣΣΕΥΣ", "οδυσσευσ"));
+                assert_eq!(score("kelvin", "\u{212A}elvin"), score("kelvin", "kelvin"));
+            }
+        }
+
+        // Case-sensitive matching doesn't fold
+        let config = Config::default().casing(CaseMatching::Respect);
+        assert!(
+            Matcher::new("strasse", &config)
+                .match_list(&["straße"])
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn unicode_boundary_bonuses_apply_to_every_script() {
+        use crate::r#const::{CAPITALIZATION_BONUS, DELIMITER_BONUS};
+
+        let score = |needle: &str, haystack: &str| {
+            Matcher::new(needle, &Config::default()).match_list(&[haystack])[0].score
+        };
+        // Non-ASCII delimiters, compared to a non-delimiter of the same byte length
+        assert_eq!(score("ф", "а、ф"), score("ф", "а日ф") + DELIMITER_BONUS);
+        assert_eq!(
+            score("фа", "путь—файл"),
+            score("фа", "путьфайл") + DELIMITER_BONUS
+        );
+        // Non-ASCII uppercase letters after lowercase letters
+        assert_eq!(score("É", "ыÉ"), score("É", "ЫÉ") + CAPITALIZATION_BONUS);
+        // Combining marks aren't delimiters
+        assert_eq!(score("ф", "а\u{301}ф"), score("ф", "аыф"));
+    }
+
+    #[test]
+    fn path_mode_prefers_file_names() {
+        let haystacks = ["main_tests/foo/bar.rs", "src/main_window.rs", "src/main.rs"];
+        let order = |config: &Config| {
+            Matcher::new("main", config)
+                .match_list(&haystacks)
+                .iter()
+                .map(|m| m.index)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(order(&Config::default()), vec![0, 1, 2]);
+
+        let config = Config::default().path_mode(Some(PathMode::default()));
+        assert_eq!(order(&config), vec![2, 1, 0]);
+        assert_eq!(order(&config.clone().max_typos(None)), vec![2, 1, 0]);
+
+        let matches = Matcher::new("main", &config).match_list(&haystacks);
+        let indices = Matcher::new("main", &config).match_list_indices(&haystacks);
+        assert_eq!(indices[0].indices, vec![7, 6, 5, 4]);
+        assert!(
+            matches
+                .iter()
+                .zip(&indices)
+                .all(|(m, i)| m.score == i.score)
+        );
+    }
+
+    #[test]
+    fn path_mode_prefers_path_separators() {
+        let score = |config: &Config, haystack: &str| {
+            Matcher::new("b", config).match_list(&[haystack])[0].score
+        };
+        let config = Config::default();
+        assert_eq!(score(&config, "a/b"), score(&config, "a_b"));
+        assert_eq!(score(&config, "a\\b"), score(&config, "a_b"));
+
+        let config = config.path_mode(Some(PathMode::default()));
+        assert!(score(&config, "a/b") > score(&config, "a_b"));
+        assert_eq!(score(&config, "a\\b"), score(&config, "a_b"));
+
+        let config = config.path_mode(Some(PathMode {
+            backslash: true,
+            ..PathMode::default()
+        }));
+        assert_eq!(score(&config, "a\\b"), score(&config, "a/b"));
+    }
+
     #[test]
     fn unsorted_output_preserves_candidate_order() {
         let haystacks = ["foo", "nomatch", "xfoo", "f_o_o", "bar"];
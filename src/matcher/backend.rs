@@ -3,11 +3,20 @@
 use super::algo::{MatcherImpl, Specialized};
 use crate::{Config, Match, MatchIndices};
 
-#[cfg(target_arch = "aarch64")]
-use crate::literal::LiteralNEON;
-use crate::literal::LiteralScalar;
+#[cfg(all(feature = "regex", target_arch = "aarch64"))]
+use crate::literal::RegexNEON;
+#[cfg(feature = "regex")]
+use crate::literal::RegexScalar;
 #[cfg(target_arch = "x86_64")]
-use crate::literal::{LiteralAVX, LiteralAVX512, LiteralSSE};
+use crate::literal::{
+    GlobAVX, GlobAVX512, GlobSSE, InitialsAVX, InitialsAVX512, InitialsSSE, LiteralAVX,
+    LiteralAVX512, LiteralSSE, PathSegmentsAVX, PathSegmentsAVX512, PathSegmentsSSE,
+};
+#[cfg(target_arch = "aarch64")]
+use crate::literal::{GlobNEON, InitialsNEON, LiteralNEON, PathSegmentsNEON};
+use crate::literal::{GlobScalar, InitialsScalar, LiteralScalar, PathSegmentsScalar};
+#[cfg(all(feature = "regex", target_arch = "x86_64"))]
+use crate::literal::{RegexAVX, RegexAVX512, RegexSSE};
 
 #[cfg(target_arch = "aarch64")]
 use crate::prefilter::backend::PrefilterNEON;
@@ -76,6 +85,68 @@ pub enum MatcherBackend {
     #[cfg(target_arch = "aarch64")]
     LiteralNEON(LiteralNEON),
     LiteralScalar(LiteralScalar),
+
+    // Glob matching backends, selected when `Config::matching` is `Matching::Glob`
+    #[cfg(target_arch = "x86_64")]
+    GlobAVX512(GlobAVX512),
+    #[cfg(target_arch = "x86_64")]
+    GlobAVX(GlobAVX),
+    #[cfg(target_arch = "x86_64")]
+    GlobSSE(GlobSSE),
+    #[cfg(target_arch = "aarch64")]
+    GlobNEON(GlobNEON),
+    GlobScalar(GlobScalar),
+
+    // Initials matching backends, selected when `Config::matching` is `Matching::Initials`
+    #[cfg(target_arch = "x86_64")]
+    InitialsAVX512(InitialsAVX512),
+    #[cfg(target_arch = "x86_64")]
+    InitialsAVX(InitialsAVX),
+    #[cfg(target_arch = "x86_64")]
+    InitialsSSE(InitialsSSE),
+    #[cfg(target_arch = "aarch64")]
+    InitialsNEON(InitialsNEON),
+    InitialsScalar(InitialsScalar),
+
+    // Path segments matching backends, selected when `Config::matching` is
+    // `Matching::PathSegments`
+    #[cfg(target_arch = "x86_64")]
+    PathSegmentsAVX512(PathSegmentsAVX512),
+    #[cfg(target_arch = "x86_64")]
+    PathSegmentsAVX(PathSegmentsAVX),
+    #[cfg(target_arch = "x86_64")]
+    PathSegmentsSSE(PathSegmentsSSE),
+    #[cfg(target_arch = "aarch64")]
+    PathSegmentsNEON(PathSegmentsNEON),
+    PathSegmentsScalar(PathSegmentsScalar),
+
+    // Regex matching backends, selected when `Config::matching` is `Matching::Regex`
+    #[cfg(all(feature = "regex", target_arch = "x86_64"))]
+    RegexAVX512(RegexAVX512),
+    #[cfg(all(feature = "regex", target_arch = "x86_64"))]
+    RegexAVX(RegexAVX),
+    #[cfg(all(feature = "regex", target_arch = "x86_64"))]
+    RegexSSE(RegexSSE),
+    #[cfg(all(feature = "regex", target_arch = "aarch64"))]
+    RegexNEON(RegexNEON),
+    #[cfg(feature = "regex")]
+    RegexScalar(RegexScalar),
+}
+
+impl MatcherBackend {
+    /// Whether this is a literal matching backend, which is cheaper than fuzzy matching
+    pub fn is_literal(&self) -> bool {
+        match self {
+            #[cfg(target_arch = "x86_64")]
+            MatcherBackend::LiteralAVX512(_)
+            | MatcherBackend::LiteralAVX(_)
+            | MatcherBackend::LiteralSSE(_) => true,
+            #[cfg(target_arch = "aarch64")]
+            MatcherBackend::LiteralNEON(_) => true,
+            MatcherBackend::LiteralScalar(_) => true,
+            _ => false,
+        }
+    }
 }
 
 /// Implements [`Specialized`] for one concrete backend, attaching the
@@ -169,3 +240,161 @@ impl_specialized!(PrefilterNEON, SmithWatermanNEONU8, target_feature = "neon");
 impl_specialized!(PrefilterNEON, SmithWatermanNEON, target_feature = "neon");
 impl_specialized!(PrefilterScalar, SmithWatermanScalarU8);
 impl_specialized!(PrefilterScalar, SmithWatermanScalar);
+
+#[cfg(test)]
+mod backend_parity {
+    use super::*;
+    use crate::literal::LiteralScalar;
+    use crate::normalize::Normalizer;
+    use crate::smith_waterman::score_fits_in_u8;
+    use crate::{CaseLocale, CaseMatching, Matching};
+
+    type Probe = unsafe fn(&str, &str, &Config) -> Option<(u16, Vec<u32>)>;
+
+    /// Runs one needle/haystack through a specialized backend, normalizing the needle and
+    /// choosing `UNICODE` the same way the real dispatch does
+    unsafe fn probe<T: Specialized>(
+        needle: &str,
+        haystack: &str,
+        config: &Config,
+    ) -> Option<(u16, Vec<u32>)> {
+        let needle = Normalizer::for_config(config, needle).normalize_needle(needle);
+        let mut matcher = unsafe { T::build(&needle, config) };
+        let m = match config.unicode.respects_unicode_for(&needle) {
+            true => unsafe { matcher.match_one_indices::<0, true, &str>(haystack, 0) },
+            false => unsafe { matcher.match_one_indices::<0, false, &str>(haystack, 0) },
+        };
+        m.map(|m| (m.score, m.indices))
+    }
+
+    /// Every backend available on this CPU for the matching mode, scalar first
+    fn backends(matching: Matching, use_u8: bool) -> Vec<(&'static str, Probe)> {
+        let mut backends = vec![];
+        let mut add = |label, available, probe: Probe| {
+            if available {
+                backends.push((label, probe));
+            }
+        };
+        if matching.is_fuzzy() {
+            add("Scalar", true, probe::<MatcherScalar>);
+            add("Scalar-u8", use_u8, probe::<MatcherScalarU8>);
+            #[cfg(target_arch = "x86_64")]
+            {
+                add("SSE", MatcherSSE::is_available(), probe::<MatcherSSE>);
+                add("AVX", MatcherAVX::is_available(), probe::<MatcherAVX>);
+                add(
+                    "AVX-512",
+                    MatcherAVX512::is_available(),
+                    probe::<MatcherAVX512>,
+                );
+                add(
+                    "SSE-u8",
+                    use_u8 && MatcherSSEU8::is_available(),
+                    probe::<MatcherSSEU8>,
+                );
+                add(
+                    "AVX-u8",
+                    use_u8 && MatcherAVXU8::is_available(),
+                    probe::<MatcherAVXU8>,
+                );
+                add(
+                    "AVX-512-u8",
+                    use_u8 && MatcherAVX512U8::is_available(),
+                    probe::<MatcherAVX512U8>,
+                );
+            }
+            #[cfg(target_arch = "aarch64")]
+            {
+                add("NEON", MatcherNEON::is_available(), probe::<MatcherNEON>);
+                add(
+                    "NEON-u8",
+                    use_u8 && MatcherNEONU8::is_available(),
+                    probe::<MatcherNEONU8>,
+                );
+            }
+        } else {
+            add("LiteralScalar", true, probe::<LiteralScalar>);
+            #[cfg(target_arch = "x86_64")]
+            {
+                use crate::literal::{LiteralAVX, LiteralAVX512, LiteralSSE};
+                add(
+                    "LiteralSSE",
+                    LiteralSSE::is_available(),
+                    probe::<LiteralSSE>,
+                );
+                add(
+                    "LiteralAVX",
+                    LiteralAVX::is_available(),
+                    probe::<LiteralAVX>,
+                );
+                add(
+                    "LiteralAVX-512",
+                    LiteralAVX512::is_available(),
+                    probe::<LiteralAVX512>,
+                );
+            }
+            #[cfg(target_arch = "aarch64")]
+            {
+                use crate::literal::LiteralNEON;
+                add(
+                    "LiteralNEON",
+                    LiteralNEON::is_available(),
+                    probe::<LiteralNEON>,
+                );
+            }
+        }
+        backends
+    }
+
+    #[test]
+    fn turkic_case_folding_on_every_backend() {
+        let haystacks = ["istanbul", "İstanbul", "ıstanbul", "Istanbul"];
+        let cases: &[(&str, [bool; 4])] = &[
+            ("istanbul", [true, true, false, false]),
+            ("İSTANBUL", [true, true, false, false]),
+            ("ıstanbul", [false, false, true, true]),
+            ("ISTANBUL", [false, false, true, true]),
+        ];
+
+        for matching in [Matching::Fuzzy, Matching::Substring] {
+            let config = Config::default()
+                .matching(matching)
+                .casing(CaseMatching::Ignore)
+                .case_locale(CaseLocale::Turkic);
+            for &(needle, want) in cases {
+                let use_u8 = score_fits_in_u8(needle.len(), &config.scoring);
+                let backends = backends(matching, use_u8);
+                for (haystack, want) in haystacks.into_iter().zip(want) {
+                    let expected = unsafe { (backends[0].1)(needle, haystack, &config) };
+                    assert_eq!(
+                        expected.is_some(),
+                        want,
+                        "needle={needle:?} haystack={haystack:?} {matching:?}"
+                    );
+                    for &(label, probe) in &backends[1..] {
+                        assert_eq!(
+                            unsafe { probe(needle, haystack, &config) },
+                            expected,
+                            "{label} mismatch: needle={needle:?} haystack={haystack:?} {matching:?}"
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn turkic_case_folding_reports_original_indices() {
+        let config = Config::default()
+            .casing(CaseMatching::Ignore)
+            .case_locale(CaseLocale::Turkic);
+        let indices = |needle, haystack| unsafe {
+            probe::<MatcherScalar>(needle, haystack, &config).map(|(_, indices)| indices)
+        };
+        assert_eq!(indices("iz", "İzmir"), Some(vec![2, 1, 0]));
+        assert_eq!(indices("ız", "Izmir"), Some(vec![1, 0]));
+        // The locale-independent folding pairs "i" with "I"
+        let config = config.case_locale(CaseLocale::Unicode);
+        assert!(unsafe { probe::<MatcherScalar>("istanbul", "Istanbul", &config) }.is_some());
+    }
+}
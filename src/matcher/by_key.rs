@@ -0,0 +1,191 @@
+use std::ops::Range;
+
+use super::{CHUNK_SIZE, Matcher};
+use crate::Match;
+
+impl Matcher {
+    /// Matches a list of items by the haystack returned from `key`, returning a list of
+    /// [`Match`] values where [`Match::index`] refers to the item. Equivalent to calling
+    /// [`Matcher::match_list`] on the keys, without collecting every key up front.
+    ///
+    /// ```
+    /// use frizbee::{Config, Matcher};
+    ///
+    /// struct Symbol {
+    ///     name: String,
+    ///     line: usize,
+    /// }
+    ///
+    /// let symbols = vec![
+    ///     Symbol { name: "fooBar".to_string(), line: 1 },
+    ///     Symbol { name: "prelude".to_string(), line: 4 },
+    /// ];
+    /// let mut matcher = Matcher::new("fbr", &Config::default());
+    /// let matches = matcher.match_list_by(&symbols, |symbol| &symbol.name);
+    /// assert_eq!(matches.len(), 1);
+    /// assert_eq!(symbols[matches[0].index as usize].line, 1);
+    /// ```
+    pub fn match_list_by<T, F>(&mut self, items: &[T], key: F) -> Vec<Match>
+    where
+        F: Fn(&T) -> &str,
+    {
+        Self::guard_against_haystack_overflow(items.len(), 0);
+
+        let mut matches = vec![];
+        let mut keys = Vec::with_capacity(items.len().min(CHUNK_SIZE));
+        for start in (0..items.len()).step_by(CHUNK_SIZE) {
+            let end = (start + CHUNK_SIZE).min(items.len());
+            self.match_keys_chunk(items, &key, start..end, &mut keys, &mut matches);
+        }
+        self.sort_matches(&mut matches);
+        matches
+    }
+
+    /// Parallel version of [`Matcher::match_list_by`] on multiple real threads, with the same
+    /// chunking as [`Matcher::match_list_parallel`]. The `threads` must be >0.
+    pub fn match_list_parallel_by<T, F>(
+        &mut self,
+        items: &[T],
+        key: F,
+        threads: usize,
+    ) -> Vec<Match>
+    where
+        T: Sync,
+        F: Fn(&T) -> &str + Sync,
+    {
+        let threads = Self::parallel_threads(items.len(), threads);
+        if items.is_empty() || self.patterns.is_empty() || threads == 1 {
+            return self.match_list_by(items, key);
+        }
+
+        let (matches, _) = self.match_chunks_parallel(
+            items.len(),
+            threads,
+            |matcher, range, matches| {
+                let mut keys = Vec::with_capacity(range.len());
+                matcher.match_keys_chunk(items, &key, range, &mut keys, matches);
+            },
+            || false,
+            |_| {},
+        );
+        matches
+    }
+
+    /// Matches a list of items by the haystack returned from `key` (see
+    /// [`Matcher::match_list_by`]), returning each matched item alongside its [`Match`]
+    pub fn match_items_by<'a, T, F>(&mut self, items: &'a [T], key: F) -> Vec<(&'a T, Match)>
+    where
+        F: Fn(&T) -> &str,
+    {
+        self.match_list_by(items, key)
+            .into_iter()
+            .map(|m| (&items[m.index as usize], m))
+            .collect()
+    }
+
+    /// Parallel version of [`Matcher::match_items_by`] (see
+    /// [`Matcher::match_list_parallel_by`])
+    pub fn match_items_parallel_by<'a, T, F>(
+        &mut self,
+        items: &'a [T],
+        key: F,
+        threads: usize,
+    ) -> Vec<(&'a T, Match)>
+    where
+        T: Sync,
+        F: Fn(&T) -> &str + Sync,
+    {
+        self.match_list_parallel_by(items, key, threads)
+            .into_iter()
+            .map(|m| (&items[m.index as usize], m))
+            .collect()
+    }
+
+    /// Gathers the keys of the items in `range` into the reusable `keys` buffer, and matches
+    /// them in index order
+    fn match_keys_chunk<'a, T, F>(
+        &mut self,
+        items: &'a [T],
+        key: &F,
+        range: Range<usize>,
+        keys: &mut Vec<&'a str>,
+        matches: &mut Vec<Match>,
+    ) where
+        F: Fn(&T) -> &str,
+    {
+        let start = range.start as u32;
+        keys.clear();
+        keys.extend(items[range].iter().map(key));
+        self.match_list_into(keys, start, matches);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Config, Matcher, Pattern, SortStrategy};
+
+    #[derive(Debug, PartialEq)]
+    struct Item {
+        name: String,
+        id: usize,
+    }
+
+    fn items() -> Vec<Item> {
+        let names = [
+            "Ada Lovelace",
+            "Alan Turing",
+            "Grace Hopper",
+            "Edsger Dijkstra",
+        ];
+        (0..5000)
+            .map(|id| Item {
+                name: format!("{} #{id}", names[id % names.len()]),
+                id,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn match_list_by_matches_keys() {
+        let items = items();
+        let names = items
+            .iter()
+            .map(|item| item.name.as_str())
+            .collect::<Vec<_>>();
+        for query in ["al", "al !alan", ""] {
+            for sort in [SortStrategy::ScoreThenIndexAsc, SortStrategy::IndexDesc] {
+                let config = Config::default().sort(sort);
+                let mut matcher = Matcher::from_patterns(&Pattern::parse_query(query), &config);
+                let expected = matcher.match_list(&names);
+
+                let matches = matcher.match_list_by(&items, |item| &item.name);
+                assert_eq!(matches, expected, "query={query:?}, sort={sort:?}");
+                for threads in [1, 2, 3] {
+                    let matches =
+                        matcher.match_list_parallel_by(&items, |item| &item.name, threads);
+                    assert_eq!(
+                        matches, expected,
+                        "query={query:?}, sort={sort:?}, threads={threads}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn match_items_by_pairs_items_with_matches() {
+        let items = items();
+        let mut matcher = Matcher::new("ada", &Config::default());
+        let expected = matcher.match_list_by(&items, |item| &item.name);
+
+        let pairs = matcher.match_items_by(&items, |item| &item.name);
+        assert_eq!(pairs.len(), expected.len());
+        for ((item, m), expected) in pairs.iter().zip(&expected) {
+            assert_eq!(m, expected);
+            assert_eq!(item.id, m.index as usize);
+        }
+
+        let parallel = matcher.match_items_parallel_by(&items, |item| &item.name, 2);
+        assert_eq!(parallel, pairs);
+    }
+}
@@ -0,0 +1,156 @@
+use std::ops::Range;
+use std::time::Instant;
+
+use super::{CHUNK_SIZE, Matcher};
+use crate::Match;
+
+impl Matcher {
+    /// Matches a list of haystacks in 2048 item chunks until every haystack has been matched or
+    /// the `deadline` passes, returning the matches found so far, ordered according to
+    /// [`crate::Config::sort`], and the range of haystacks they cover.
+    ///
+    /// The first chunk is always matched, so every call makes progress even when the deadline
+    /// has already passed. When the range ends before `haystacks.len()`, continue from its end
+    /// with [`Matcher::match_list_with_deadline_from`], and combine the results with
+    /// [`crate::k_merge::k_merge_matches_by_sort`].
+    ///
+    /// ```
+    /// use std::time::{Duration, Instant};
+    ///
+    /// use frizbee::{Config, Matcher};
+    /// use frizbee::k_merge::k_merge_matches_by_sort;
+    ///
+    /// let haystacks = vec!["fooBar"; 10_000];
+    /// let config = Config::default();
+    /// let mut matcher = Matcher::new("fbr", &config);
+    ///
+    /// let mut runs = vec![];
+    /// let mut start = 0;
+    /// while start < haystacks.len() {
+    ///     let deadline = Instant::now() + Duration::from_millis(4);
+    ///     let (matches, covered) = matcher.match_list_with_deadline_from(&haystacks, start, deadline);
+    ///     runs.push(matches);
+    ///     start = covered.end;
+    /// }
+    /// let matches = k_merge_matches_by_sort(runs, config.sort);
+    /// assert_eq!(matches, matcher.match_list(&haystacks));
+    /// ```
+    pub fn match_list_with_deadline<S: AsRef<str>>(
+        &mut self,
+        haystacks: &[S],
+        deadline: Instant,
+    ) -> (Vec<Match>, Range<usize>) {
+        self.match_list_with_deadline_from(haystacks, 0, deadline)
+    }
+
+    /// Resumes a [`Matcher::match_list_with_deadline`] call from the haystack at `start`,
+    /// typically the end of the range covered by the previous call. Indices of the returned
+    /// matches refer to the full list of haystacks.
+    pub fn match_list_with_deadline_from<S: AsRef<str>>(
+        &mut self,
+        haystacks: &[S],
+        start: usize,
+        deadline: Instant,
+    ) -> (Vec<Match>, Range<usize>) {
+        Self::guard_against_haystack_overflow(haystacks.len(), 0);
+        assert!(
+            start <= haystacks.len(),
+            "start out of bounds: {start} > {}",
+            haystacks.len()
+        );
+
+        let mut matches = vec![];
+        let mut end = start;
+        while end < haystacks.len() {
+            let chunk_end = (end + CHUNK_SIZE).min(haystacks.len());
+            self.match_list_into(&haystacks[end..chunk_end], end as u32, &mut matches);
+            end = chunk_end;
+
+            if Instant::now() >= deadline {
+                break;
+            }
+        }
+
+        self.sort_matches(&mut matches);
+        (matches, start..end)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::CHUNK_SIZE;
+    use crate::k_merge::k_merge_matches_by_sort;
+    use crate::{Config, Matcher, SortStrategy};
+
+    fn haystacks() -> Vec<String> {
+        (0..10_000)
+            .map(|index| match index % 7 {
+                0 => format!("needle_{index}"),
+                3 => format!("n_ee_dle/{index}"),
+                _ => format!("haystack_{index}"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn expired_deadline_matches_one_chunk_per_call() {
+        let haystacks = haystacks();
+        for sort in [
+            SortStrategy::ScoreThenIndexAsc,
+            SortStrategy::ScoreThenIndexDesc,
+            SortStrategy::IndexAsc,
+            SortStrategy::IndexDesc,
+        ] {
+            let config = Config::default().sort(sort);
+            let mut matcher = Matcher::new("needle", &config);
+            let expired = Instant::now();
+
+            let mut runs = vec![];
+            let mut start = 0;
+            while start < haystacks.len() {
+                let (matches, covered) =
+                    matcher.match_list_with_deadline_from(&haystacks, start, expired);
+                assert_eq!(covered.start, start);
+                assert_eq!(covered.len(), CHUNK_SIZE.min(haystacks.len() - start));
+                assert_eq!(
+                    matches,
+                    Matcher::new("needle", &config)
+                        .match_list(&haystacks[covered.clone()])
+                        .into_iter()
+                        .map(|mut m| {
+                            m.index += start as u32;
+                            m
+                        })
+                        .collect::<Vec<_>>(),
+                    "sort={sort:?}, covered={covered:?}"
+                );
+                runs.push(matches);
+                start = covered.end;
+            }
+
+            assert_eq!(runs.len(), haystacks.len().div_ceil(CHUNK_SIZE));
+            assert_eq!(
+                k_merge_matches_by_sort(runs, sort),
+                matcher.match_list(&haystacks),
+                "sort={sort:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn distant_deadline_matches_everything() {
+        let haystacks = haystacks();
+        let mut matcher = Matcher::new("needle", &Config::default());
+        let deadline = Instant::now() + Duration::from_secs(3600);
+        let (matches, covered) = matcher.match_list_with_deadline(&haystacks, deadline);
+        assert_eq!(covered, 0..haystacks.len());
+        assert_eq!(matches, matcher.match_list(&haystacks));
+
+        let (matches, covered) =
+            matcher.match_list_with_deadline_from(&haystacks, haystacks.len(), deadline);
+        assert_eq!(covered, haystacks.len()..haystacks.len());
+        assert!(matches.is_empty());
+    }
+}
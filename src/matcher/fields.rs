@@ -0,0 +1,934 @@
+use std::ops::Range;
+
+use super::multi::{CompiledPattern, groups_mut, keep_best, retain_min_score};
+use super::query::CompiledQuery;
+use super::{CHUNK_SIZE, CompiledPatterns, Matcher};
+use crate::pattern::{split_atoms, unknown_field_prefix};
+use crate::{Field, FieldScoring, Match, Pattern, TooManyFields, UnknownField};
+
+impl Matcher {
+    pub fn fields(&self) -> &[Field] {
+        &self.fields
+    }
+
+    /// Sets the fields of each item, matched with [`Matcher::match_list_fields`]. At most
+    /// [`TooManyFields::MAX`] fields are supported, as [`Match::field`] is a `u16`, returning an
+    /// error and keeping the previous fields otherwise.
+    ///
+    /// A matcher built with [`Matcher::from_query`] parses its query again, scoping the atoms
+    /// prefixed with a field's name to that field (see [`crate::Pattern::parse_with_fields`]).
+    /// Otherwise, parse the patterns with the fields yourself.
+    pub fn set_fields(&mut self, fields: &[Field]) -> Result<(), TooManyFields> {
+        if fields.len() > TooManyFields::MAX {
+            return Err(TooManyFields {
+                count: fields.len(),
+            });
+        }
+        if self.fields == fields {
+            return Ok(());
+        }
+        self.fields = fields.to_vec();
+        if let Some(query) = &self.query_text {
+            self.raw_patterns = Pattern::parse_query_with_fields(query, &self.fields);
+            self.unknown_field_prefixes = match self.fields.is_empty() {
+                true => vec![],
+                false => split_atoms(query)
+                    .into_iter()
+                    .filter_map(|atom| unknown_field_prefix(atom, &self.fields))
+                    .collect(),
+            };
+            self.patterns = Self::build_patterns(&self.raw_patterns, &self.config);
+        }
+        Ok(())
+    }
+
+    /// Checks that every pattern scoped to a field (see [`crate::Pattern::field`]) names one of
+    /// the [`Matcher::fields`], returning the first unknown field otherwise. This catches
+    /// patterns built with an unknown [`crate::Pattern::field`], which are matched against
+    /// every field in [`Matcher::match_list_fields`], as if unscoped.
+    ///
+    /// Parsing only scopes atoms to known fields, matching any other prefix as part of the
+    /// needle. For a matcher built with [`Matcher::from_query`], the atoms whose prefix is
+    /// shaped like a field name, e.g. `nmae:foo`, are reported as well, so callers can warn
+    /// about typos.
+    ///
+    /// ```
+    /// use frizbee::{Config, Field, Matcher, Pattern};
+    ///
+    /// let patterns = [Pattern::from("foo").field(Some("nmae"))];
+    /// let mut matcher = Matcher::from_patterns(&patterns, &Config::default());
+    /// matcher.set_fields(&[Field::new("label"), Field::new("name")]).unwrap();
+    /// let err = matcher.check_fields().unwrap_err();
+    /// assert_eq!(err.to_string(), "unknown field `nmae`, expected one of: label, name");
+    ///
+    /// let mut matcher = Matcher::from_query("nmae:foo", &Config::default());
+    /// matcher.set_fields(&[Field::new("label"), Field::new("name")]).unwrap();
+    /// assert_eq!(matcher.check_fields().unwrap_err().field, "nmae");
+    /// ```
+    pub fn check_fields(&self) -> Result<(), UnknownField> {
+        let unknown = self
+            .raw_patterns
+            .iter()
+            .find_map(|pattern| {
+                let name = pattern.field.as_deref()?;
+                (!self.fields.iter().any(|field| field.is_named(name))).then_some(name)
+            })
+            .or(self.unknown_field_prefixes.first().map(String::as_str));
+        match unknown {
+            Some(name) => Err(UnknownField {
+                field: name.to_string(),
+                known: self.fields.iter().map(|field| field.name.clone()).collect(),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Matches a list of items with multiple fields each (see [`Matcher::set_fields`]), where
+    /// `field` returns the haystack of an item's field by its index in [`Matcher::fields`].
+    ///
+    /// Each pattern is matched against every field of an item, weighting each field's score
+    /// by [`Field::weight`]. A pattern matches an item when it matches any of its fields, and a
+    /// negated pattern when it matches none of them. An OR-group matches when any of its
+    /// patterns match, scoring by the best of them. The pattern's score is the best weighted
+    /// field score, or the sum of the weighted field scores (see [`crate::Config::field_scoring`]),
+    /// and the item's score is the sum across patterns. [`Match::field`] holds the index of the
+    /// field with the highest weighted score across patterns, preferring earlier fields.
+    ///
+    /// Patterns scoped to a field, e.g. `name:foo`, are only matched against the field with that
+    /// name (see [`crate::Pattern::field`] and [`Matcher::set_fields`]).
+    ///
+    /// ```
+    /// use frizbee::{Config, Field, Matcher};
+    ///
+    /// struct Command {
+    ///     label: &'static str,
+    ///     alias: &'static str,
+    /// }
+    ///
+    /// let commands = [
+    ///     Command { label: "Format Document", alias: "prettier" },
+    ///     Command { label: "Open File", alias: "find" },
+    /// ];
+    /// let mut matcher = Matcher::new("find", &Config::default());
+    /// matcher.set_fields(&[Field::new("label"), Field::new("alias").weight(50)]).unwrap();
+    ///
+    /// let matches = matcher.match_list_fields(&commands, |command, field| match field {
+    ///     0 => command.label,
+    ///     _ => command.alias,
+    /// });
+    /// assert_eq!(matches.len(), 1);
+    /// assert_eq!(matches[0].index, 1);
+    /// assert_eq!(matches[0].field, 1);
+    /// ```
+    pub fn match_list_fields<T, F>(&mut self, items: &[T], field: F) -> Vec<Match>
+    where
+        F: Fn(&T, usize) -> &str,
+    {
+        Self::guard_against_haystack_overflow(items.len(), 0);
+        assert!(
+            !self.fields.is_empty(),
+            "no fields set, see Matcher::set_fields"
+        );
+
+        let mut matches = vec![];
+        for start in (0..items.len()).step_by(CHUNK_SIZE) {
+            let end = (start + CHUNK_SIZE).min(items.len());
+            self.match_fields_chunk(items, &field, start..end, &mut matches);
+        }
+        self.sort_matches(&mut matches);
+        matches
+    }
+
+    /// Parallel version of [`Matcher::match_list_fields`] on multiple real threads, with the
+    /// same chunking as [`Matcher::match_list_parallel`]. The `threads` must be >0.
+    pub fn match_list_parallel_fields<T, F>(
+        &mut self,
+        items: &[T],
+        field: F,
+        threads: usize,
+    ) -> Vec<Match>
+    where
+        T: Sync,
+        F: Fn(&T, usize) -> &str + Sync,
+    {
+        let threads = Self::parallel_threads(items.len(), threads);
+        if items.is_empty() || threads == 1 {
+            return self.match_list_fields(items, field);
+        }
+        assert!(
+            !self.fields.is_empty(),
+            "no fields set, see Matcher::set_fields"
+        );
+
+        let (matches, _) = self.match_chunks_parallel(
+            items.len(),
+            threads,
+            |matcher, range, matches| matcher.match_fields_chunk(items, &field, range, matches),
+            || false,
+            |_| {},
+        );
+        matches
+    }
+
+    /// Matches the items in `range` by evaluating each pattern against every field of the
+    /// remaining candidates, as in [`Matcher::match_list_multi_into`], emitting matches in
+    /// index order
+    fn match_fields_chunk<T, F>(
+        &mut self,
+        items: &[T],
+        field: &F,
+        range: Range<usize>,
+        matches: &mut Vec<Match>,
+    ) where
+        F: Fn(&T, usize) -> &str,
+    {
+        let offset = range.start;
+        let items = &items[range];
+        let fields = &self.fields;
+        let num_fields = fields.len();
+        let start = matches.len();
+        let min_score = self.config.min_score;
+        // Field scores are weighted, so the min score is only checked against the item's score
+        self.patterns.set_score_floor(0, 0);
+        let patterns: &mut [CompiledPattern] = match &mut self.patterns {
+            CompiledPatterns::Empty => {
+                let indices = (0..items.len()).map(|i| i + offset);
+                matches.extend(indices.map(Match::from_index));
+                return;
+            }
+            CompiledPatterns::Single(pattern) => std::slice::from_mut(pattern),
+            CompiledPatterns::Multi(patterns) => patterns,
+            CompiledPatterns::Tree(query) => {
+                let field_scoring = self.config.field_scoring;
+                Self::match_fields_query(
+                    query,
+                    fields,
+                    field_scoring,
+                    items,
+                    field,
+                    offset,
+                    matches,
+                );
+                retain_min_score(matches, start, min_score);
+                return;
+            }
+        };
+
+        let mut candidates = (0..items.len())
+            .map(|position| FieldCandidate {
+                position,
+                score: 0,
+                exact: false,
+            })
+            .collect::<Vec<_>>();
+        // Field scores of each item, summed across the groups' best passing patterns
+        let mut field_scores = FieldScores::new(num_fields);
+        field_scores.reset(items.len());
+        // Field scores of each candidate for the current pattern, and for the best passing
+        // pattern of the current group
+        let mut pattern_fields = FieldScores::new(num_fields);
+        let mut group_fields = FieldScores::new(num_fields);
+
+        let mut gathered: Vec<&str> = Vec::with_capacity(items.len());
+        let mut hits: Vec<Match> = Vec::new();
+        let mut pattern_scores: Vec<Option<u16>> = Vec::with_capacity(items.len());
+        let mut pattern_exact: Vec<bool> = Vec::with_capacity(items.len());
+        let mut group_scores: Vec<Option<u16>> = Vec::with_capacity(items.len());
+        let mut group_exact: Vec<bool> = Vec::with_capacity(items.len());
+        for group in groups_mut(patterns) {
+            if candidates.is_empty() {
+                break;
+            }
+
+            group_scores.clear();
+            group_scores.resize(candidates.len(), None);
+            group_exact.clear();
+            group_exact.resize(candidates.len(), false);
+            group_fields.reset(candidates.len());
+            for pattern in group {
+                pattern_scores.clear();
+                pattern_scores.resize(candidates.len(), None);
+                pattern_exact.clear();
+                pattern_exact.resize(candidates.len(), false);
+                pattern_fields.reset(candidates.len());
+                for (field_idx, field_config) in fields.iter().enumerate() {
+                    if !targets(pattern, field_config, fields) {
+                        continue;
+                    }
+                    gathered.clear();
+                    gathered.extend(
+                        candidates
+                            .iter()
+                            .map(|candidate| field(&items[candidate.position], field_idx)),
+                    );
+                    hits.clear();
+                    Self::dispatch_pattern_into(pattern, &gathered, 0, &mut hits);
+
+                    for hit in &hits {
+                        let weighted = field_config.apply_weight(hit.score);
+                        if !pattern.negated {
+                            pattern_exact[hit.index as usize] |= hit.exact;
+                            pattern_fields.add(hit.index as usize, field_idx, weighted, hit);
+                        }
+
+                        let score = &mut pattern_scores[hit.index as usize];
+                        *score = Some(match (*score, self.config.field_scoring) {
+                            (None, _) => weighted,
+                            (Some(score), FieldScoring::Best) => score.max(weighted),
+                            (Some(score), FieldScoring::WeightedSum) => {
+                                score.saturating_add(weighted)
+                            }
+                        });
+                    }
+                }
+
+                // A pattern passes when it matches any field, or, when negated, no field.
+                // The group keeps the best passing pattern, along with its field scores
+                for (i, pattern_score) in pattern_scores.iter().enumerate() {
+                    let passed = if pattern.negated {
+                        pattern_score.is_none().then_some(0)
+                    } else {
+                        *pattern_score
+                    };
+                    if let Some(score) = passed
+                        && group_scores[i].is_none_or(|best| score > best)
+                    {
+                        group_scores[i] = Some(score);
+                        group_exact[i] = pattern_exact[i];
+                        group_fields.copy_row(i, &pattern_fields, i);
+                    }
+                }
+            }
+
+            let mut i = 0;
+            candidates.retain_mut(|candidate| {
+                let passed = match group_scores[i] {
+                    Some(score) => {
+                        candidate.score = candidate.score.saturating_add(score);
+                        candidate.exact |= group_exact[i];
+                        field_scores.add_row(candidate.position, &group_fields, i);
+                        true
+                    }
+                    None => false,
+                };
+                i += 1;
+                passed
+            });
+        }
+
+        matches.extend(candidates.iter().map(|candidate| {
+            let field = field_scores.best_field(candidate.position);
+            Match {
+                score: candidate.score,
+                index: (offset + candidate.position) as u32,
+                exact: candidate.exact,
+                field: field as u16,
+                #[cfg(feature = "match_end_col")]
+                end_col: field_scores.end_col(candidate.position, field),
+            }
+        }));
+        retain_min_score(matches, start, min_score);
+        self.patterns.set_score_floor(0, min_score);
+    }
+
+    /// Matches the items of a chunk against a query tree (see
+    /// [`Matcher::match_query_candidates`]), where a pattern matches an item as in
+    /// [`Matcher::match_fields_chunk`]
+    #[allow(clippy::too_many_arguments)]
+    fn match_fields_query<T, F>(
+        query: &mut CompiledQuery,
+        fields: &[Field],
+        field_scoring: FieldScoring,
+        items: &[T],
+        field: &F,
+        offset: usize,
+        matches: &mut Vec<Match>,
+    ) where
+        F: Fn(&T, usize) -> &str,
+    {
+        let num_fields = fields.len();
+        // Score and row of field scores of each pattern's match on each item, at
+        // `pattern_idx * items.len() + position`, where OR nodes decide which patterns' field
+        // scores count once the query has been matched
+        let mut pattern_fields = FieldScores::new(num_fields);
+        let mut recorded: Vec<Option<(u16, usize)>> =
+            vec![None; query.num_patterns() * items.len()];
+
+        // Candidates are indexed by their position in the chunk
+        let mut candidates = (0..items.len()).map(Match::from_index).collect::<Vec<_>>();
+        let mut gathered: Vec<&str> = Vec::with_capacity(items.len());
+        let mut hits: Vec<Match> = Vec::new();
+        let mut pattern_hits: Vec<Option<Match>> = Vec::with_capacity(items.len());
+        Self::match_query_candidates(
+            query,
+            0,
+            &mut candidates,
+            true,
+            &mut |pattern_idx, pattern, candidates, _, pattern_matches| {
+                let recorded = &mut recorded[pattern_idx * items.len()..][..items.len()];
+                pattern_hits.clear();
+                pattern_hits.resize(candidates.len(), None);
+                for (field_idx, field_config) in fields.iter().enumerate() {
+                    if !targets(pattern, field_config, fields) {
+                        continue;
+                    }
+                    gathered.clear();
+                    gathered.extend(
+                        candidates
+                            .iter()
+                            .map(|candidate| field(&items[candidate.index as usize], field_idx)),
+                    );
+                    hits.clear();
+                    Self::dispatch_pattern_into(pattern, &gathered, 0, &mut hits);
+
+                    for hit in &hits {
+                        let weighted = field_config.apply_weight(hit.score);
+                        let position = candidates[hit.index as usize].index as usize;
+                        let (_, row) = *recorded[position]
+                            .get_or_insert_with(|| (0, pattern_fields.push_row()));
+                        pattern_fields.add(row, field_idx, weighted, hit);
+
+                        let pattern_hit = &mut pattern_hits[hit.index as usize];
+                        let score = match (*pattern_hit, field_scoring) {
+                            (None, _) => weighted,
+                            (Some(m), FieldScoring::Best) => m.score.max(weighted),
+                            (Some(m), FieldScoring::WeightedSum) => {
+                                m.score.saturating_add(weighted)
+                            }
+                        };
+                        *pattern_hit = Some(Match {
+                            score,
+                            exact: hit.exact || pattern_hit.is_some_and(|m| m.exact),
+                            ..Match::from_index(hit.index as usize)
+                        });
+                    }
+                }
+                for m in pattern_hits.iter().flatten() {
+                    let position = candidates[m.index as usize].index as usize;
+                    if let Some((score, _)) = &mut recorded[position] {
+                        *score = m.score;
+                    }
+                }
+                pattern_matches.extend(pattern_hits.iter().flatten());
+            },
+        );
+
+        matches.extend(candidates.iter().map(|candidate| {
+            let position = candidate.index as usize;
+            let recorded = |pattern_idx: usize| recorded[pattern_idx * items.len() + position];
+            let field_scores = winning_field_scores(query, 0, &recorded, &pattern_fields)
+                .map(|(_, field_scores)| field_scores)
+                .unwrap_or_else(|| FieldScores::zeroed(num_fields));
+            let field = field_scores.best_field(0);
+            Match {
+                score: candidate.score,
+                index: (offset + position) as u32,
+                exact: candidate.exact,
+                field: field as u16,
+                #[cfg(feature = "match_end_col")]
+                end_col: field_scores.end_col(0, field),
+            }
+        }));
+    }
+}
+
+/// Replays the query for an item with the patterns' `recorded` matches on it, by pattern index
+/// (see [`Matcher::match_fields_query`]), returning its score and the field scores of the
+/// patterns it passed through, counting only the best passing child of each OR node. The
+/// query's first pattern is at `pattern_idx`
+fn winning_field_scores(
+    query: &CompiledQuery,
+    pattern_idx: usize,
+    recorded: &impl Fn(usize) -> Option<(u16, usize)>,
+    pattern_fields: &FieldScores,
+) -> Option<(u16, FieldScores)> {
+    let num_fields = pattern_fields.num_fields;
+    match query {
+        CompiledQuery::Pattern(pattern) => match (pattern.negated, recorded(pattern_idx)) {
+            (false, Some((score, row))) => {
+                let mut field_scores = FieldScores::zeroed(num_fields);
+                field_scores.copy_row(0, pattern_fields, row);
+                Some((score, field_scores))
+            }
+            (true, None) => Some((0, FieldScores::zeroed(num_fields))),
+            _ => None,
+        },
+        CompiledQuery::Not(query) => {
+            winning_field_scores(query, pattern_idx, recorded, pattern_fields)
+                .is_none()
+                .then(|| (0, FieldScores::zeroed(num_fields)))
+        }
+        CompiledQuery::And(children) => {
+            let mut combined = (0u16, FieldScores::zeroed(num_fields));
+            let mut pattern_idx = pattern_idx;
+            for child in children {
+                let (score, field_scores) =
+                    winning_field_scores(child, pattern_idx, recorded, pattern_fields)?;
+                combined.0 = combined.0.saturating_add(score);
+                combined.1.add_row(0, &field_scores, 0);
+                pattern_idx += child.num_patterns();
+            }
+            Some(combined)
+        }
+        CompiledQuery::Or(children) => {
+            let mut best = None;
+            let mut pattern_idx = pattern_idx;
+            for child in children {
+                let passed = winning_field_scores(child, pattern_idx, recorded, pattern_fields);
+                keep_best(&mut best, passed, |(score, _)| *score);
+                pattern_idx += child.num_patterns();
+            }
+            best
+        }
+    }
+}
+
+/// Whether the pattern is matched against the field, as it's unscoped or scoped to the field.
+/// Patterns scoped to none of the `fields` are matched against all of them
+fn targets(pattern: &CompiledPattern, field: &Field, fields: &[Field]) -> bool {
+    pattern
+        .field
+        .as_deref()
+        .is_none_or(|name| field.is_named(name) || !fields.iter().any(|field| field.is_named(name)))
+}
+
+/// Weighted score of each field for rows of candidates, with the end column of the match on
+/// each field
+struct FieldScores {
+    num_fields: usize,
+    scores: Vec<u16>,
+    #[cfg(feature = "match_end_col")]
+    end_cols: Vec<u16>,
+}
+
+impl FieldScores {
+    fn new(num_fields: usize) -> Self {
+        Self {
+            num_fields,
+            scores: vec![],
+            #[cfg(feature = "match_end_col")]
+            end_cols: vec![],
+        }
+    }
+
+    /// A single row of zeroes
+    fn zeroed(num_fields: usize) -> Self {
+        let mut field_scores = Self::new(num_fields);
+        field_scores.reset(1);
+        field_scores
+    }
+
+    /// Zeroes the scores, resizing to `rows` rows
+    fn reset(&mut self, rows: usize) {
+        self.scores.clear();
+        self.scores.resize(rows * self.num_fields, 0);
+        #[cfg(feature = "match_end_col")]
+        {
+            self.end_cols.clear();
+            self.end_cols.resize(rows * self.num_fields, 0);
+        }
+    }
+
+    /// Appends a row of zeroes, returning its index
+    fn push_row(&mut self) -> usize {
+        let row = self.scores.len() / self.num_fields;
+        self.scores.resize(self.scores.len() + self.num_fields, 0);
+        #[cfg(feature = "match_end_col")]
+        self.end_cols.resize(self.scores.len(), 0);
+        row
+    }
+
+    /// Adds the weighted score of a match on a field
+    fn add(&mut self, row: usize, field_idx: usize, weighted: u16, hit: &Match) {
+        let slot = row * self.num_fields + field_idx;
+        self.scores[slot] = self.scores[slot].saturating_add(weighted);
+        #[cfg(feature = "match_end_col")]
+        {
+            self.end_cols[slot] = self.end_cols[slot].max(hit.end_col);
+        }
+        #[cfg(not(feature = "match_end_col"))]
+        let _ = hit;
+    }
+
+    /// Adds the scores of `other`'s row to `row`
+    fn add_row(&mut self, row: usize, other: &Self, other_row: usize) {
+        let (start, other_start) = (row * self.num_fields, other_row * self.num_fields);
+        for field_idx in 0..self.num_fields {
+            let slot = &mut self.scores[start + field_idx];
+            *slot = slot.saturating_add(other.scores[other_start + field_idx]);
+            #[cfg(feature = "match_end_col")]
+            {
+                let slot = &mut self.end_cols[start + field_idx];
+                *slot = (*slot).max(other.end_cols[other_start + field_idx]);
+            }
+        }
+    }
+
+    /// Replaces the scores of `row` with those of `other`'s row
+    fn copy_row(&mut self, row: usize, other: &Self, other_row: usize) {
+        let (start, other_start) = (row * self.num_fields, other_row * self.num_fields);
+        let range = other_start..other_start + self.num_fields;
+        self.scores[start..start + self.num_fields].copy_from_slice(&other.scores[range.clone()]);
+        #[cfg(feature = "match_end_col")]
+        self.end_cols[start..start + self.num_fields].copy_from_slice(&other.end_cols[range]);
+    }
+
+    /// Index of the field with the highest weighted score, preferring earlier fields
+    fn best_field(&self, row: usize) -> usize {
+        let scores = &self.scores[row * self.num_fields..][..self.num_fields];
+        // Reversed, so that ties go to the earlier field
+        (0..scores.len())
+            .rev()
+            .max_by_key(|&field_idx| scores[field_idx])
+            .unwrap_or(0)
+    }
+
+    #[cfg(feature = "match_end_col")]
+    fn end_col(&self, row: usize, field_idx: usize) -> u16 {
+        self.end_cols[row * self.num_fields + field_idx]
+    }
+}
+
+/// An item of the chunk that matched every pattern so far
+struct FieldCandidate {
+    /// Position of the item in the chunk
+    position: usize,
+    score: u16,
+    exact: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Config, Field, FieldScoring, Matcher, Pattern, Query, SortStrategy, TooManyFields,
+        UnknownField,
+    };
+
+    fn items() -> Vec<[String; 2]> {
+        let labels = ["open_file", "save_file", "closeWindow", "find"];
+        let details = ["File", "Window", "Edit", "file: open", "", "Search"];
+        (0..5000)
+            .map(|index| {
+                [
+                    format!("{}-{index}", labels[index % labels.len()]),
+                    details[index % 7 % details.len()].to_string(),
+                ]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn single_field_matches_match_list() {
+        let items = items();
+        let labels = items.iter().map(|item| &item[0]).collect::<Vec<_>>();
+        for query in ["file", "file !save", "!file", ""] {
+            for sort in [SortStrategy::ScoreThenIndexAsc, SortStrategy::IndexDesc] {
+                let config = Config::default().sort(sort);
+                let mut matcher = Matcher::from_query(query, &config);
+                matcher.set_fields(&[Field::new("label")]).unwrap();
+                let expected = matcher.match_list(&labels);
+
+                let matches = matcher.match_list_fields(&items, |item, field| &item[field]);
+                assert_eq!(matches, expected, "query={query:?}, sort={sort:?}");
+                assert!(matches.iter().all(|m| m.field == 0));
+
+                let parallel =
+                    matcher.match_list_parallel_fields(&items, |item, field| &item[field], 3);
+                assert_eq!(parallel, expected, "query={query:?}, sort={sort:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn fields_combine_by_field_scoring() {
+        let items = [
+            ["foo", "bar"],
+            ["bar", "foo"],
+            ["foo", "foo"],
+            ["baz", "qux"],
+        ];
+        let score = Matcher::new("foo", &Config::default()).match_list(&["foo"])[0].score;
+        let fields = [Field::new("label"), Field::new("detail").weight(50)];
+
+        for (field_scoring, expected) in [
+            (
+                FieldScoring::Best,
+                [(0, score, 0), (1, score / 2, 1), (2, score, 0)],
+            ),
+            (
+                FieldScoring::WeightedSum,
+                [(0, score, 0), (1, score / 2, 1), (2, score + score / 2, 0)],
+            ),
+        ] {
+            let config = Config::default()
+                .sort(SortStrategy::IndexAsc)
+                .field_scoring(field_scoring);
+            let mut matcher = Matcher::new("foo", &config);
+            matcher.set_fields(&fields).unwrap();
+            let matches = matcher.match_list_fields(&items, |item, field| item[field]);
+            let matches = matches
+                .iter()
+                .map(|m| (m.index, m.score, m.field))
+                .collect::<Vec<_>>();
+            assert_eq!(matches, expected, "field_scoring={field_scoring:?}");
+        }
+    }
+
+    #[test]
+    fn min_score_applies_to_weighted_item_score() {
+        let items = [["foo", "bar"], ["bar", "foo"], ["baz", "qux"]];
+        let score = Matcher::new("foo", &Config::default()).match_list(&["foo"])[0].score;
+        let fields = [
+            Field::new("label").weight(50),
+            Field::new("detail").weight(200),
+        ];
+        for (query, min_score, expected) in [
+            // A single pattern reaches the min score through the weight of the field
+            ("foo", score + 1, vec![1]),
+            ("foo", score / 2, vec![0, 1]),
+            ("foo | bar", score * 2, vec![0, 1]),
+            ("foo bar", score * 2 + score / 2, vec![0, 1]),
+            ("foo bar", score * 2 + score / 2 + 1, vec![]),
+        ] {
+            let config = Config::default()
+                .sort(SortStrategy::IndexAsc)
+                .min_score(min_score);
+            let mut matcher = Matcher::from_query(query, &config);
+            matcher.set_fields(&fields).unwrap();
+            let matches = matcher.match_list_fields(&items, |item, field| item[field]);
+            assert_eq!(
+                matches.iter().map(|m| m.index).collect::<Vec<_>>(),
+                expected,
+                "query={query:?}, min_score={min_score}"
+            );
+        }
+    }
+
+    #[test]
+    fn patterns_match_across_fields() {
+        let items = [
+            ["foo", "bar"],
+            ["foo", "baz"],
+            ["bar", "bar"],
+            ["foo bar", "baz"],
+        ];
+        let config = Config::default().sort(SortStrategy::IndexAsc);
+        let mut matcher = Matcher::from_patterns(&Pattern::parse_query("foo bar !baz"), &config);
+        matcher
+            .set_fields(&[Field::new("label"), Field::new("detail")])
+            .unwrap();
+
+        // A negated pattern excludes items matching it in any field
+        let matches = matcher.match_list_fields(&items, |item, field| item[field]);
+        assert_eq!(matches.iter().map(|m| m.index).collect::<Vec<_>>(), vec![0]);
+    }
+
+    #[test]
+    fn field_is_credited_to_the_winning_alternative() {
+        let items = [["bar fo", "xx quux"], ["fo", "bar"]];
+        let fields = [Field::new("label"), Field::new("detail")];
+        let config = Config::default().sort(SortStrategy::IndexAsc);
+        let fields_of = |query: &Query| {
+            let mut matcher = Matcher::from_query_ast(query, &config);
+            matcher.set_fields(&fields).unwrap();
+            let matches = matcher.match_list_fields(&items, |item, field| item[field]);
+            matches
+                .iter()
+                .map(|m| (m.index, m.score, m.field))
+                .collect::<Vec<_>>()
+        };
+
+        // `fo` loses to `quux` on the first item, so only `bar` and `quux` pick its field
+        let expected = fields_of(&Query::parse("bar quux"));
+        assert_eq!(expected[0].2, 1, "{expected:?}");
+        for query in ["bar (fo | quux)", "bar (fo | quux | !(x y))"] {
+            let matches = fields_of(&Query::parse(query));
+            assert_eq!(matches[0], expected[0], "query={query:?}");
+            assert_eq!(matches[1].2, 1, "query={query:?}");
+        }
+    }
+
+    #[test]
+    fn query_tree_matches_across_fields() {
+        let items = [
+            ["foo", "bar"],
+            ["foo", "baz"],
+            ["qux", "bar"],
+            ["qux", "quux"],
+            ["bar baz", "foo"],
+        ];
+        let config = Config::default().sort(SortStrategy::IndexAsc);
+        let query = Query::parse("(foo | qux) !(bar baz)");
+        let mut matcher = Matcher::from_query_ast(&query, &config);
+        matcher
+            .set_fields(&[Field::new("label"), Field::new("detail").weight(50)])
+            .unwrap();
+
+        let matches = matcher.match_list_fields(&items, |item, field| item[field]);
+        let matches = matches
+            .iter()
+            .map(|m| (m.index, m.field))
+            .collect::<Vec<_>>();
+        assert_eq!(matches, vec![(0, 0), (1, 0), (2, 0), (3, 0)]);
+
+        // Matches the same as a single field
+        let labels = items.iter().map(|item| item[0]).collect::<Vec<_>>();
+        let expected = matcher.match_list(&labels);
+        matcher.set_fields(&[Field::new("label")]).unwrap();
+        assert_eq!(
+            matcher.match_list_fields(&items, |item, field| item[field]),
+            expected
+        );
+    }
+
+    #[test]
+    fn scoped_patterns_match_their_field() {
+        let items = [
+            ["src/main.rs", "main"],
+            ["main/src.rs", "lib"],
+            ["docs/src.md", "src"],
+            ["src/lib.rs", "test"],
+        ];
+        let fields = [Field::new("path"), Field::new("Name")];
+        let config = Config::default().sort(SortStrategy::IndexAsc);
+        let indices = |query: &str| {
+            let mut matcher = Matcher::from_query(query, &config);
+            matcher.set_fields(&fields).unwrap();
+            assert_eq!(matcher.check_fields(), Ok(()), "query: {query:?}");
+            let matches = matcher.match_list_fields(&items, |item, field| item[field]);
+            matches
+                .iter()
+                .map(|m| (m.index, m.field))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(indices("main"), vec![(0, 1), (1, 0)]);
+        assert_eq!(indices("name:main"), vec![(0, 1)]);
+        assert_eq!(indices("path:^src"), vec![(0, 0), (3, 0)]);
+        assert_eq!(
+            indices("path:'src !NAME:test"),
+            vec![(0, 0), (1, 0), (2, 0)]
+        );
+        assert_eq!(
+            indices("name:src | path:main"),
+            vec![(0, 0), (1, 0), (2, 1)]
+        );
+
+        // Scopes also apply within query trees
+        let query =
+            Query::parse_with_fields("(name:main | name:lib) !(path:^src name:main)", &fields);
+        let mut matcher = Matcher::from_query_ast(&query, &config);
+        matcher.set_fields(&fields).unwrap();
+        let matches = matcher.match_list_fields(&items, |item, field| item[field]);
+        assert_eq!(matches.iter().map(|m| m.index).collect::<Vec<_>>(), vec![1]);
+    }
+
+    #[test]
+    fn unknown_field_prefixes_are_part_of_the_needle() {
+        let items = [
+            ["TODO:fix", "x"],
+            ["nothing to fix here", "x"],
+            ["rs:42", "x42"],
+            ["C:\\Users", "std::fmt"],
+        ];
+        let config = Config::default().sort(SortStrategy::IndexAsc);
+        let fields = [Field::new("label"), Field::new("detail")];
+        let matcher = |query: &str| {
+            let mut matcher = Matcher::from_query(query, &config);
+            matcher.set_fields(&fields).unwrap();
+            matcher
+        };
+        let indices = |query: &str| {
+            let matches = matcher(query).match_list_fields(&items, |item, field| item[field]);
+            matches.iter().map(|m| m.index).collect::<Vec<_>>()
+        };
+        assert_eq!(indices("TODO:fix"), vec![0]);
+        assert_eq!(indices("rs:42"), vec![2]);
+        assert_eq!(indices("detail:42"), vec![2]);
+        assert_eq!(indices("C:\\Users"), vec![3]);
+        assert_eq!(indices("std::fmt"), vec![3]);
+
+        // Prefixes shaped like a field name are reported, while still matched as is
+        let unknown = |query: &str| matcher(query).check_fields().err().map(|err| err.field);
+        assert_eq!(unknown("TODO:fix"), Some("TODO".to_string()));
+        assert_eq!(unknown("foo !nmae:bar"), Some("nmae".to_string()));
+        for query in [
+            "detail:42",
+            "C:\\Users",
+            "std::fmt",
+            "http://foo",
+            ":foo",
+            "nmae\\:foo",
+        ] {
+            assert_eq!(unknown(query), None, "query: {query:?}");
+        }
+        let mut matcher = matcher("nmae:foo");
+        matcher.set_fields(&[Field::new("nmae")]).unwrap();
+        assert_eq!(matcher.check_fields(), Ok(()));
+        matcher.set_patterns(&Pattern::parse_query("nmae:foo"));
+        assert_eq!(matcher.check_fields(), Ok(()));
+
+        // Without fields, every prefix is part of the needle
+        let mut matcher = Matcher::from_query("TODO:fix", &config);
+        let haystacks = items.map(|item| item[0]);
+        assert_eq!(matcher.match_list(&haystacks).len(), 1);
+    }
+
+    #[test]
+    fn unknown_fields_are_reported() {
+        let items = [["foo", "bar"], ["bar", "foo"]];
+        let patterns = [
+            Pattern::from("foo"),
+            Pattern::from("bar").negated(true).field(Some("nmae")),
+        ];
+        let mut matcher = Matcher::from_patterns(&patterns, &Config::default());
+        matcher
+            .set_fields(&[Field::new("label"), Field::new("name")])
+            .unwrap();
+        assert_eq!(
+            matcher.check_fields(),
+            Err(UnknownField {
+                field: "nmae".to_string(),
+                known: vec!["label".to_string(), "name".to_string()],
+            })
+        );
+        // A pattern on an unknown field is matched against every field
+        let matches = matcher.match_list_fields(&items, |item, field| item[field]);
+        assert!(matches.is_empty());
+
+        matcher.set_patterns(&[Pattern::from("foo").field(Some("nmae"))]);
+        assert!(matcher.check_fields().is_err());
+        assert_eq!(
+            matcher
+                .match_list_fields(&items, |item, field| item[field])
+                .len(),
+            2
+        );
+    }
+
+    #[test]
+    fn too_many_fields_are_rejected() {
+        let items = [["foo", "bar"]];
+        let mut matcher = Matcher::new("bar", &Config::default());
+        matcher
+            .set_fields(&[Field::new("a"), Field::new("b")])
+            .unwrap();
+        let fields = vec![Field::new("c"); TooManyFields::MAX + 1];
+        assert_eq!(
+            matcher.set_fields(&fields),
+            Err(TooManyFields {
+                count: TooManyFields::MAX + 1
+            })
+        );
+        // The previous fields are kept
+        assert_eq!(matcher.fields().len(), 2);
+        let matches = matcher.match_list_fields(&items, |item, field| item[field]);
+        assert_eq!(matches[0].field, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "no fields set")]
+    fn no_fields_panics() {
+        let _ = Matcher::new("a", &Config::default()).match_list_fields(&["a"], |item, _| *item);
+    }
+}
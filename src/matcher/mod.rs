@@ -1,17 +1,37 @@
+use std::borrow::Cow;
+
+use crate::normalize::Normalizer;
 use crate::smith_waterman::score_fits_in_u8;
 use crate::sort::radix_sort_matches;
-use crate::{Config, Match, MatchIndices, Pattern};
+use crate::{Config, Field, Match, MatchIndices, Matching, Pattern, Query, Scoring};
 
-#[cfg(target_arch = "aarch64")]
-use crate::literal::LiteralNEON;
-use crate::literal::LiteralScalar;
+#[cfg(all(feature = "regex", target_arch = "aarch64"))]
+use crate::literal::RegexNEON;
+#[cfg(feature = "regex")]
+use crate::literal::RegexScalar;
 #[cfg(target_arch = "x86_64")]
-use crate::literal::{LiteralAVX, LiteralAVX512, LiteralSSE};
+use crate::literal::{
+    GlobAVX, GlobAVX512, GlobSSE, InitialsAVX, InitialsAVX512, InitialsSSE, LiteralAVX,
+    LiteralAVX512, LiteralSSE, PathSegmentsAVX, PathSegmentsAVX512, PathSegmentsSSE,
+};
+#[cfg(target_arch = "aarch64")]
+use crate::literal::{GlobNEON, InitialsNEON, LiteralNEON, PathSegmentsNEON};
+use crate::literal::{GlobScalar, InitialsScalar, LiteralScalar, PathSegmentsScalar};
+#[cfg(all(feature = "regex", target_arch = "x86_64"))]
+use crate::literal::{RegexAVX, RegexAVX512, RegexSSE};
 
 pub(crate) mod algo;
 mod backend;
+mod by_key;
+mod deadline;
+mod fields;
 mod iter;
 mod parallel;
+pub(crate) use parallel::CHUNK_SIZE;
+pub use parallel::{CancellationToken, Cancelled};
+mod query;
+mod refine;
+mod top_k;
 use algo::{MANY_TYPOS, NO_PREFILTER, Specialized};
 use backend::*;
 pub use iter::{FuzzyMatch, FuzzyMatchExt, FuzzyMatchIndices};
@@ -47,6 +67,43 @@ macro_rules! dispatch {
             #[cfg(target_arch = "aarch64")]
             MatcherBackend::LiteralNEON($m) => $body,
             MatcherBackend::LiteralScalar($m) => $body,
+            #[cfg(target_arch = "x86_64")]
+            MatcherBackend::GlobAVX512($m) => $body,
+            #[cfg(target_arch = "x86_64")]
+            MatcherBackend::GlobAVX($m) => $body,
+            #[cfg(target_arch = "x86_64")]
+            MatcherBackend::GlobSSE($m) => $body,
+            #[cfg(target_arch = "aarch64")]
+            MatcherBackend::GlobNEON($m) => $body,
+            MatcherBackend::GlobScalar($m) => $body,
+            #[cfg(target_arch = "x86_64")]
+            MatcherBackend::InitialsAVX512($m) => $body,
+            #[cfg(target_arch = "x86_64")]
+            MatcherBackend::InitialsAVX($m) => $body,
+            #[cfg(target_arch = "x86_64")]
+            MatcherBackend::InitialsSSE($m) => $body,
+            #[cfg(target_arch = "aarch64")]
+            MatcherBackend::InitialsNEON($m) => $body,
+            MatcherBackend::InitialsScalar($m) => $body,
+            #[cfg(target_arch = "x86_64")]
+            MatcherBackend::PathSegmentsAVX512($m) => $body,
+            #[cfg(target_arch = "x86_64")]
+            MatcherBackend::PathSegmentsAVX($m) => $body,
+            #[cfg(target_arch = "x86_64")]
+            MatcherBackend::PathSegmentsSSE($m) => $body,
+            #[cfg(target_arch = "aarch64")]
+            MatcherBackend::PathSegmentsNEON($m) => $body,
+            MatcherBackend::PathSegmentsScalar($m) => $body,
+            #[cfg(all(feature = "regex", target_arch = "x86_64"))]
+            MatcherBackend::RegexAVX512($m) => $body,
+            #[cfg(all(feature = "regex", target_arch = "x86_64"))]
+            MatcherBackend::RegexAVX($m) => $body,
+            #[cfg(all(feature = "regex", target_arch = "x86_64"))]
+            MatcherBackend::RegexSSE($m) => $body,
+            #[cfg(all(feature = "regex", target_arch = "aarch64"))]
+            MatcherBackend::RegexNEON($m) => $body,
+            #[cfg(feature = "regex")]
+            MatcherBackend::RegexScalar($m) => $body,
         }
     };
 }
@@ -73,14 +130,24 @@ macro_rules! dispatch_typos {
 }
 
 mod multi;
-use multi::{CompiledPattern, CompiledPatterns};
+use multi::{CompiledPattern, CompiledPatterns, retain_min_score};
+use query::CompiledQuery;
 
 /// Primary entrypoint for fuzzy matching
 #[derive(Debug, Clone)]
 pub struct Matcher {
     config: Config,
     raw_patterns: Vec<Pattern>,
+    /// Source of the patterns when built from a [`Query`]
+    raw_query: Option<Query>,
+    /// Source of the patterns when parsed with [`Matcher::from_query`], parsed again when the
+    /// fields change so that its atoms may be scoped to them
+    query_text: Option<String>,
+    /// Prefixes of the query's atoms shaped like a field name but naming none of the fields,
+    /// reported by [`Matcher::check_fields`]
+    unknown_field_prefixes: Vec<String>,
     patterns: CompiledPatterns,
+    fields: Vec<Field>,
 }
 
 impl Matcher {
@@ -93,7 +160,8 @@ impl Matcher {
 
     /// Creates a matcher from a list of [`Pattern`]s (see [`Pattern::parse_query`]),
     /// matched independently. A haystack matches when all of the patterns match where
-    /// the score is the sum of each pattern's score.
+    /// the score is the sum of each pattern's score. Patterns joined into an OR-group match
+    /// when any of them match, scoring by the best of them.
     ///
     /// ```
     /// use frizbee::{Config, Matcher, Pattern};
@@ -106,11 +174,44 @@ impl Matcher {
         Self {
             patterns: Self::build_patterns(patterns, config),
             raw_patterns: patterns.to_vec(),
+            raw_query: None,
+            query_text: None,
+            unknown_field_prefixes: vec![],
+            config: config.clone(),
+            fields: vec![],
+        }
+    }
+
+    /// Creates a matcher from a boolean [`Query`] (see [`Query::parse`]), where AND nodes sum
+    /// the scores of their children, OR nodes take the best of them and NOT nodes score 0.
+    ///
+    /// Queries without nested groups, e.g. `foo | bar baz`, match the same as
+    /// [`Matcher::from_patterns`]. Otherwise, the query is matched as a tree which evaluates
+    /// cheaper (literal) patterns first, narrowing the haystacks for the rest.
+    ///
+    /// ```
+    /// use frizbee::{Config, Matcher, Query};
+    ///
+    /// let query = Query::parse("(foo | bar) !(baz qux)");
+    /// let mut matcher = Matcher::from_query_ast(&query, &Config::default());
+    /// let matches = matcher.match_list(&["foo", "bar baz qux", "bar qux", "qux"]);
+    /// assert_eq!(matches.len(), 2); // "bar baz qux" contains both "baz" and "qux"
+    /// ```
+    pub fn from_query_ast(query: &Query, config: &Config) -> Self {
+        Self {
+            patterns: Self::build_query(query, config),
+            raw_patterns: query.patterns().into_iter().cloned().collect(),
+            raw_query: Some(query.clone()),
+            query_text: None,
+            unknown_field_prefixes: vec![],
             config: config.clone(),
+            fields: vec![],
         }
     }
 
-    /// Shorthand for calling [`Matcher::from_patterns`] with [`Pattern::parse_query`].
+    /// Shorthand for calling [`Matcher::from_patterns`] with [`Pattern::parse_query`]. The
+    /// query is parsed again with [`Pattern::parse_query_with_fields`] when setting the fields
+    /// (see [`Matcher::set_fields`]), so its atoms may be scoped to them.
     ///
     /// ```rust
     /// use frizbee::{Config, Matcher};
@@ -138,13 +239,23 @@ impl Matcher {
     /// let matches = matcher.match_list(&["foo", "barfoo", "foobar"]);
     /// ```
     pub fn from_query(query: &str, config: &Config) -> Self {
-        Self::from_patterns(&Pattern::parse_query(query), config)
+        Self {
+            query_text: Some(query.to_string()),
+            ..Self::from_patterns(&Pattern::parse_query(query), config)
+        }
     }
 
+    /// Returns the patterns of the matcher, or the patterns at the leaves of the query when
+    /// built from a [`Query`] (see [`Matcher::query_ast`])
     pub fn patterns(&self) -> &[Pattern] {
         &self.raw_patterns
     }
 
+    /// Returns the query when the matcher was built from a [`Query`]
+    pub fn query_ast(&self) -> Option<&Query> {
+        self.raw_query.as_ref()
+    }
+
     pub fn config(&self) -> &Config {
         &self.config
     }
@@ -156,7 +267,10 @@ impl Matcher {
             return;
         }
         self.config = config;
-        self.patterns = Self::build_patterns(&self.raw_patterns, &self.config);
+        self.patterns = match &self.raw_query {
+            Some(query) => Self::build_query(query, &self.config),
+            None => Self::build_patterns(&self.raw_patterns, &self.config),
+        };
     }
 
     /// Updates the pattern, as in [`Matcher::new`], and rebuilds the internal matcher
@@ -168,41 +282,140 @@ impl Matcher {
     /// Updates the patterns, as in [`Matcher::from_patterns`], and rebuilds the internal
     /// matcher backends. Skipped if the patterns are the same as the previous ones.
     pub fn set_patterns(&mut self, patterns: &[Pattern]) {
-        if self.raw_patterns == patterns {
+        if self.raw_query.is_none() && self.query_text.is_none() && self.raw_patterns == patterns {
             return;
         }
         self.raw_patterns = patterns.to_vec();
+        self.raw_query = None;
+        self.query_text = None;
+        self.unknown_field_prefixes.clear();
         self.patterns = Self::build_patterns(&self.raw_patterns, &self.config);
     }
 
+    /// Updates the query, as in [`Matcher::from_query_ast`], and rebuilds the internal
+    /// matcher backends. Skipped if the query is the same as the previous one.
+    pub fn set_query_ast(&mut self, query: &Query) {
+        if self.raw_query.as_ref() == Some(query) {
+            return;
+        }
+        self.raw_patterns = query.patterns().into_iter().cloned().collect();
+        self.raw_query = Some(query.clone());
+        self.query_text = None;
+        self.unknown_field_prefixes.clear();
+        self.patterns = Self::build_query(query, &self.config);
+    }
+
     fn build_patterns(sources: &[Pattern], config: &Config) -> CompiledPatterns {
-        let mut compiled = sources
-            .iter()
-            .filter_map(|source| Self::compile(source, config))
-            .collect::<Vec<_>>();
-        match compiled.as_slice() {
-            [] => CompiledPatterns::Empty,
-            [single] if !single.negated => CompiledPatterns::Single(compiled.pop().unwrap()),
-            _ => CompiledPatterns::Multi(compiled),
+        let mut compiled = vec![];
+        for group in sources.chunk_by(|_, next| next.or) {
+            // Empty needles match every haystack, and so does any OR-group containing one
+            let group = group
+                .iter()
+                .map(|source| Self::compile(source, config))
+                .collect::<Option<Vec<_>>>();
+            for (i, mut pattern) in group.into_iter().flatten().enumerate() {
+                pattern.or = i > 0;
+                compiled.push(pattern);
+            }
         }
+        let mut patterns = CompiledPatterns::new(compiled);
+        patterns.set_score_floor(0, config.min_score);
+        patterns
+    }
+
+    fn build_query(query: &Query, config: &Config) -> CompiledPatterns {
+        let mut patterns = match CompiledQuery::compile(query, config).into_patterns() {
+            Ok(patterns) => CompiledPatterns::new(patterns),
+            Err(query) => CompiledPatterns::Tree(query),
+        };
+        patterns.set_score_floor(0, config.min_score);
+        patterns
     }
 
     /// Builds the backend for a pattern, resolving any per-pattern overrides against the
     /// matcher's config (see [`crate::PatternConfig::resolve`]). Returns `None` for empty
     /// needles.
     fn compile(source: &Pattern, config: &Config) -> Option<CompiledPattern> {
-        if source.needle.is_empty() {
+        let mut config = source.config.resolve(config);
+        // `Config::min_score` applies to the combined score of the patterns (see
+        // `CompiledPatterns::set_score_floor`), so only the pattern's own min score is kept
+        config.min_score = source.config.min_score.unwrap_or(0);
+        let needle = match config.matching {
+            Matching::Fuzzy
+            | Matching::Exact
+            | Matching::Prefix
+            | Matching::Suffix
+            | Matching::Substring
+            | Matching::Word
+            | Matching::Initials => {
+                Normalizer::for_config(&config, &source.needle).normalize_needle(&source.needle)
+            }
+            _ => Cow::Borrowed(source.needle.as_str()),
+        };
+        // Min and max length of the regex's matches, where the max is `None` when unbounded
+        let regex_len: Option<(Option<usize>, Option<usize>)> =
+            config.matching.is_regex().then(|| {
+                #[cfg(feature = "regex")]
+                return crate::literal::regex_match_len(
+                    &needle,
+                    config.casing.respects_case_for(&needle),
+                );
+                #[cfg(not(feature = "regex"))]
+                unreachable!()
+            });
+        if needle.is_empty() {
             return None;
         }
-        let config = source.config.resolve(config);
+        let (max_score, max_score_per_byte) = match regex_len {
+            // A regex matching the empty string scores 0, like an empty needle
+            Some((Some(0), _)) => (0, 0),
+            Some((_, Some(max_len))) => (config.scoring.max_score(max_len), 0),
+            // Each char of an unbounded regex match is at least one byte of the haystack
+            Some((_, None)) => (
+                config.scoring.max_score(0),
+                config.scoring.max_score_per_char(),
+            ),
+            None => (Self::pattern_max_score(&needle, &config), 0),
+        };
         Some(CompiledPattern {
             negated: source.negated,
-            needs_unicode: config.unicode.respects_unicode_for(&source.needle),
+            or: source.or,
+            field: source.field.clone(),
+            needs_unicode: config.unicode.respects_unicode_for(&needle),
+            #[cfg(feature = "graphemes")]
+            graphemes: config.unicode.is_graphemes(),
             max_typos: config.max_typos,
-            backend: Self::get_backend(&source.needle, &config),
+            min_score: source.config.min_score,
+            max_score,
+            max_score_per_byte,
+            backend: Self::get_backend(&needle, &config),
         })
     }
 
+    /// Upper bound on the score of any haystack for a non-regex needle
+    fn pattern_max_score(needle: &str, config: &Config) -> u16 {
+        match config.matching {
+            // Every initials match earns both the delimiter and capitalization bonuses
+            Matching::Initials => Scoring {
+                delimiter_bonus: (config.scoring.max_delimiter_bonus())
+                    .saturating_add(config.scoring.capitalization_bonus),
+                delimiters: None,
+                ..config.scoring.clone()
+            }
+            .max_score(needle.len())
+            .saturating_add(config.max_path_bonus()),
+            // Every needle segment may earn the prefix bonus at the start of its path segment
+            Matching::PathSegments => config.scoring.max_score(needle.len()).saturating_add(
+                (config.scoring.prefix_bonus)
+                    .saturating_mul(needle.matches('/').count().try_into().unwrap_or(u16::MAX)),
+            ),
+            _ => config
+                .scoring
+                .max_score(needle.len())
+                .saturating_add(config.max_path_bonus()),
+        }
+    }
+
     /// Matches a list of haystacks, returning a list of [`Match`] values.
     /// This API provides the most performant path when matching on lists.
     ///
@@ -212,13 +425,18 @@ impl Matcher {
     pub fn match_list<S: AsRef<str>>(&mut self, haystacks: &[S]) -> Vec<Match> {
         let mut matches = vec![];
         self.match_list_into(haystacks, 0, &mut matches);
+        self.sort_matches(&mut matches);
+        matches
+    }
+
+    /// Orders matches emitted in index order according to [`Config::sort`]
+    pub(crate) fn sort_matches(&self, matches: &mut [Match]) {
         if self.config.sort.is_reversed() {
             matches.reverse();
         }
         if !self.patterns.is_empty() && self.config.sort.is_by_score() {
-            radix_sort_matches(&mut matches);
+            radix_sort_matches(matches);
         }
-        matches
     }
 
     /// Matches a list of haystacks, returning a list of [`MatchIndices`] which are equivalent
@@ -245,11 +463,15 @@ impl Matcher {
                 }
             }
             CompiledPatterns::Single(pattern) => {
-                dispatch!(&mut pattern.backend, matcher => {
+                let mut matches = dispatch!(&mut pattern.backend, matcher => {
                     dispatch_typos!(pattern.max_typos, pattern.needs_unicode, |TYPOS, UNICODE| {
                         unsafe { matcher.match_list_indices::<TYPOS, UNICODE, S>(haystacks) }
                     })
-                })
+                });
+                for m in &mut matches {
+                    pattern.expand_indices(haystacks[m.index as usize].as_ref(), &mut m.indices);
+                }
+                matches
             }
             CompiledPatterns::Multi(patterns) => haystacks
                 .iter()
@@ -257,6 +479,15 @@ impl Matcher {
                 .filter_map(|(index, haystack)| {
                     Self::match_one_indices_multi(patterns, haystack, index as u32)
                 })
+                .filter(|m| m.score >= self.config.min_score)
+                .collect(),
+            CompiledPatterns::Tree(query) => haystacks
+                .iter()
+                .enumerate()
+                .filter_map(|(index, haystack)| {
+                    Self::match_one_indices_query(query, haystack.as_ref(), index as u32)
+                })
+                .filter(|m| m.score >= self.config.min_score)
                 .collect(),
         };
 
@@ -343,7 +574,10 @@ impl Matcher {
             CompiledPatterns::Single(pattern) => {
                 Self::dispatch_pattern_one(pattern, haystack, index)
             }
-            CompiledPatterns::Multi(patterns) => Self::match_one_multi(patterns, haystack, index),
+            CompiledPatterns::Multi(patterns) => Self::match_one_multi(patterns, haystack, index)
+                .filter(|m| m.score >= self.config.min_score),
+            CompiledPatterns::Tree(query) => Self::match_one_query(query, haystack.as_ref(), index)
+                .filter(|m| m.score >= self.config.min_score),
         }
     }
 
@@ -366,29 +600,42 @@ impl Matcher {
             }
             CompiledPatterns::Multi(patterns) => {
                 Self::match_one_indices_multi(patterns, haystack, index)
+                    .filter(|m| m.score >= self.config.min_score)
+            }
+            CompiledPatterns::Tree(query) => {
+                Self::match_one_indices_query(query, haystack.as_ref(), index)
+                    .filter(|m| m.score >= self.config.min_score)
             }
         }
     }
 
-    fn match_list_into<S: AsRef<str>>(
+    pub(crate) fn match_list_into<S: AsRef<str>>(
         &mut self,
         haystacks: &[S],
         haystack_index_offset: u32,
         matches: &mut Vec<Match>,
     ) {
         Self::guard_against_haystack_overflow(haystacks.len(), haystack_index_offset);
+        let start = matches.len();
         match &mut self.patterns {
             CompiledPatterns::Empty => {
                 let indices = (0..haystacks.len()).map(|i| i + haystack_index_offset as usize);
                 matches.extend(indices.map(Match::from_index));
+                return;
             }
+            // The backend filters by the min score itself
             CompiledPatterns::Single(pattern) => {
-                Self::dispatch_pattern_into(pattern, haystacks, haystack_index_offset, matches)
+                Self::dispatch_pattern_into(pattern, haystacks, haystack_index_offset, matches);
+                return;
             }
             CompiledPatterns::Multi(patterns) => {
                 Self::match_list_multi_into(patterns, haystacks, haystack_index_offset, matches)
             }
+            CompiledPatterns::Tree(query) => {
+                Self::match_list_query_into(query, haystacks, haystack_index_offset, matches)
+            }
         }
+        retain_min_score(matches, start, self.config.min_score);
     }
 
     fn dispatch_pattern_into<S: AsRef<str>>(
@@ -427,11 +674,14 @@ impl Matcher {
         haystack: S,
         index: u32,
     ) -> Option<MatchIndices> {
-        dispatch!(&mut pattern.backend, matcher => {
+        let haystack = haystack.as_ref();
+        let mut m = dispatch!(&mut pattern.backend, matcher => {
             dispatch_typos!(pattern.max_typos, pattern.needs_unicode, |TYPOS, UNICODE| {
-                unsafe { matcher.match_one_indices::<TYPOS, UNICODE, S>(haystack, index) }
+                unsafe { matcher.match_one_indices::<TYPOS, UNICODE, &str>(haystack, index) }
             })
-        })
+        })?;
+        pattern.expand_indices(haystack, &mut m.indices);
+        Some(m)
     }
 
     #[inline(always)]
@@ -446,6 +696,19 @@ impl Matcher {
     }
 
     fn get_backend(needle: &str, config: &Config) -> MatcherBackend {
+        #[cfg(feature = "regex")]
+        if config.matching.is_regex() {
+            return Self::get_regex_backend(needle, config);
+        }
+        if config.matching.is_glob() {
+            return Self::get_glob_backend(needle, config);
+        }
+        if config.matching.is_initials() {
+            return Self::get_initials_backend(needle, config);
+        }
+        if config.matching.is_path_segments() {
+            return Self::get_path_segments_backend(needle, config);
+        }
         if !config.matching.is_fuzzy() {
             return Self::get_literal_backend(needle, config);
         }
@@ -522,6 +785,115 @@ impl Matcher {
 
         MatcherBackend::LiteralScalar(unsafe { LiteralScalar::build(needle, config) })
     }
+
+    fn get_glob_backend(needle: &str, config: &Config) -> MatcherBackend {
+        #[cfg(target_arch = "x86_64")]
+        {
+            if GlobAVX512::is_available() {
+                return MatcherBackend::GlobAVX512(unsafe { GlobAVX512::build(needle, config) });
+            }
+            if GlobAVX::is_available() {
+                return MatcherBackend::GlobAVX(unsafe { GlobAVX::build(needle, config) });
+            }
+            if GlobSSE::is_available() {
+                return MatcherBackend::GlobSSE(unsafe { GlobSSE::build(needle, config) });
+            }
+        }
+
+        #[cfg(target_arch = "aarch64")]
+        {
+            if GlobNEON::is_available() {
+                return MatcherBackend::GlobNEON(unsafe { GlobNEON::build(needle, config) });
+            }
+        }
+
+        MatcherBackend::GlobScalar(unsafe { GlobScalar::build(needle, config) })
+    }
+
+    fn get_initials_backend(needle: &str, config: &Config) -> MatcherBackend {
+        #[cfg(target_arch = "x86_64")]
+        {
+            if InitialsAVX512::is_available() {
+                return MatcherBackend::InitialsAVX512(unsafe {
+                    InitialsAVX512::build(needle, config)
+                });
+            }
+            if InitialsAVX::is_available() {
+                return MatcherBackend::InitialsAVX(unsafe { InitialsAVX::build(needle, config) });
+            }
+            if InitialsSSE::is_available() {
+                return MatcherBackend::InitialsSSE(unsafe { InitialsSSE::build(needle, config) });
+            }
+        }
+
+        #[cfg(target_arch = "aarch64")]
+        {
+            if InitialsNEON::is_available() {
+                return MatcherBackend::InitialsNEON(unsafe {
+                    InitialsNEON::build(needle, config)
+                });
+            }
+        }
+
+        MatcherBackend::InitialsScalar(unsafe { InitialsScalar::build(needle, config) })
+    }
+
+    fn get_path_segments_backend(needle: &str, config: &Config) -> MatcherBackend {
+        #[cfg(target_arch = "x86_64")]
+        {
+            if PathSegmentsAVX512::is_available() {
+                return MatcherBackend::PathSegmentsAVX512(unsafe {
+                    PathSegmentsAVX512::build(needle, config)
+                });
+            }
+            if PathSegmentsAVX::is_available() {
+                return MatcherBackend::PathSegmentsAVX(unsafe {
+                    PathSegmentsAVX::build(needle, config)
+                });
+            }
+            if PathSegmentsSSE::is_available() {
+                return MatcherBackend::PathSegmentsSSE(unsafe {
+                    PathSegmentsSSE::build(needle, config)
+                });
+            }
+        }
+
+        #[cfg(target_arch = "aarch64")]
+        {
+            if PathSegmentsNEON::is_available() {
+                return MatcherBackend::PathSegmentsNEON(unsafe {
+                    PathSegmentsNEON::build(needle, config)
+                });
+            }
+        }
+
+        MatcherBackend::PathSegmentsScalar(unsafe { PathSegmentsScalar::build(needle, config) })
+    }
+
+    #[cfg(feature = "regex")]
+    fn get_regex_backend(needle: &str, config: &Config) -> MatcherBackend {
+        #[cfg(target_arch = "x86_64")]
+        {
+            if RegexAVX512::is_available() {
+                return MatcherBackend::RegexAVX512(unsafe { RegexAVX512::build(needle, config) });
+            }
+            if RegexAVX::is_available() {
+                return MatcherBackend::RegexAVX(unsafe { RegexAVX::build(needle, config) });
+            }
+            if RegexSSE::is_available() {
+                return MatcherBackend::RegexSSE(unsafe { RegexSSE::build(needle, config) });
+            }
+        }
+
+        #[cfg(target_arch = "aarch64")]
+        {
+            if RegexNEON::is_available() {
+                return MatcherBackend::RegexNEON(unsafe { RegexNEON::build(needle, config) });
+            }
+        }
+
+        MatcherBackend::RegexScalar(unsafe { RegexScalar::build(needle, config) })
+    }
 }
 
 #[cfg(test)]
@@ -784,6 +1156,56 @@ mod tests {
         assert!(is_u16);
     }
 
+    #[test]
+    fn initials_max_score_bounds_matches() {
+        let config = Config::default().matching(Matching::Initials);
+        // Long enough that the initials bonuses outgrow the fuzzy bound
+        let mut matcher = Matcher::new("GTDAB", &config);
+        let CompiledPatterns::Single(pattern) = &matcher.patterns else {
+            panic!("expected a single pattern");
+        };
+        let max_score = pattern.max_score;
+        let matches = matcher.match_list(&["GoToDefinitionAtBuffer", "go_to_def_at_buf"]);
+        assert_eq!(matches.len(), 1);
+        assert!(matches.iter().all(|m| m.score <= max_score));
+    }
+
+    #[test]
+    fn path_segments_max_score_bounds_matches() {
+        let config = Config::default().matching(Matching::PathSegments);
+        let mut matcher = Matcher::new("s/m/l/a", &config);
+        let CompiledPatterns::Single(pattern) = &matcher.patterns else {
+            panic!("expected a single pattern");
+        };
+        let max_score = pattern.max_score;
+        let matches = matcher.match_list(&["s/m/l/a", "S/M/L/A", "src/matcher/lib/algo.rs"]);
+        assert_eq!(matches.len(), 3);
+        assert!(matches.iter().all(|m| m.score <= max_score));
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn regex_max_score_bounds_matches() {
+        let config = Config::default().matching(Matching::Regex);
+        let haystacks = ["foobar", "FooBar", "foo_bar", "xx_foo_bar_baz"];
+
+        // Bounded by the max match length, whatever the haystack's length
+        let mut matcher = Matcher::new("fo{1,2}_?bar", &config);
+        let max_score = matcher.patterns.max_score(0);
+        assert_eq!(matcher.patterns.max_score(usize::MAX), max_score);
+        let matches = matcher.match_list(&haystacks);
+        assert_eq!(matches.len(), 4);
+        assert!(matches.iter().all(|m| m.score <= max_score));
+
+        // Bounded by the length of the longest haystack
+        let mut matcher = Matcher::new("fo+_?bar", &config);
+        let max_score = matcher.patterns.max_score(14);
+        assert!(max_score < matcher.patterns.max_score(15));
+        let matches = matcher.match_list(&haystacks);
+        assert_eq!(matches.len(), 4);
+        assert!(matches.iter().all(|m| m.score <= max_score));
+    }
+
     #[test]
     fn reuse_handles_state_changes() {
         let long_needle = "abcdefghijklmnopqrst";
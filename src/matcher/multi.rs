@@ -1,31 +1,178 @@
 use super::Matcher;
 use super::backend::MatcherBackend;
+use super::query::CompiledQuery;
 use crate::{Match, MatchIndices};
 
 /// Patterns matched independently, where a haystack matches when all of the
-/// non-negated patterns match and none of the negated patterns match.
+/// non-negated patterns match and none of the negated patterns match. Consecutive patterns
+/// joined into an OR-group match when any of the group's patterns pass. Queries that can't
+/// be expressed as OR-groups are matched as a tree.
 #[allow(clippy::large_enum_variant)]
 #[derive(Debug, Clone)]
 pub(super) enum CompiledPatterns {
     Empty,
     Single(CompiledPattern),
     Multi(Vec<CompiledPattern>),
+    Tree(CompiledQuery),
 }
 
 impl CompiledPatterns {
+    pub(super) fn new(mut patterns: Vec<CompiledPattern>) -> Self {
+        match patterns.as_slice() {
+            [] => CompiledPatterns::Empty,
+            [single] if !single.negated => CompiledPatterns::Single(patterns.pop().unwrap()),
+            _ => CompiledPatterns::Multi(patterns),
+        }
+    }
+
     pub(super) fn is_empty(&self) -> bool {
         matches!(self, CompiledPatterns::Empty)
     }
+
+    /// Upper bound on the combined score of any haystack up to `max_haystack_len` bytes
+    pub(super) fn max_score(&self, max_haystack_len: usize) -> u16 {
+        match self {
+            CompiledPatterns::Empty => 0,
+            CompiledPatterns::Single(pattern) => pattern.max_score_for(max_haystack_len),
+            CompiledPatterns::Multi(patterns) => groups(patterns)
+                .map(|group| {
+                    let max_scores = group
+                        .iter()
+                        .filter(|p| !p.negated)
+                        .map(|p| p.max_score_for(max_haystack_len));
+                    max_scores.max().unwrap_or(0)
+                })
+                .fold(0, |sum, max_score| sum.saturating_add(max_score)),
+            CompiledPatterns::Tree(query) => query.max_score(max_haystack_len),
+        }
+    }
+
+    /// Raises the min score of the non-negated patterns, so that their backends skip the
+    /// haystacks whose combined score can't reach `floor`, or restores their own min scores
+    /// when 0. A pattern must make up whatever the other groups can't, at their max scores.
+    /// Query trees are left as is
+    ///
+    /// A single pattern also filters by `min_score` (see [`crate::Config::min_score`]) unless
+    /// it overrides it, skipping the haystacks too short to reach it. Multiple patterns may
+    /// make up for each other's scores, so each only skips the haystacks which can't reach it
+    /// with the others' max scores, and the matcher checks their combined score
+    pub(super) fn set_score_floor(&mut self, floor: u16, min_score: u16) {
+        match self {
+            CompiledPatterns::Empty | CompiledPatterns::Tree(_) => {}
+            CompiledPatterns::Single(pattern) => {
+                pattern.set_score_floor(floor.max(pattern.min_score.unwrap_or(min_score)))
+            }
+            CompiledPatterns::Multi(patterns) => {
+                let group_max_scores = groups(patterns)
+                    .map(|group| {
+                        let max_scores = group
+                            .iter()
+                            .filter(|p| !p.negated)
+                            .map(|p| p.max_score_for(usize::MAX));
+                        max_scores.max().unwrap_or(0) as u32
+                    })
+                    .collect::<Vec<_>>();
+                let max_score = group_max_scores.iter().sum::<u32>();
+                let floor = floor.max(min_score);
+                for (group, group_max_score) in groups_mut(patterns).zip(group_max_scores) {
+                    let others = (max_score - group_max_score).min(u16::MAX as u32) as u16;
+                    for pattern in group.iter_mut().filter(|p| !p.negated) {
+                        pattern.set_score_floor(floor.saturating_sub(others));
+                    }
+                }
+            }
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
 pub(super) struct CompiledPattern {
     pub(super) negated: bool,
+    /// Alternative to the previous pattern in the same OR-group. Always false for the first
+    /// pattern of a group
+    pub(super) or: bool,
+    /// Name of the field to match against, when matching fields (see [`crate::Pattern::field`])
+    pub(super) field: Option<String>,
     pub(super) needs_unicode: bool,
+    /// Extends the indices to whole graphemes (see [`crate::UnicodeMatching::Graphemes`])
+    #[cfg(feature = "graphemes")]
+    pub(super) graphemes: bool,
     pub(super) max_typos: Option<u16>,
+    /// Matches scoring below this are filtered out, whatever the other patterns score (see
+    /// [`crate::PatternConfig::min_score`])
+    pub(super) min_score: Option<u16>,
+    /// Upper bound on the score of any haystack (see [`crate::Scoring::max_score`]), on top of
+    /// [`Self::max_score_per_byte`] for each byte of the haystack
+    pub(super) max_score: u16,
+    /// Upper bound on the score of each haystack byte, for regexes whose matches can be of any
+    /// length. 0 for every other pattern, whose score only depends on the needle
+    pub(super) max_score_per_byte: u16,
     pub(super) backend: MatcherBackend,
 }
 
+impl CompiledPattern {
+    /// Upper bound on the score of any haystack up to `max_haystack_len` bytes
+    pub(super) fn max_score_for(&self, max_haystack_len: usize) -> u16 {
+        (self.max_score_per_byte as usize)
+            .saturating_mul(max_haystack_len)
+            .saturating_add(self.max_score as usize)
+            .min(u16::MAX as usize) as u16
+    }
+
+    /// Filters out the matches scoring below `floor`, on top of the pattern's own min score
+    fn set_score_floor(&mut self, floor: u16) {
+        let min_score = self.min_score.unwrap_or(0).max(floor);
+        dispatch!(&mut self.backend, matcher => matcher.set_min_score(min_score));
+    }
+
+    /// Extends the indices of a match to whole graphemes, when matching graphemes
+    #[inline(always)]
+    pub(super) fn expand_indices(&self, haystack: &str, indices: &mut Vec<u32>) {
+        #[cfg(feature = "graphemes")]
+        if self.graphemes {
+            crate::graphemes::expand_indices(haystack, indices);
+        }
+        #[cfg(not(feature = "graphemes"))]
+        let _ = (haystack, indices);
+    }
+}
+
+/// Splits the patterns into OR-groups, where a pattern without an alternative is a group of one
+pub(super) fn groups(patterns: &[CompiledPattern]) -> impl Iterator<Item = &[CompiledPattern]> {
+    patterns.chunk_by(|_, next| next.or)
+}
+
+/// Mutable version of [`groups`]
+pub(super) fn groups_mut(
+    patterns: &mut [CompiledPattern],
+) -> impl Iterator<Item = &mut [CompiledPattern]> {
+    patterns.chunk_by_mut(|_, next| next.or)
+}
+
+/// Drops the matches from `start` on scoring below `min_score`, checked against the combined
+/// score of the patterns (see [`CompiledPatterns::set_score_floor`])
+pub(super) fn retain_min_score(matches: &mut Vec<Match>, start: usize, min_score: u16) {
+    if min_score == 0 {
+        return;
+    }
+    let mut position = 0;
+    matches.retain(|m| {
+        position += 1;
+        position <= start || m.score >= min_score
+    });
+}
+
+/// Keeps the best passing alternative of an OR-group, preferring earlier alternatives on ties
+pub(super) fn keep_best<M>(best: &mut Option<M>, passed: Option<M>, score: impl Fn(&M) -> u16) {
+    if let Some(passed) = passed
+        && best
+            .as_ref()
+            .is_none_or(|best| score(&passed) > score(best))
+    {
+        *best = Some(passed);
+    }
+}
+
 impl Matcher {
     pub(super) fn match_one_multi<S: AsRef<str>>(
         patterns: &mut [CompiledPattern],
@@ -34,20 +181,25 @@ impl Matcher {
     ) -> Option<Match> {
         let haystack = haystack.as_ref();
         let mut combined = Match::from_index(index as usize);
-        for pattern in patterns {
-            let result = Self::dispatch_pattern_one(pattern, haystack, index);
-            if pattern.negated {
-                if result.is_some() {
-                    return None;
-                }
-            } else {
-                let m = result?;
-                combined.score = combined.score.saturating_add(m.score);
-                combined.exact |= m.exact;
-                #[cfg(feature = "match_end_col")]
-                {
-                    combined.end_col = combined.end_col.max(m.end_col);
-                }
+        for group in groups_mut(patterns) {
+            let mut best = None;
+            for pattern in group {
+                let result = Self::dispatch_pattern_one(pattern, haystack, index);
+                // A negated pattern passes, scoring 0, when it doesn't match
+                let passed = if pattern.negated {
+                    result.is_none().then(|| Match::from_index(index as usize))
+                } else {
+                    result
+                };
+                keep_best(&mut best, passed, |m| m.score);
+            }
+
+            let m = best?;
+            combined.score = combined.score.saturating_add(m.score);
+            combined.exact |= m.exact;
+            #[cfg(feature = "match_end_col")]
+            {
+                combined.end_col = combined.end_col.max(m.end_col);
             }
         }
         Some(combined)
@@ -60,17 +212,23 @@ impl Matcher {
     ) -> Option<MatchIndices> {
         let haystack = haystack.as_ref();
         let mut combined = MatchIndices::from_index(index as usize);
-        for pattern in patterns {
-            if pattern.negated {
-                if Self::dispatch_pattern_one(pattern, haystack, index).is_some() {
-                    return None;
-                }
-            } else {
-                let m = Self::dispatch_pattern_one_indices(pattern, haystack, index)?;
-                combined.score = combined.score.saturating_add(m.score);
-                combined.exact |= m.exact;
-                combined.indices.extend(m.indices);
+        for group in groups_mut(patterns) {
+            let mut best = None;
+            for pattern in group {
+                let passed = if pattern.negated {
+                    Self::dispatch_pattern_one(pattern, haystack, index)
+                        .is_none()
+                        .then(|| MatchIndices::from_index(index as usize))
+                } else {
+                    Self::dispatch_pattern_one_indices(pattern, haystack, index)
+                };
+                keep_best(&mut best, passed, |m| m.score);
             }
+
+            let m = best?;
+            combined.score = combined.score.saturating_add(m.score);
+            combined.exact |= m.exact;
+            combined.indices.extend(m.indices);
         }
         // Indices are reported in reverse order, and patterns may share matched chars.
         combined.indices.sort_unstable_by(|a, b| b.cmp(a));
@@ -81,22 +239,28 @@ impl Matcher {
     /// Matches multiple patterns by matching the first non-negated pattern against every
     /// haystack, then re-matching each remaining pattern against only the haystacks that
     /// survived the previous patterns. Scores are summed across the non-negated patterns.
+    ///
+    /// OR-groups are matched the same way, alternative by alternative, keeping the candidates
+    /// passing any of them and adding the best alternative's score.
     pub(super) fn match_list_multi_into<S: AsRef<str>>(
         patterns: &mut [CompiledPattern],
         haystacks: &[S],
         haystack_index_offset: u32,
         matches: &mut Vec<Match>,
     ) {
-        let base_pattern_idx = patterns.iter().position(|p| !p.negated);
+        let mut groups = groups_mut(patterns).collect::<Vec<_>>();
+        let base_group_idx = groups
+            .iter()
+            .position(|group| matches!(group, [pattern] if !pattern.negated));
         let mut candidates = Vec::new();
-        match base_pattern_idx {
+        match base_group_idx {
             Some(i) => Self::dispatch_pattern_into(
-                &mut patterns[i],
+                &mut groups[i][0],
                 haystacks,
                 haystack_index_offset,
                 &mut candidates,
             ),
-            // All patterns are negated, so every haystack is a candidate.
+            // Every group is negated or has alternatives, so every haystack is a candidate.
             None => {
                 let indices = (0..haystacks.len()).map(|i| i + haystack_index_offset as usize);
                 candidates.extend(indices.map(Match::from_index));
@@ -105,8 +269,9 @@ impl Matcher {
 
         let mut gathered: Vec<&str> = Vec::new();
         let mut hits: Vec<Match> = Vec::new();
-        for (pattern_idx, pattern) in patterns.iter_mut().enumerate() {
-            if Some(pattern_idx) == base_pattern_idx || candidates.is_empty() {
+        let mut best: Vec<Option<Match>> = Vec::new();
+        for (group_idx, group) in groups.into_iter().enumerate() {
+            if Some(group_idx) == base_group_idx || candidates.is_empty() {
                 continue;
             }
 
@@ -116,40 +281,82 @@ impl Matcher {
                     .iter()
                     .map(|m| haystacks[(m.index - haystack_index_offset) as usize].as_ref()),
             );
-            hits.clear();
-            Self::dispatch_pattern_into(pattern, &gathered, 0, &mut hits);
-
-            // Backends emit matches in input order, so `hit.index` is the position of the
-            // candidate it matched.
-            if pattern.negated {
-                // `retain` visits in order, so the counter tracks each candidate's position.
-                let mut hits = hits.iter().peekable();
-                let mut position = 0;
-                candidates.retain(|_| {
-                    let matched = hits.next_if(|hit| hit.index as usize == position).is_some();
-                    position += 1;
-                    !matched
-                });
-            } else {
-                candidates = hits
-                    .drain(..)
-                    .map(|mut hit| {
-                        let candidate = candidates[hit.index as usize];
-                        hit.index = candidate.index;
-                        hit.score = hit.score.saturating_add(candidate.score);
-                        hit.exact |= candidate.exact;
-                        #[cfg(feature = "match_end_col")]
-                        {
-                            hit.end_col = hit.end_col.max(candidate.end_col);
-                        }
-                        hit
-                    })
-                    .collect();
+
+            if let [pattern] = group {
+                hits.clear();
+                Self::dispatch_pattern_into(pattern, &gathered, 0, &mut hits);
+                Self::apply_hits(pattern.negated, &mut hits, &mut candidates);
+                continue;
+            }
+
+            best.clear();
+            best.resize(candidates.len(), None);
+            for pattern in group.iter_mut() {
+                hits.clear();
+                Self::dispatch_pattern_into(pattern, &gathered, 0, &mut hits);
+
+                // Backends emit matches in input order, so `hit.index` is the position of the
+                // candidate it matched.
+                if pattern.negated {
+                    let mut hits = hits.iter().peekable();
+                    for (position, best) in best.iter_mut().enumerate() {
+                        let matched = hits.next_if(|hit| hit.index as usize == position).is_some();
+                        keep_best(best, (!matched).then(Match::default), |m| m.score);
+                    }
+                } else {
+                    for hit in hits.drain(..) {
+                        keep_best(&mut best[hit.index as usize], Some(hit), |m| m.score);
+                    }
+                }
             }
+
+            let mut best = best.iter();
+            candidates.retain_mut(|candidate| match best.next().copied().flatten() {
+                Some(m) => {
+                    candidate.score = candidate.score.saturating_add(m.score);
+                    candidate.exact |= m.exact;
+                    #[cfg(feature = "match_end_col")]
+                    {
+                        candidate.end_col = candidate.end_col.max(m.end_col);
+                    }
+                    true
+                }
+                None => false,
+            });
         }
 
         matches.extend(candidates);
     }
+
+    /// Narrows the candidates to those matched by a single pattern's `hits` (or not matched,
+    /// when negated), adding the hits' scores
+    pub(super) fn apply_hits(negated: bool, hits: &mut Vec<Match>, candidates: &mut Vec<Match>) {
+        if negated {
+            // `retain` visits in order, so the counter tracks each candidate's position.
+            let mut hits = hits.iter().peekable();
+            let mut position = 0;
+            candidates.retain(|_| {
+                let matched = hits.next_if(|hit| hit.index as usize == position).is_some();
+                position += 1;
+                !matched
+            });
+        } else {
+            *candidates = hits
+                .drain(..)
+                .map(|mut hit| {
+                    let candidate = candidates[hit.index as usize];
+                    hit.index = candidate.index;
+                    hit.score = hit.score.saturating_add(candidate.score);
+                    hit.exact |= candidate.exact;
+                    #[cfg(feature = "match_end_col")]
+                    {
+                        hit.end_col = hit.end_col.max(candidate.end_col);
+                    }
+                    hit
+                })
+                .collect();
+        }
+    }
 }
 
 #[cfg(test)]
@@ -221,6 +428,90 @@ mod tests {
         assert_eq!(matches.iter().map(|m| m.index).collect::<Vec<_>>(), vec![1]);
     }
 
+    #[test]
+    fn multi_pattern_min_score_floors_each_pattern() {
+        let haystacks = ["foo bar", "xfxoxo bar"];
+        let config = Config::default().sort(SortStrategy::IndexAsc);
+        let min_score = multi("foo bar", &config).match_list(&haystacks)[0].score;
+        let matches = multi("foo bar", &config.clone().min_score(min_score)).match_list(&haystacks);
+        assert_eq!(matches.iter().map(|m| m.index).collect::<Vec<_>>(), vec![0]);
+
+        // Each pattern skips the haystacks which can't reach the min score, even with the
+        // other patterns' max scores
+        let mut matcher = multi("foo bar", &config.min_score(u16::MAX));
+        let CompiledPatterns::Multi(patterns) = &mut matcher.patterns else {
+            panic!("expected multiple patterns");
+        };
+        let mut hits = vec![];
+        Matcher::dispatch_pattern_into(&mut patterns[0], &haystacks, 0, &mut hits);
+        assert!(hits.is_empty(), "hits: {hits:?}");
+    }
+
+    #[test]
+    fn multi_pattern_or_groups() {
+        let haystacks = [
+            "core/main.go",
+            "core/x.rb",
+            "core/x.py",
+            "core/x.c",
+            "lib/x.go",
+        ];
+        let config = Config::default().sort(SortStrategy::IndexAsc);
+        let matches = multi("^core go$ | rb$ | py$", &config).match_list(&haystacks);
+        assert_eq!(
+            matches.iter().map(|m| m.index).collect::<Vec<_>>(),
+            vec![0, 1, 2]
+        );
+
+        // A leading OR-group filters on its own
+        let matches = multi("go$ | rb$ ^core", &config).match_list(&haystacks);
+        assert_eq!(
+            matches.iter().map(|m| m.index).collect::<Vec<_>>(),
+            vec![0, 1]
+        );
+    }
+
+    #[test]
+    fn multi_pattern_or_group_negation() {
+        let haystacks = ["foo", "foobar", "baz", "qux", "bar"];
+        let config = Config::default().sort(SortStrategy::IndexAsc);
+
+        // Matches when either "foo" is present or "bar" is absent
+        let matches = multi("foo | !bar", &config).match_list(&haystacks);
+        assert_eq!(
+            matches.iter().map(|m| m.index).collect::<Vec<_>>(),
+            vec![0, 1, 2, 3]
+        );
+
+        let matches = multi("foo !bar | baz", &config).match_list(&haystacks);
+        assert_eq!(matches.iter().map(|m| m.index).collect::<Vec<_>>(), vec![0]);
+    }
+
+    #[test]
+    fn multi_pattern_or_group_scores_best_alternative() {
+        let haystacks = ["foo", "xfxoxox", "bar"];
+        let config = Config::default().sort(SortStrategy::IndexAsc);
+        let foo = Matcher::new("foo", &config).match_list(&haystacks);
+        let matches = multi("fxo | foo", &config).match_list(&haystacks);
+
+        assert_eq!(
+            matches.iter().map(|m| m.index).collect::<Vec<_>>(),
+            vec![0, 1]
+        );
+        assert_eq!(matches[0].score, foo[0].score);
+        assert_eq!(matches[0].exact, foo[0].exact);
+        assert!(matches[1].score >= foo[1].score);
+    }
+
+    #[test]
+    fn multi_pattern_or_group_max_score() {
+        let config = Config::default();
+        let max = |query: &str| multi(query, &config).patterns.max_score(usize::MAX);
+        assert_eq!(max("foo | ba"), max("foo"));
+        assert_eq!(max("fo | bar !baz"), max("bar"));
+        assert_eq!(max("a | b c"), max("a c"));
+    }
+
     #[test]
     fn multi_pattern_contradiction_is_empty() {
         let haystacks = ["foo", "foobar"];
@@ -240,7 +531,16 @@ mod tests {
     #[test]
     fn multi_pattern_match_iter_matches_match_list() {
         let haystacks = ["foobar", "foo", "barfoo", "bar", "qux", "FooBar"];
-        for query in ["foo !bar", "foo bar", "!foo", "^foo bar$", "foo !^bar"] {
+        for query in [
+            "foo !bar",
+            "foo bar",
+            "!foo",
+            "^foo bar$",
+            "foo !^bar",
+            "foo | qux",
+            "bar$ | ^qux !^bar",
+            "foo | !bar",
+        ] {
             let config = Config::default().sort(SortStrategy::IndexAsc);
             let mut matcher = multi(query, &config);
             let from_iter = matcher.match_iter(haystacks.iter()).collect::<Vec<_>>();
@@ -252,7 +552,14 @@ mod tests {
     #[test]
     fn multi_pattern_match_list_indices_matches_match_list() {
         let haystacks = ["foobar", "foo", "barfoo", "bar", "qux", "FooBar"];
-        for query in ["foo !bar", "foo bar", "!foo", "foo fo"] {
+        for query in [
+            "foo !bar",
+            "foo bar",
+            "!foo",
+            "foo fo",
+            "foo | qux",
+            "bar | fo !qux",
+        ] {
             let config = Config::default().sort(SortStrategy::IndexAsc);
             let mut matcher = multi(query, &config);
             let matches = matcher.match_list(&haystacks);
@@ -1,13 +1,55 @@
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::fmt;
+use std::ops::Range;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::thread;
 
 use super::Matcher;
-use crate::k_merge::{
-    k_merge_matches_by_index_asc, k_merge_matches_by_index_desc,
-    k_merge_matches_by_score_then_index_asc, k_merge_matches_by_score_then_index_desc,
-};
-use crate::sort::radix_sort_matches;
-use crate::{Match, SortStrategy};
+use crate::Match;
+use crate::k_merge::k_merge_matches_by_sort;
+
+/// Smaller chunks enable better load balancing via stealing
+/// but too small increases atomic contention
+pub(crate) const CHUNK_SIZE: usize = 2048;
+
+/// Shared flag for aborting a [`Matcher::match_list_parallel_cancellable`] call from another
+/// thread, for example when the query changes before matching finishes
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requests cancellation. Threads stop after finishing their current chunk
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Returned when a [`CancellationToken`] aborts matching, holding the matches of the
+/// haystacks matched before cancellation
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cancelled {
+    /// Matches of the first `matched_len` haystacks, ordered according to
+    /// [`crate::Config::sort`]
+    pub matches: Vec<Match>,
+    /// Number of haystacks, from the start of the list, that were matched
+    pub matched_len: usize,
+}
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "matching cancelled after {} haystacks", self.matched_len)
+    }
+}
+
+impl std::error::Error for Cancelled {}
 
 impl Matcher {
     /// Matches a list of haystacks in parallel on multiple real threads, returning a list of
@@ -20,78 +62,163 @@ impl Matcher {
         haystacks: &[S],
         threads: usize,
     ) -> Vec<Match> {
-        Self::guard_against_haystack_overflow(haystacks.len(), 0);
-        assert!(threads > 0, "threads must be positive");
-
-        // Limit threads based on the number of haystacks
-        let threads = threads.min(haystacks.len().div_ceil(2000)).max(1);
+        let threads = Self::parallel_threads(haystacks.len(), threads);
 
         if haystacks.is_empty() || self.patterns.is_empty() || threads == 1 {
             return self.match_list(haystacks);
         }
 
-        // Smaller chunks enable better load balancing via stealing
-        // but too small increases atomic contention
-        let chunk_size = 2048;
-        let num_chunks = haystacks.len().div_ceil(chunk_size);
+        let (matches, _) = self.match_chunks_parallel(
+            haystacks.len(),
+            threads,
+            |matcher, range, matches| matcher.match_haystacks_chunk(haystacks, range, matches),
+            || false,
+            |_| {},
+        );
+        matches
+    }
+
+    /// Cancellable version of [`Matcher::match_list_parallel`]. The `token` is checked before
+    /// each 2048 item chunk is claimed, and `progress`, when provided, is called with the
+    /// number of completed chunks and the total number of chunks after each chunk completes.
+    /// The `threads` must be >0.
+    ///
+    /// Chunks are claimed in index order, so when cancelled, the matched haystacks form a
+    /// prefix of the list and [`Cancelled::matches`] is ordered as if only that prefix had been
+    /// matched.
+    ///
+    /// ```
+    /// use frizbee::{CancellationToken, Config, Matcher};
+    ///
+    /// let haystacks = vec!["fooBar"; 10_000];
+    /// let mut matcher = Matcher::new("fbr", &Config::default());
+    ///
+    /// let token = CancellationToken::new();
+    /// let cancel_halfway = |completed, total| {
+    ///     if completed * 2 >= total {
+    ///         token.cancel();
+    ///     }
+    /// };
+    /// let cancelled = matcher
+    ///     .match_list_parallel_cancellable(&haystacks, 1, &token, Some(&cancel_halfway))
+    ///     .unwrap_err();
+    /// assert_eq!(cancelled.matched_len, 3 * 2048);
+    /// assert_eq!(cancelled.matches.len(), 3 * 2048);
+    /// ```
+    pub fn match_list_parallel_cancellable<S: AsRef<str> + Sync>(
+        &mut self,
+        haystacks: &[S],
+        threads: usize,
+        token: &CancellationToken,
+        progress: Option<&(dyn Fn(usize, usize) + Sync)>,
+    ) -> Result<Vec<Match>, Cancelled> {
+        let threads = Self::parallel_threads(haystacks.len(), threads);
+
+        let num_chunks = haystacks.len().div_ceil(CHUNK_SIZE);
+        let (matches, matched_len) = self.match_chunks_parallel(
+            haystacks.len(),
+            threads,
+            |matcher, range, matches| matcher.match_haystacks_chunk(haystacks, range, matches),
+            || token.is_cancelled(),
+            |completed| {
+                if let Some(progress) = progress {
+                    progress(completed, num_chunks);
+                }
+            },
+        );
+
+        if matched_len < haystacks.len() {
+            Err(Cancelled {
+                matches,
+                matched_len,
+            })
+        } else {
+            Ok(matches)
+        }
+    }
+
+    /// Validates the arguments of a parallel match over `len` items, returning the number of
+    /// threads worth spawning for them
+    pub(super) fn parallel_threads(len: usize, threads: usize) -> usize {
+        Self::guard_against_haystack_overflow(len, 0);
+        assert!(threads > 0, "threads must be positive");
+
+        // Limit threads based on the number of haystacks
+        threads.min(len.div_ceil(2000)).max(1)
+    }
+
+    /// Matches `len` items in 2048 item chunks on `threads` threads until every chunk has been
+    /// claimed or `stop` returns true. Each chunk's range of items is matched by `match_chunk`,
+    /// after which `on_chunk` is called with the number of completed chunks.
+    ///
+    /// Chunks are claimed in index order, so the matched items always form a prefix of the
+    /// list. Returns the matches ordered according to [`crate::Config::sort`], along with the
+    /// length of the prefix.
+    pub(super) fn match_chunks_parallel(
+        &self,
+        len: usize,
+        threads: usize,
+        match_chunk: impl Fn(&mut Matcher, Range<usize>, &mut Vec<Match>) + Sync,
+        stop: impl Fn() -> bool + Sync,
+        on_chunk: impl Fn(usize) + Sync,
+    ) -> (Vec<Match>, usize) {
+        let num_chunks = len.div_ceil(CHUNK_SIZE);
         let next_chunk = AtomicUsize::new(0);
+        let completed_chunks = AtomicUsize::new(0);
+
+        let worker = || {
+            let mut local_matches = Vec::new();
+            let mut matcher = self.clone();
 
-        let matcher = &*self;
-
-        thread::scope(|s| {
-            let handles: Vec<_> = (0..threads)
-                .map(|_| {
-                    s.spawn(|| {
-                        let mut local_matches = Vec::new();
-                        let mut matcher = matcher.clone();
-
-                        loop {
-                            // Claim next available chunk
-                            let chunk_idx = next_chunk.fetch_add(1, Ordering::Relaxed);
-                            if chunk_idx >= num_chunks {
-                                break;
-                            }
-
-                            let start = chunk_idx * chunk_size;
-                            let end = (start + chunk_size).min(haystacks.len());
-                            let haystacks_chunk = &haystacks[start..end];
-
-                            matcher.match_list_into(
-                                haystacks_chunk,
-                                start as u32,
-                                &mut local_matches,
-                            );
-                        }
-
-                        // Each thread sorts so that we can perform k-way merge
-                        if matcher.config.sort.is_reversed() {
-                            local_matches.reverse();
-                        }
-                        if matcher.config.sort.is_by_score() {
-                            radix_sort_matches(&mut local_matches);
-                        }
-
-                        local_matches
-                    })
-                })
-                .collect();
-
-            let matches = handles.into_iter().map(|h| h.join().unwrap()).collect();
-            match matcher.config.sort {
-                SortStrategy::ScoreThenIndexAsc => k_merge_matches_by_score_then_index_asc(matches),
-                SortStrategy::ScoreThenIndexDesc => {
-                    k_merge_matches_by_score_then_index_desc(matches)
+            while !stop() {
+                // Claim next available chunk
+                let chunk_idx = next_chunk.fetch_add(1, Ordering::Relaxed);
+                if chunk_idx >= num_chunks {
+                    break;
                 }
-                SortStrategy::IndexAsc => k_merge_matches_by_index_asc(matches),
-                SortStrategy::IndexDesc => k_merge_matches_by_index_desc(matches),
+
+                let start = chunk_idx * CHUNK_SIZE;
+                let end = (start + CHUNK_SIZE).min(len);
+                match_chunk(&mut matcher, start..end, &mut local_matches);
+                on_chunk(completed_chunks.fetch_add(1, Ordering::Relaxed) + 1);
             }
-        })
+
+            // Each thread sorts so that we can perform k-way merge
+            matcher.sort_matches(&mut local_matches);
+            local_matches
+        };
+
+        let runs = if threads == 1 {
+            vec![worker()]
+        } else {
+            thread::scope(|s| {
+                let handles: Vec<_> = (0..threads).map(|_| s.spawn(worker)).collect();
+                handles.into_iter().map(|h| h.join().unwrap()).collect()
+            })
+        };
+
+        let claimed_chunks = next_chunk.into_inner().min(num_chunks);
+        let matched_len = (claimed_chunks * CHUNK_SIZE).min(len);
+        (k_merge_matches_by_sort(runs, self.config.sort), matched_len)
+    }
+
+    fn match_haystacks_chunk<S: AsRef<str>>(
+        &mut self,
+        haystacks: &[S],
+        range: Range<usize>,
+        matches: &mut Vec<Match>,
+    ) {
+        let start = range.start as u32;
+        self.match_list_into(&haystacks[range], start, matches);
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{Config, Matcher};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::{CHUNK_SIZE, CancellationToken};
+    use crate::{Config, Matcher, SortStrategy};
 
     fn thread_counts() -> &'static [usize] {
         if cfg!(miri) {
@@ -172,4 +299,85 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn cancellable_completes_without_cancellation() {
+        let haystacks = (0..5000)
+            .map(|index| format!("abc-{index}"))
+            .collect::<Vec<_>>();
+        let config = Config::default();
+        let sequential = Matcher::new("abc", &config).match_list(&haystacks);
+
+        let token = CancellationToken::new();
+        for &threads in thread_counts() {
+            let completed = AtomicUsize::new(0);
+            let progress = |_, total| {
+                assert_eq!(total, 3);
+                completed.fetch_add(1, Ordering::Relaxed);
+            };
+            let matches = Matcher::new("abc", &config)
+                .match_list_parallel_cancellable(&haystacks, threads, &token, Some(&progress))
+                .unwrap();
+            assert_eq!(matches, sequential, "threads={threads}");
+            assert_eq!(completed.into_inner(), 3, "threads={threads}");
+        }
+    }
+
+    #[test]
+    fn cancelled_returns_ordered_prefix() {
+        let haystacks = (0..10_000)
+            .map(|index| format!("{}-{index}", ["abc", "xabc", "a_b_c"][index % 3]))
+            .collect::<Vec<_>>();
+
+        for sort in [SortStrategy::ScoreThenIndexAsc, SortStrategy::IndexDesc] {
+            let config = Config::default().sort(sort);
+            for &threads in thread_counts() {
+                let token = CancellationToken::new();
+                let cancel = |completed, _| {
+                    if completed == 2 {
+                        token.cancel();
+                    }
+                };
+                let result = Matcher::new("abc", &config).match_list_parallel_cancellable(
+                    &haystacks,
+                    threads,
+                    &token,
+                    Some(&cancel),
+                );
+
+                // Chunks claimed before the cancellation was observed still complete, so with
+                // many threads every chunk may be claimed before cancelling
+                let (matches, matched_len) = match result {
+                    Ok(matches) => (matches, haystacks.len()),
+                    Err(cancelled) => (cancelled.matches, cancelled.matched_len),
+                };
+                if threads == 1 {
+                    assert_eq!(matched_len, 2 * CHUNK_SIZE);
+                }
+                assert!(matched_len >= 2 * CHUNK_SIZE);
+                assert_eq!(
+                    matches,
+                    Matcher::new("abc", &config).match_list(&haystacks[..matched_len]),
+                    "sort={sort:?}, threads={threads}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn cancelled_before_start_matches_nothing() {
+        let token = CancellationToken::new();
+        token.cancel();
+        let cancelled = Matcher::new("a", &Config::default())
+            .match_list_parallel_cancellable(&["a"; 3000], 2, &token, None)
+            .unwrap_err();
+        assert_eq!(cancelled.matched_len, 0);
+        assert!(cancelled.matches.is_empty());
+
+        // Nothing to cancel when there are no haystacks
+        let matches = Matcher::new("a", &Config::default())
+            .match_list_parallel_cancellable::<&str>(&[], 2, &token, None)
+            .unwrap();
+        assert!(matches.is_empty());
+    }
 }
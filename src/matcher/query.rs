@@ -0,0 +1,529 @@
+use super::Matcher;
+use super::multi::{CompiledPattern, keep_best};
+use crate::{Config, Match, MatchIndices, Query};
+
+/// A [`Query`] compiled into a tree of patterns, where a pattern matches when it matches the
+/// haystack, or when it doesn't if negated. Children of [`CompiledQuery::And`] are ordered
+/// cheapest first, so that literal patterns narrow the candidates before fuzzy ones.
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone)]
+pub(super) enum CompiledQuery {
+    Pattern(CompiledPattern),
+    Not(Box<CompiledQuery>),
+    /// Matches every haystack when empty
+    And(Vec<CompiledQuery>),
+    Or(Vec<CompiledQuery>),
+}
+
+impl CompiledQuery {
+    /// Compiles the patterns of the query (see [`Matcher::compile`]), simplifying the tree
+    /// by flattening nested nodes of the same type and folding negations into the patterns.
+    /// Empty needles match every haystack, and so does any OR node containing one.
+    pub(super) fn compile(query: &Query, config: &Config) -> Self {
+        match query {
+            Query::Atom(source) => match Matcher::compile(source, config) {
+                Some(mut pattern) => {
+                    pattern.or = false;
+                    CompiledQuery::Pattern(pattern)
+                }
+                None => CompiledQuery::And(vec![]),
+            },
+            Query::Not(query) => match Self::compile(query, config) {
+                CompiledQuery::Pattern(mut pattern) => {
+                    pattern.negated = !pattern.negated;
+                    CompiledQuery::Pattern(pattern)
+                }
+                CompiledQuery::Not(query) => *query,
+                query => CompiledQuery::Not(Box::new(query)),
+            },
+            Query::And(queries) => {
+                let mut children = vec![];
+                for child in queries.iter().map(|query| Self::compile(query, config)) {
+                    match child {
+                        CompiledQuery::And(nested) => children.extend(nested),
+                        child => children.push(child),
+                    }
+                }
+                children.sort_by_key(Self::cost);
+                Self::unwrap_single(children, CompiledQuery::And)
+            }
+            Query::Or(queries) => {
+                let mut children = vec![];
+                for child in queries.iter().map(|query| Self::compile(query, config)) {
+                    match child {
+                        child if child.matches_everything() => return CompiledQuery::And(vec![]),
+                        CompiledQuery::Or(nested) => children.extend(nested),
+                        child => children.push(child),
+                    }
+                }
+                Self::unwrap_single(children, CompiledQuery::Or)
+            }
+        }
+    }
+
+    fn unwrap_single(mut children: Vec<Self>, node: impl Fn(Vec<Self>) -> Self) -> Self {
+        match children.len() {
+            0 => CompiledQuery::And(vec![]),
+            1 => children.pop().unwrap(),
+            _ => node(children),
+        }
+    }
+
+    fn matches_everything(&self) -> bool {
+        matches!(self, CompiledQuery::And(children) if children.is_empty())
+    }
+
+    /// Number of patterns in the query, which are indexed in depth-first order
+    pub(super) fn num_patterns(&self) -> usize {
+        match self {
+            CompiledQuery::Pattern(_) => 1,
+            CompiledQuery::Not(query) => query.num_patterns(),
+            CompiledQuery::And(children) | CompiledQuery::Or(children) => {
+                children.iter().map(Self::num_patterns).sum()
+            }
+        }
+    }
+
+    /// Relative cost of matching the query, where literal matching is cheaper than fuzzy
+    /// matching, which is cheaper with typos disabled
+    fn cost(&self) -> usize {
+        match self {
+            CompiledQuery::Pattern(pattern) if pattern.backend.is_literal() => 1,
+            CompiledQuery::Pattern(pattern) if pattern.max_typos == Some(0) => 2,
+            CompiledQuery::Pattern(_) => 3,
+            CompiledQuery::Not(query) => query.cost(),
+            CompiledQuery::And(children) | CompiledQuery::Or(children) => {
+                children.iter().map(Self::cost).sum()
+            }
+        }
+    }
+
+    /// Upper bound on the combined score of any haystack up to `max_haystack_len` bytes
+    pub(super) fn max_score(&self, max_haystack_len: usize) -> u16 {
+        match self {
+            CompiledQuery::Pattern(pattern) if pattern.negated => 0,
+            CompiledQuery::Pattern(pattern) => pattern.max_score_for(max_haystack_len),
+            CompiledQuery::Not(_) => 0,
+            CompiledQuery::And(children) => children.iter().fold(0, |sum, child| {
+                sum.saturating_add(child.max_score(max_haystack_len))
+            }),
+            CompiledQuery::Or(children) => children
+                .iter()
+                .map(|child| child.max_score(max_haystack_len))
+                .max()
+                .unwrap_or(0),
+        }
+    }
+
+    /// Converts the query into a list of patterns (see [`super::multi::CompiledPatterns`]),
+    /// when it's at most an AND of OR-groups of patterns. Otherwise, returns the query as is.
+    #[allow(clippy::result_large_err)]
+    pub(super) fn into_patterns(self) -> Result<Vec<CompiledPattern>, Self> {
+        fn is_group(query: &CompiledQuery) -> bool {
+            match query {
+                CompiledQuery::Pattern(_) => true,
+                CompiledQuery::Or(alternatives) => alternatives
+                    .iter()
+                    .all(|alternative| matches!(alternative, CompiledQuery::Pattern(_))),
+                _ => false,
+            }
+        }
+        let groups = match self {
+            CompiledQuery::And(children) if children.iter().all(is_group) => children,
+            query if is_group(&query) => vec![query],
+            query => return Err(query),
+        };
+
+        let mut patterns = vec![];
+        for group in groups {
+            match group {
+                CompiledQuery::Pattern(pattern) => patterns.push(pattern),
+                CompiledQuery::Or(alternatives) => {
+                    for (i, alternative) in alternatives.into_iter().enumerate() {
+                        let CompiledQuery::Pattern(mut pattern) = alternative else {
+                            unreachable!("OR-group alternatives are patterns");
+                        };
+                        pattern.or = i > 0;
+                        patterns.push(pattern);
+                    }
+                }
+                _ => unreachable!("groups are patterns or OR-groups"),
+            }
+        }
+        Ok(patterns)
+    }
+}
+
+/// Adds the score of a passing child to its parent's match
+fn combine(into: &mut Match, m: &Match) {
+    into.score = into.score.saturating_add(m.score);
+    into.exact |= m.exact;
+    #[cfg(feature = "match_end_col")]
+    {
+        into.end_col = into.end_col.max(m.end_col);
+    }
+}
+
+impl Matcher {
+    /// Narrows the `candidates`, in index order, to those matching the query, adding their
+    /// scores. Nodes are evaluated against the remaining candidates only, so an AND node stops
+    /// once no candidates remain.
+    ///
+    /// `match_pattern` matches a pattern against the given candidates, emitting a hit for each
+    /// matched candidate in order, where [`Match::index`] is the position of the candidate. It
+    /// receives the index of the pattern in the whole query (see
+    /// [`CompiledQuery::num_patterns`]), offset by `pattern_idx` for the query's first
+    /// pattern, and whether the hits' scores are kept, which they aren't for negated patterns
+    /// or under a NOT node. Without scores, an OR node also skips the candidates that passed an
+    /// earlier alternative, since the best of them no longer matters.
+    pub(super) fn match_query_candidates(
+        query: &mut CompiledQuery,
+        pattern_idx: usize,
+        candidates: &mut Vec<Match>,
+        scored: bool,
+        match_pattern: &mut impl FnMut(usize, &mut CompiledPattern, &[Match], bool, &mut Vec<Match>),
+    ) {
+        match query {
+            CompiledQuery::Pattern(pattern) => {
+                let mut hits = vec![];
+                let scored = scored && !pattern.negated;
+                match_pattern(pattern_idx, pattern, candidates, scored, &mut hits);
+                Self::apply_hits(pattern.negated, &mut hits, candidates);
+            }
+            CompiledQuery::Not(query) => {
+                let mut matched = candidates
+                    .iter()
+                    .map(|candidate| Match::from_index(candidate.index as usize))
+                    .collect();
+                Self::match_query_candidates(
+                    query,
+                    pattern_idx,
+                    &mut matched,
+                    false,
+                    match_pattern,
+                );
+
+                // Both are in index order
+                let mut matched = matched.iter().map(|m| m.index).peekable();
+                candidates.retain(|candidate| matched.next_if_eq(&candidate.index).is_none());
+            }
+            CompiledQuery::And(children) => {
+                let mut pattern_idx = pattern_idx;
+                for child in children {
+                    if candidates.is_empty() {
+                        break;
+                    }
+                    Self::match_query_candidates(
+                        child,
+                        pattern_idx,
+                        candidates,
+                        scored,
+                        match_pattern,
+                    );
+                    pattern_idx += child.num_patterns();
+                }
+            }
+            CompiledQuery::Or(children) => {
+                let mut best: Vec<Option<Match>> = vec![None; candidates.len()];
+                let mut pattern_idx = pattern_idx;
+                for child in children {
+                    let child_idx = pattern_idx;
+                    pattern_idx += child.num_patterns();
+                    let mut passed = candidates
+                        .iter()
+                        .zip(&best)
+                        .filter(|(_, best)| scored || best.is_none())
+                        .map(|(candidate, _)| Match::from_index(candidate.index as usize))
+                        .collect::<Vec<_>>();
+                    if passed.is_empty() {
+                        break;
+                    }
+                    Self::match_query_candidates(
+                        child,
+                        child_idx,
+                        &mut passed,
+                        scored,
+                        match_pattern,
+                    );
+
+                    // Both are in index order
+                    let mut passed = passed.into_iter().peekable();
+                    for (candidate, best) in candidates.iter().zip(best.iter_mut()) {
+                        let m = passed.next_if(|m| m.index == candidate.index);
+                        keep_best(best, m, |m| m.score);
+                    }
+                }
+
+                let mut best = best.into_iter();
+                candidates.retain_mut(|candidate| match best.next().flatten() {
+                    Some(m) => {
+                        combine(candidate, &m);
+                        true
+                    }
+                    None => false,
+                });
+            }
+        }
+    }
+
+    /// Matches the query against every haystack, narrowing the candidates node by node (see
+    /// [`Matcher::match_query_candidates`])
+    pub(super) fn match_list_query_into<S: AsRef<str>>(
+        query: &mut CompiledQuery,
+        haystacks: &[S],
+        haystack_index_offset: u32,
+        matches: &mut Vec<Match>,
+    ) {
+        let mut candidates = (0..haystacks.len())
+            .map(|i| Match::from_index(i + haystack_index_offset as usize))
+            .collect::<Vec<_>>();
+        let mut gathered: Vec<&str> = Vec::new();
+        Self::match_query_candidates(
+            query,
+            0,
+            &mut candidates,
+            true,
+            &mut |_, pattern, candidates, _, hits| {
+                gathered.clear();
+                gathered.extend(
+                    candidates
+                        .iter()
+                        .map(|m| haystacks[(m.index - haystack_index_offset) as usize].as_ref()),
+                );
+                Self::dispatch_pattern_into(pattern, &gathered, 0, hits);
+            },
+        );
+        matches.extend(candidates);
+    }
+
+    pub(super) fn match_one_query(
+        query: &mut CompiledQuery,
+        haystack: &str,
+        index: u32,
+    ) -> Option<Match> {
+        match query {
+            CompiledQuery::Pattern(pattern) => {
+                let result = Self::dispatch_pattern_one(pattern, haystack, index);
+                if pattern.negated {
+                    result.is_none().then(|| Match::from_index(index as usize))
+                } else {
+                    result
+                }
+            }
+            CompiledQuery::Not(query) => Self::match_one_query(query, haystack, index)
+                .is_none()
+                .then(|| Match::from_index(index as usize)),
+            CompiledQuery::And(children) => {
+                let mut combined = Match::from_index(index as usize);
+                for child in children {
+                    combine(
+                        &mut combined,
+                        &Self::match_one_query(child, haystack, index)?,
+                    );
+                }
+                Some(combined)
+            }
+            CompiledQuery::Or(children) => {
+                let mut best = None;
+                for child in children {
+                    let passed = Self::match_one_query(child, haystack, index);
+                    keep_best(&mut best, passed, |m| m.score);
+                }
+                best
+            }
+        }
+    }
+
+    pub(super) fn match_one_indices_query(
+        query: &mut CompiledQuery,
+        haystack: &str,
+        index: u32,
+    ) -> Option<MatchIndices> {
+        let mut m = Self::match_query_indices(query, haystack, index)?;
+        // Indices are reported in reverse order, and patterns may share matched chars.
+        m.indices.sort_unstable_by(|a, b| b.cmp(a));
+        m.indices.dedup();
+        Some(m)
+    }
+
+    fn match_query_indices(
+        query: &mut CompiledQuery,
+        haystack: &str,
+        index: u32,
+    ) -> Option<MatchIndices> {
+        match query {
+            CompiledQuery::Pattern(pattern) if pattern.negated => {
+                Self::dispatch_pattern_one(pattern, haystack, index)
+                    .is_none()
+                    .then(|| MatchIndices::from_index(index as usize))
+            }
+            CompiledQuery::Pattern(pattern) => {
+                Self::dispatch_pattern_one_indices(pattern, haystack, index)
+            }
+            CompiledQuery::Not(query) => Self::match_one_query(query, haystack, index)
+                .is_none()
+                .then(|| MatchIndices::from_index(index as usize)),
+            CompiledQuery::And(children) => {
+                let mut combined = MatchIndices::from_index(index as usize);
+                for child in children {
+                    let m = Self::match_query_indices(child, haystack, index)?;
+                    combined.score = combined.score.saturating_add(m.score);
+                    combined.exact |= m.exact;
+                    combined.indices.extend(m.indices);
+                }
+                Some(combined)
+            }
+            CompiledQuery::Or(children) => {
+                let mut best = None;
+                for child in children {
+                    let passed = Self::match_query_indices(child, haystack, index);
+                    keep_best(&mut best, passed, |m| m.score);
+                }
+                best
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Config, Matcher, Pattern, Query, SortStrategy};
+
+    const HAYSTACKS: [&str; 10] = [
+        "src/main.rs",
+        "src/lib.rs",
+        "src/test.rs",
+        "lib/test.rs",
+        "lib/bench.rs",
+        "docs/index.md",
+        "docs/src.md",
+        "tests/api.rs",
+        "Cargo.toml",
+        "src/bench/main.rs",
+    ];
+
+    fn indices(query: &str) -> Vec<u32> {
+        let config = Config::default().sort(SortStrategy::IndexAsc);
+        let mut matcher = Matcher::from_query_ast(&Query::parse(query), &config);
+        matcher
+            .match_list(&HAYSTACKS)
+            .iter()
+            .map(|m| m.index)
+            .collect()
+    }
+
+    #[test]
+    fn query_groups() {
+        assert_eq!(indices("(^src | ^lib) rs$"), vec![0, 1, 2, 3, 4, 9]);
+        assert_eq!(indices("(^src | ^lib) rs$ !(test | bench)"), vec![0, 1]);
+        assert_eq!(indices("^docs | (^src 'main)"), vec![0, 5, 6, 9]);
+        assert_eq!(indices("!(^src | ^lib | ^docs)"), vec![7, 8]);
+        assert_eq!(indices("!(^src !main)"), vec![0, 3, 4, 5, 6, 7, 8, 9]);
+        assert_eq!(indices("!(!(^src))"), vec![0, 1, 2, 9]);
+    }
+
+    #[test]
+    fn query_empty_groups_and_needles() {
+        assert_eq!(indices("").len(), HAYSTACKS.len());
+        assert_eq!(indices("()").len(), HAYSTACKS.len());
+        // An alternative with an empty needle matches every haystack
+        let everything = Query::Or(vec![
+            Query::Atom(Pattern::parse("^src")),
+            Query::Atom(Pattern::parse("^")),
+        ]);
+        let mut matcher = Matcher::from_query_ast(&everything, &Config::default());
+        assert_eq!(matcher.match_list(&HAYSTACKS).len(), HAYSTACKS.len());
+
+        // Negating a group that matches everything matches nothing
+        let nothing = Query::Not(Box::new(Query::And(vec![])));
+        let mut matcher = Matcher::from_query_ast(&nothing, &Config::default());
+        assert!(matcher.match_list(&HAYSTACKS).is_empty());
+    }
+
+    #[test]
+    fn query_without_parens_matches_patterns() {
+        for query in [
+            "src rs$",
+            "^src !test",
+            "^src | ^lib rs$ !bench",
+            "!^src",
+            "main",
+        ] {
+            for sort in [SortStrategy::ScoreThenIndexAsc, SortStrategy::IndexDesc] {
+                let config = Config::default().sort(sort);
+                let expected = Matcher::from_query(query, &config).match_list(&HAYSTACKS);
+                let mut matcher = Matcher::from_query_ast(&Query::parse(query), &config);
+                assert_eq!(matcher.match_list(&HAYSTACKS), expected, "query: {query:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn query_scores_combine_per_node() {
+        let config = Config::default().sort(SortStrategy::IndexAsc);
+        let score = |query: &str, haystack: &str| {
+            Matcher::from_query_ast(&Query::parse(query), &config)
+                .match_one(haystack, 0)
+                .map(|m| m.score)
+        };
+        let main = score("main", "src/main.rs").unwrap();
+        let src = score("src", "src/main.rs").unwrap();
+
+        // AND sums, OR takes the best alternative and NOT scores 0
+        assert_eq!(score("(main (src | zzz))", "src/main.rs"), Some(main + src));
+        assert_eq!(
+            score("(main | src) !(zzz yyy)", "src/main.rs"),
+            Some(main.max(src))
+        );
+        assert_eq!(score("!(zzz | yyy)", "src/main.rs"), Some(0));
+        assert_eq!(score("!(main | zzz)", "src/main.rs"), None);
+    }
+
+    #[test]
+    fn query_match_list_agrees_with_match_one() {
+        let haystacks = (0..5000)
+            .map(|i| HAYSTACKS[i % HAYSTACKS.len()].replace("rs", &format!("rs{}", i % 7)))
+            .collect::<Vec<_>>();
+        for query in [
+            "(^src | ^lib) rs !(test | bench)",
+            "(main | !^src) (rs1 | rs2 | md)",
+            "!(^src !main) | toml",
+            "!(lib (test | bench) | docs)",
+        ] {
+            for sort in [SortStrategy::ScoreThenIndexAsc, SortStrategy::IndexAsc] {
+                let config = Config::default().sort(sort);
+                let mut matcher = Matcher::from_query_ast(&Query::parse(query), &config);
+                let matches = matcher.match_list(&haystacks);
+                assert!(!matches.is_empty(), "query: {query:?}");
+
+                let mut from_iter = matcher.match_iter(&haystacks).collect::<Vec<_>>();
+                matcher.sort_matches(&mut from_iter);
+                assert_eq!(matches, from_iter, "query: {query:?}");
+
+                let parallel = matcher.match_list_parallel(&haystacks, 3);
+                assert_eq!(matches, parallel, "query: {query:?}");
+
+                let indices = matcher.match_list_indices(&haystacks);
+                assert_eq!(matches.len(), indices.len(), "query: {query:?}");
+                for (m, i) in matches.iter().zip(&indices) {
+                    assert_eq!((m.index, m.score), (i.index, i.score), "query: {query:?}");
+                    assert!(i.indices.windows(2).all(|w| w[0] > w[1]));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn query_applies_pattern_overrides() {
+        let query = Query::parse("(mian | tset) rs$");
+        let config = Config::default()
+            .sort(SortStrategy::IndexAsc)
+            .max_typos(Some(1));
+        let mut matcher = Matcher::from_query_ast(&query, &config);
+        assert!(!matcher.match_list(&HAYSTACKS).is_empty());
+
+        let query = query.map_patterns(|pattern| pattern.max_typos(Some(0)));
+        let mut matcher = Matcher::from_query_ast(&query, &config);
+        assert!(matcher.match_list(&HAYSTACKS).is_empty());
+    }
+}
@@ -0,0 +1,224 @@
+use super::Matcher;
+use crate::{Config, Match, Matching, Pattern};
+
+impl Matcher {
+    /// Whether every haystack matching `patterns` is guaranteed to match the current
+    /// patterns, such that [`Matcher::refine`] may rescore only the previous results.
+    ///
+    /// This holds when each current pattern is kept, in the same position, with chars
+    /// appended to its needle (fuzzy, prefix and substring matching only), and when any
+    /// additional patterns are appended after them, without adding alternatives to the last
+    /// OR-group. Every other change, including an updated [`crate::PatternConfig`], is
+    /// treated as a new query, as is any change from a matcher built from a [`crate::Query`].
+    ///
+    /// Never holds with a minimum score, in the config or any pattern (see
+    /// [`Config::min_score`]), as a haystack scoring below it for the current patterns may
+    /// score above it for the new ones.
+    pub fn is_refinement(&self, patterns: &[Pattern]) -> bool {
+        self.raw_query.is_none()
+            && self.config.min_score == 0
+            && self
+                .raw_patterns
+                .iter()
+                .chain(patterns)
+                .all(|pattern| pattern.config.min_score.is_none_or(|min| min == 0))
+            && patterns.len() >= self.raw_patterns.len()
+            && patterns
+                .get(self.raw_patterns.len())
+                .is_none_or(|appended| !appended.or || self.raw_patterns.is_empty())
+            && self
+                .raw_patterns
+                .iter()
+                .zip(patterns)
+                .all(|(previous, next)| refines(previous, next, &self.config))
+    }
+
+    /// Updates the patterns, as in [`Matcher::set_patterns`], and matches the haystacks
+    /// as in [`Matcher::match_list`], where `previous` holds the results of the last match
+    /// on the same haystacks.
+    ///
+    /// When the new patterns narrow the previous ones (see [`Matcher::is_refinement`]), only
+    /// the haystacks in `previous` are prefiltered and rescored. Otherwise, every haystack
+    /// is matched. Either way, the results are the same as calling [`Matcher::match_list`].
+    ///
+    /// ```
+    /// use frizbee::{Config, Matcher, Pattern};
+    ///
+    /// let haystacks = ["foo", "foobar", "fob", "bar"];
+    /// let mut matcher = Matcher::from_query("fo", &Config::default());
+    /// let matches = matcher.match_list(&haystacks);
+    /// assert_eq!(matches.len(), 3);
+    ///
+    /// // Typing another char only rescans the previous 3 results
+    /// let matches = matcher.refine(&Pattern::parse_query("foo"), &haystacks, &matches);
+    /// assert_eq!(matches.len(), 2);
+    /// ```
+    pub fn refine<S: AsRef<str>>(
+        &mut self,
+        patterns: &[Pattern],
+        haystacks: &[S],
+        previous: &[Match],
+    ) -> Vec<Match> {
+        let narrowed = self.is_refinement(patterns);
+        self.set_patterns(patterns);
+        if !narrowed {
+            return self.match_list(haystacks);
+        }
+
+        // Gather the previous results in index order, keeping the output ordered like a full scan
+        let mut candidates = previous.iter().map(|m| m.index).collect::<Vec<_>>();
+        candidates.sort_unstable();
+        let gathered = candidates
+            .iter()
+            .map(|&index| haystacks[index as usize].as_ref())
+            .collect::<Vec<_>>();
+
+        let mut matches = vec![];
+        self.match_list_into(&gathered, 0, &mut matches);
+        for m in matches.iter_mut() {
+            m.index = candidates[m.index as usize];
+        }
+        self.sort_matches(&mut matches);
+        matches
+    }
+}
+
+/// Whether every haystack matching `next` also matches `previous`
+fn refines(previous: &Pattern, next: &Pattern, config: &Config) -> bool {
+    if previous == next {
+        return true;
+    }
+    // Appending to a negated needle excludes fewer haystacks
+    if previous.negated
+        || next.negated
+        || previous.or != next.or
+        || previous.field != next.field
+        || previous.config != next.config
+    {
+        return false;
+    }
+    // Suffix and exact matches are anchored to the end, so appended chars may match
+    // haystacks the previous needle didn't
+    let matching = previous.config.matching.unwrap_or(config.matching);
+    matches!(
+        matching,
+        Matching::Fuzzy | Matching::Prefix | Matching::Substring
+    ) && next.needle.starts_with(&previous.needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Config, Field, Matcher, Pattern, SortStrategy};
+
+    const HAYSTACKS: [&str; 8] = [
+        "foo", "foobar", "fob", "bar/foo", "barfoo", "FooBaz", "f_o_o_b", "qux",
+    ];
+
+    fn assert_refine_matches_full_scan(from: &str, to: &str, config: &Config) {
+        let mut matcher = Matcher::from_query(from, config);
+        let previous = matcher.match_list(&HAYSTACKS);
+        let refined = matcher.refine(&Pattern::parse_query(to), &HAYSTACKS, &previous);
+        let full = Matcher::from_query(to, config).match_list(&HAYSTACKS);
+        assert_eq!(refined, full, "from: {from:?}, to: {to:?}");
+    }
+
+    #[test]
+    fn refine_matches_full_scan() {
+        for sort in [
+            SortStrategy::ScoreThenIndexAsc,
+            SortStrategy::ScoreThenIndexDesc,
+            SortStrategy::IndexAsc,
+            SortStrategy::IndexDesc,
+        ] {
+            for max_typos in [None, Some(0), Some(1)] {
+                let config = Config::default().sort(sort).max_typos(max_typos);
+                for (from, to) in [
+                    ("fo", "foo"),
+                    ("foo", "foob"),
+                    ("foo", "foo bar"),
+                    ("foo", "foo !^bar"),
+                    ("^fo", "^foo"),
+                    ("'oo", "'oob"),
+                    ("", "foo"),
+                    ("foo | bar", "foo | barf"),
+                    ("foo | bar", "foo | bar baz"),
+                    // Not refinements, so these fall back to a full scan
+                    ("oo$", "oob$"),
+                    ("!bar", "!barf"),
+                    ("foo", "bar"),
+                    ("foo bar", "foo"),
+                    ("foo", "foo | bar"),
+                    ("foo | bar", "foo bar"),
+                ] {
+                    assert_refine_matches_full_scan(from, to, &config);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn refine_under_min_score_matches_full_scan() {
+        let config = Config::default().min_score(40);
+        assert_refine_matches_full_scan("f", "foo", &config);
+        let matcher = Matcher::from_query("f", &config);
+        assert!(!matcher.is_refinement(&Pattern::parse_query("foo")));
+
+        // Per-pattern minimum scores also rescan every haystack
+        let with_min_score = |needle: &str| [Pattern::from(needle).min_score(Some(40))];
+        let mut matcher = Matcher::from_patterns(&with_min_score("f"), &Config::default());
+        let previous = matcher.match_list(&HAYSTACKS);
+        assert!(!matcher.is_refinement(&with_min_score("foo")));
+        let refined = matcher.refine(&with_min_score("foo"), &HAYSTACKS, &previous);
+        let full = Matcher::from_patterns(&with_min_score("foo"), &Config::default())
+            .match_list(&HAYSTACKS);
+        assert!(!full.is_empty());
+        assert_eq!(refined, full);
+    }
+
+    #[test]
+    fn is_refinement() {
+        let matcher = Matcher::from_query("fo !bar ^qu", &Config::default());
+        assert!(matcher.is_refinement(&Pattern::parse_query("fo !bar ^qu")));
+        assert!(matcher.is_refinement(&Pattern::parse_query("foo !bar ^qux")));
+        assert!(matcher.is_refinement(&Pattern::parse_query("fo !bar ^qu baz")));
+        assert!(matcher.is_refinement(&Pattern::parse_query("fo !bar ^qu !baz")));
+
+        assert!(!matcher.is_refinement(&Pattern::parse_query("fo !bar")));
+        assert!(!matcher.is_refinement(&Pattern::parse_query("fo !barz ^qu")));
+        assert!(!matcher.is_refinement(&Pattern::parse_query("f !bar ^qu")));
+        assert!(!matcher.is_refinement(&Pattern::parse_query("fo !bar qu")));
+        assert!(!matcher.is_refinement(&Pattern::parse_query("!bar fo ^qu")));
+
+        // Widening the query with an alternative is not a refinement, but narrowing one is
+        assert!(!matcher.is_refinement(&Pattern::parse_query("fo !bar ^qu | baz")));
+        let matcher = Matcher::from_query("fo | bar", &Config::default());
+        assert!(matcher.is_refinement(&Pattern::parse_query("foo | bar")));
+        assert!(!matcher.is_refinement(&Pattern::parse_query("fo bar")));
+
+        // Scoping to another field matches different haystacks
+        let fields = [Field::new("name"), Field::new("path")];
+        let mut matcher = Matcher::from_query("name:fo", &Config::default());
+        matcher.set_fields(&fields).unwrap();
+        let parse = |query| Pattern::parse_query_with_fields(query, &fields);
+        assert!(matcher.is_refinement(&parse("name:foo")));
+        assert!(!matcher.is_refinement(&parse("path:foo")));
+        assert!(!matcher.is_refinement(&parse("foo")));
+    }
+
+    #[test]
+    fn refine_only_rescans_previous_results() {
+        let config = Config::default().sort(SortStrategy::IndexAsc);
+        let mut matcher = Matcher::from_query("fo", &config);
+
+        // Dropping "foobar" from the previous results excludes it, even though it matches,
+        // since refining trusts that the previous results came from the same haystacks
+        let previous = matcher
+            .match_list(&HAYSTACKS)
+            .into_iter()
+            .filter(|m| m.index != 1)
+            .collect::<Vec<_>>();
+        let refined = matcher.refine(&Pattern::parse_query("foo"), &HAYSTACKS, &previous);
+        assert!(refined.iter().all(|m| m.index != 1));
+        assert!(refined.iter().any(|m| m.index == 0));
+    }
+}
@@ -0,0 +1,352 @@
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicBool, AtomicU16, AtomicUsize, Ordering};
+use std::thread;
+
+use super::CHUNK_SIZE;
+use super::Matcher;
+use crate::k_merge::{
+    ByIndexAsc, ByIndexDesc, ByScoreThenIndexAsc, ByScoreThenIndexDesc, MergeOrder,
+    k_merge_matches_by,
+};
+use crate::{Match, SortStrategy};
+
+/// Expands to a call of `$method::<O, _>` with the [`MergeOrder`] matching the sort strategy
+macro_rules! dispatch_order {
+    ($sort:expr, $self:ident.$method:ident($($arg:expr),*)) => {
+        match $sort {
+            SortStrategy::ScoreThenIndexAsc => $self.$method::<ByScoreThenIndexAsc, _>($($arg),*),
+            SortStrategy::ScoreThenIndexDesc => {
+                $self.$method::<ByScoreThenIndexDesc, _>($($arg),*)
+            }
+            SortStrategy::IndexAsc => $self.$method::<ByIndexAsc, _>($($arg),*),
+            SortStrategy::IndexDesc => $self.$method::<ByIndexDesc, _>($($arg),*),
+        }
+    };
+}
+
+impl Matcher {
+    /// Matches a list of haystacks, returning only the first `k` [`Match`] values that
+    /// [`Matcher::match_list`] would return, in the same order.
+    ///
+    /// Rather than sorting every match, the best `k` are kept in a bounded heap. Haystacks are
+    /// visited in 2048 item chunks, in the index order of [`crate::Config::sort`]. Sorting by
+    /// index stops after the first `k` matches. Sorting by score only stops early once the
+    /// kept matches all reach the max score, but each chunk raises the min score to that of
+    /// the `k`th best match so far, so haystacks too short to reach it are skipped before
+    /// scoring and lower scoring matches are dropped by the backends.
+    ///
+    /// ```
+    /// use frizbee::{Config, Matcher};
+    ///
+    /// let haystacks = ["fooBar", "foo_bar", "barfoo", "fbr", "prelude"];
+    /// let mut matcher = Matcher::new("fbr", &Config::default());
+    /// let matches = matcher.match_list_top_k(&haystacks, 2);
+    /// assert_eq!(matches, matcher.match_list(&haystacks)[..2]);
+    /// ```
+    pub fn match_list_top_k<S: AsRef<str>>(&mut self, haystacks: &[S], k: usize) -> Vec<Match> {
+        Self::guard_against_haystack_overflow(haystacks.len(), 0);
+        let shared = TopKShared::default();
+        dispatch_order!(self.config.sort, self.top_k_worker(haystacks, k, &shared))
+    }
+
+    /// Parallel version of [`Matcher::match_list_top_k`] on multiple real threads, with the
+    /// same chunking as [`Matcher::match_list_parallel`]. The `threads` must be >0.
+    ///
+    /// Each thread keeps its own bounded heap of `k` matches, and publishes the score of its
+    /// `k`th best match as a floor shared across threads. A match scoring below the floor can't
+    /// enter the top `k`, so every thread matches its chunks with the floor as the min score.
+    /// The heaps are combined with [`crate::k_merge`].
+    pub fn match_list_parallel_top_k<S: AsRef<str> + Sync>(
+        &mut self,
+        haystacks: &[S],
+        k: usize,
+        threads: usize,
+    ) -> Vec<Match> {
+        let threads = Self::parallel_threads(haystacks.len(), threads);
+        if threads == 1 {
+            return self.match_list_top_k(haystacks, k);
+        }
+
+        dispatch_order!(
+            self.config.sort,
+            self.parallel_top_k_by(haystacks, k, threads)
+        )
+    }
+
+    fn parallel_top_k_by<O: MergeOrder, S: AsRef<str> + Sync>(
+        &self,
+        haystacks: &[S],
+        k: usize,
+        threads: usize,
+    ) -> Vec<Match> {
+        let shared = TopKShared::default();
+        thread::scope(|s| {
+            let handles: Vec<_> = (0..threads)
+                .map(|_| s.spawn(|| self.clone().top_k_worker::<O, S>(haystacks, k, &shared)))
+                .collect();
+
+            let runs = handles.into_iter().map(|h| h.join().unwrap()).collect();
+            let mut matches = k_merge_matches_by::<O>(runs);
+            matches.truncate(k);
+            matches
+        })
+    }
+
+    /// Claims chunks until none remain or no remaining haystack could enter the top `k`,
+    /// returning this worker's best `k` matches sorted by `O`
+    fn top_k_worker<O: MergeOrder, S: AsRef<str>>(
+        &mut self,
+        haystacks: &[S],
+        k: usize,
+        shared: &TopKShared,
+    ) -> Vec<Match> {
+        if k == 0 {
+            return vec![];
+        }
+        let mut top_k = TopK::<O>::new(k);
+
+        let num_chunks = haystacks.len().div_ceil(CHUNK_SIZE);
+        let mut max_score = self.patterns.max_score(usize::MAX);
+        // Regexes whose matches can be of any length are only bounded by the longest haystack
+        if max_score == u16::MAX {
+            let max_haystack_len = haystacks.iter().map(|h| h.as_ref().len()).max();
+            max_score = self.patterns.max_score(max_haystack_len.unwrap_or(0));
+        }
+        let mut chunk_matches = vec![];
+        let mut floor = 0;
+        while !shared.saturated.load(Ordering::Relaxed) {
+            let chunk_idx = shared.next_chunk.fetch_add(1, Ordering::Relaxed);
+            if chunk_idx >= num_chunks {
+                break;
+            }
+            // Visit chunks in the sort's index order, so that a later chunk loses every tie
+            // against the matches kept from earlier chunks
+            let chunk_idx = if self.config.sort.is_reversed() {
+                num_chunks - 1 - chunk_idx
+            } else {
+                chunk_idx
+            };
+
+            // Matching with the floor as the min score skips the haystacks that can't reach it,
+            // like `Config::min_score`
+            let shared_floor = shared.floor.load(Ordering::Relaxed);
+            if shared_floor > floor {
+                floor = shared_floor;
+                self.patterns.set_score_floor(floor, self.config.min_score);
+            }
+
+            let start = chunk_idx * CHUNK_SIZE;
+            let end = (start + CHUNK_SIZE).min(haystacks.len());
+            chunk_matches.clear();
+            self.match_list_into(&haystacks[start..end], start as u32, &mut chunk_matches);
+            // Query trees keep their own min scores
+            for &m in chunk_matches.iter().filter(|m| m.score >= floor) {
+                top_k.push(m);
+            }
+
+            // Chunks claimed from here on only hold matches that lose ties against the kept
+            // ones, so they can't enter unless they score higher than the worst, which is only
+            // impossible once the worst reaches the max score
+            if let Some(worst) = top_k.worst_when_full() {
+                if !self.config.sort.is_by_score() || worst.score >= max_score {
+                    shared.saturated.store(true, Ordering::Relaxed);
+                } else {
+                    shared.floor.fetch_max(worst.score, Ordering::Relaxed);
+                }
+            }
+        }
+
+        if floor > 0 {
+            self.patterns.set_score_floor(0, self.config.min_score);
+        }
+        top_k.into_sorted_vec()
+    }
+}
+
+/// State shared across the workers of a top `k` match
+#[derive(Default)]
+struct TopKShared {
+    next_chunk: AtomicUsize,
+    /// Highest score of any worker's `k`th best match, when sorting by score. At least `k`
+    /// matches score this high, so lower scoring matches can't enter the top `k`
+    floor: AtomicU16,
+    /// Set once no unclaimed chunk can contain a match that enters the top `k`
+    saturated: AtomicBool,
+}
+
+/// Bounded binary heap keeping the best `k` matches according to `O`, where the root is the
+/// worst kept match
+struct TopK<O: MergeOrder> {
+    k: usize,
+    heap: Vec<Match>,
+    order: PhantomData<O>,
+}
+
+impl<O: MergeOrder> TopK<O> {
+    fn new(k: usize) -> Self {
+        Self {
+            k,
+            heap: Vec::with_capacity(k.min(CHUNK_SIZE)),
+            order: PhantomData,
+        }
+    }
+
+    /// The worst kept match, once `k` matches are kept
+    fn worst_when_full(&self) -> Option<&Match> {
+        (self.heap.len() == self.k).then(|| self.heap.first())?
+    }
+
+    fn push(&mut self, m: Match) {
+        if self.heap.len() < self.k {
+            self.heap.push(m);
+            self.sift_up(self.heap.len() - 1);
+        } else if self.heap.first().is_some_and(|worst| O::less(&m, worst)) {
+            self.heap[0] = m;
+            self.sift_down(0);
+        }
+    }
+
+    fn sift_up(&mut self, mut pos: usize) {
+        while pos > 0 {
+            let parent = (pos - 1) / 2;
+            if !O::less(&self.heap[parent], &self.heap[pos]) {
+                return;
+            }
+            self.heap.swap(parent, pos);
+            pos = parent;
+        }
+    }
+
+    fn sift_down(&mut self, mut pos: usize) {
+        loop {
+            let mut worst = 2 * pos + 1;
+            if worst >= self.heap.len() {
+                return;
+            }
+            if worst + 1 < self.heap.len() && O::less(&self.heap[worst], &self.heap[worst + 1]) {
+                worst += 1;
+            }
+            if !O::less(&self.heap[pos], &self.heap[worst]) {
+                return;
+            }
+            self.heap.swap(pos, worst);
+            pos = worst;
+        }
+    }
+
+    fn into_sorted_vec(mut self) -> Vec<Match> {
+        self.heap.sort_unstable_by(|a, b| {
+            if O::less(a, b) {
+                std::cmp::Ordering::Less
+            } else if O::less(b, a) {
+                std::cmp::Ordering::Greater
+            } else {
+                std::cmp::Ordering::Equal
+            }
+        });
+        self.heap
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Config, Matcher, SortStrategy};
+
+    const SORTS: [SortStrategy; 4] = [
+        SortStrategy::ScoreThenIndexAsc,
+        SortStrategy::ScoreThenIndexDesc,
+        SortStrategy::IndexAsc,
+        SortStrategy::IndexDesc,
+    ];
+
+    fn haystacks() -> Vec<String> {
+        // Repeating values across chunk boundaries produces many tied scores
+        let values = ["top", "stop", "t_o_p", "tip", "laptop", "TOP", "bottom"];
+        (0..5000)
+            .map(|index| format!("{}-{}", values[index % values.len()], index % 3))
+            .collect()
+    }
+
+    #[test]
+    fn top_k_matches_truncated_match_list() {
+        let haystacks = haystacks();
+        for query in ["top", "top !stop", ""] {
+            for sort in SORTS {
+                let config = Config::default().sort(sort);
+                let mut matcher = Matcher::from_query(query, &config);
+                let all = matcher.match_list(&haystacks);
+
+                for k in [0, 1, 7, 100, 2048, 2049, 10_000] {
+                    let expected = &all[..k.min(all.len())];
+                    let top_k = matcher.match_list_top_k(&haystacks, k);
+                    assert_eq!(top_k, expected, "query={query:?}, sort={sort:?}, k={k}");
+                    for threads in [2, 3, 8] {
+                        let parallel = matcher.match_list_parallel_top_k(&haystacks, k, threads);
+                        assert_eq!(
+                            parallel, expected,
+                            "query={query:?}, sort={sort:?}, k={k}, threads={threads}"
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn score_floor_only_drops_matches_below_it() {
+        let haystacks = [
+            "foo",
+            "xfoo_baz",
+            "foo bar baz",
+            "bar/baz",
+            "f_o_o baz",
+            "fooqux baz",
+            "Foo-Baz",
+            "barbaz",
+        ];
+        for query in [
+            "foo",
+            "foo | bar baz",
+            "foo !qux",
+            "foo | !bar baz",
+            "^foo 'baz",
+        ] {
+            let mut matcher = Matcher::from_query(query, &Config::default());
+            let all = matcher.match_list(&haystacks);
+            for floor in all.iter().map(|m| m.score) {
+                matcher.patterns.set_score_floor(floor, 0);
+                // Each pattern's share of the floor is a lower bound, so some matches below the
+                // floor may remain, but none above it are dropped
+                let matches = matcher.match_list(&haystacks);
+                assert!(matches.iter().all(|m| all.contains(m)), "query={query:?}");
+                assert_eq!(
+                    matches
+                        .iter()
+                        .filter(|m| m.score >= floor)
+                        .collect::<Vec<_>>(),
+                    all.iter().filter(|m| m.score >= floor).collect::<Vec<_>>(),
+                    "query={query:?}, floor={floor}"
+                );
+                if query == "foo" {
+                    assert!(matches.iter().all(|m| m.score >= floor));
+                }
+            }
+            matcher.patterns.set_score_floor(0, 0);
+            assert_eq!(matcher.match_list(&haystacks), all, "query={query:?}");
+        }
+    }
+
+    #[test]
+    fn top_k_fewer_matches_than_k() {
+        let haystacks = ["foo", "bar", "xfoo"];
+        let mut matcher = Matcher::new("foo", &Config::default());
+        let matches = matcher.match_list_top_k(&haystacks, 10);
+        assert_eq!(matches, matcher.match_list(&haystacks));
+    }
+
+    #[test]
+    #[should_panic(expected = "threads must be positive")]
+    fn zero_threads_panics() {
+        let _ = Matcher::new("a", &Config::default()).match_list_parallel_top_k(&["a"], 1, 0);
+    }
+}
@@ -0,0 +1,411 @@
+//! Needle and haystack normalization before matching: diacritic stripping for
+//! [`Normalization::Diacritics`] and full case folding for [`CaseMatching::Ignore`] or a
+//! [`CaseLocale`] other than Unicode. The needle is normalized once when compiling the pattern,
+//! while each non-ASCII haystack is normalized into a copy that remembers the original byte
+//! offset of each of its bytes, so indices are reported against the original haystack
+
+use std::borrow::Cow;
+
+use crate::{CaseLocale, CaseMatching, Config, Normalization};
+
+mod table;
+
+use table::{CASE_FOLDS, DIACRITICS};
+
+/// Haystack after normalization
+#[derive(Debug, Clone)]
+pub(crate) struct NormalizedHaystack {
+    bytes: Vec<u8>,
+    /// Byte offset in the original haystack of each normalized byte
+    offsets: Vec<u32>,
+}
+
+impl NormalizedHaystack {
+    #[inline(always)]
+    pub(crate) fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    #[inline(always)]
+    pub(crate) fn as_str(&self) -> &str {
+        std::str::from_utf8(&self.bytes).expect("normalized haystacks are UTF-8")
+    }
+
+    /// Byte offset in the original haystack of the normalized byte at `index`
+    #[inline(always)]
+    pub(crate) fn original_index(&self, index: u32) -> u32 {
+        self.offsets[index as usize]
+    }
+
+    /// Maps the indices of the normalized haystack, in reverse order, to every byte of the
+    /// `original` haystack's chars they belong to, like the indices of an unnormalized match.
+    /// A char expanding into several chars (e.g. "ß" into "ss") maps all of them to the
+    /// original char, so its bytes are only reported once
+    pub(crate) fn original_indices(&self, original: &[u8], indices: &mut Vec<u32>) {
+        let is_char_start = |idx: usize| original[idx] & 0xC0 != 0x80;
+        for index in std::mem::take(indices) {
+            let offset = self.original_index(index) as usize;
+            let start = (0..=offset)
+                .rev()
+                .find(|&idx| is_char_start(idx))
+                .unwrap_or(0);
+            if indices.last() == Some(&(start as u32)) {
+                continue;
+            }
+            let end = (offset + 1..original.len())
+                .find(|&idx| is_char_start(idx))
+                .unwrap_or(original.len());
+            indices.extend((start..end).rev().map(|idx| idx as u32));
+        }
+    }
+}
+
+/// Normalizations applied to the needle and haystacks of a pattern
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Normalizer {
+    strip_diacritics: bool,
+    /// Replaces the chars that can't be matched case-insensitively by flipping their case
+    /// (e.g. "ß", "ς" or the Kelvin sign) with their full case folding
+    fold_case: bool,
+    /// Folds "I" into "ı" and "İ" into "i", so the dotted and dotless "i" never match each other
+    fold_turkic_i: bool,
+}
+
+impl Normalizer {
+    pub(crate) fn new(
+        normalization: Normalization,
+        case_locale: CaseLocale,
+        case_sensitive: bool,
+    ) -> Self {
+        Self {
+            strip_diacritics: normalization == Normalization::Diacritics,
+            fold_case: !case_sensitive,
+            fold_turkic_i: !case_sensitive && case_locale == CaseLocale::Turkic,
+        }
+    }
+
+    /// Normalizations asked for by the config when matching `needle`. Full case folding copies
+    /// the non-ASCII haystacks, so it's only applied when ignoring case with
+    /// [`CaseMatching::Ignore`] or a [`CaseLocale`] other than Unicode, while smart case keeps
+    /// flipping the case of each char like the matchers do
+    pub(crate) fn for_config(config: &Config, needle: &str) -> Self {
+        let full_case_folding =
+            config.casing == CaseMatching::Ignore || config.case_locale != CaseLocale::Unicode;
+        let case_sensitive = config.casing.respects_case_for(needle) || !full_case_folding;
+        Self::new(config.normalization, config.case_locale, case_sensitive)
+    }
+
+    /// Normalizes the needle, borrowing it when there's nothing to normalize
+    pub(crate) fn normalize_needle(self, needle: &str) -> Cow<'_, str> {
+        if !self.changes(needle) {
+            return Cow::Borrowed(needle);
+        }
+        let mut normalized = String::with_capacity(needle.len());
+        for c in needle.chars() {
+            self.normalize_char(c, |c| normalized.push(c));
+        }
+        Cow::Owned(normalized)
+    }
+
+    /// Normalizes the haystack, returning `None` when there's nothing to normalize, so the
+    /// original haystack can be matched directly
+    #[inline(always)]
+    pub(crate) fn normalize_haystack(self, haystack: &[u8]) -> Option<NormalizedHaystack> {
+        if !(self.strip_diacritics || self.fold_case) || self.skips_ascii(haystack) {
+            return None;
+        }
+        self.normalize_non_ascii_haystack(haystack)
+    }
+
+    /// Slow path of [`Self::normalize_haystack`], kept out of line so that matching ASCII
+    /// haystacks only pays for the ASCII check
+    #[inline(never)]
+    fn normalize_non_ascii_haystack(self, haystack: &[u8]) -> Option<NormalizedHaystack> {
+        // Haystacks always come from a `&str`
+        let haystack = std::str::from_utf8(haystack).ok()?;
+        let start = haystack
+            .char_indices()
+            .find(|&(_, c)| self.changes_char(c))?
+            .0;
+
+        let mut bytes = Vec::with_capacity(haystack.len());
+        let mut offsets = Vec::with_capacity(haystack.len());
+        bytes.extend_from_slice(&haystack.as_bytes()[..start]);
+        offsets.extend(0..start as u32);
+        for (idx, original) in haystack[start..].char_indices() {
+            let offset = (start + idx) as u32;
+            let original_len = original.len_utf8() as u32;
+            let mut first = true;
+            self.normalize_char(original, |c| {
+                bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+                // The bytes of the first normalized char point to the same bytes of the
+                // original char, as long as it has them. The chars it expands into point to
+                // the start of the original char
+                offsets.extend((0..c.len_utf8() as u32).map(|byte| match first {
+                    true if byte < original_len => offset + byte,
+                    _ => offset,
+                }));
+                first = false;
+            });
+        }
+        Some(NormalizedHaystack { bytes, offsets })
+    }
+
+    #[inline(always)]
+    fn changes(self, s: &str) -> bool {
+        !self.skips_ascii(s.as_bytes()) && s.chars().any(|c| self.changes_char(c))
+    }
+
+    /// Whether the haystack is ASCII, and so has nothing to normalize. Turkic case folding
+    /// normalizes the ASCII "I" too
+    #[inline(always)]
+    fn skips_ascii(self, haystack: &[u8]) -> bool {
+        haystack.is_ascii() && !(self.fold_turkic_i && haystack.contains(&b'I'))
+    }
+
+    #[inline(always)]
+    fn changes_char(self, c: char) -> bool {
+        let mut changed = false;
+        let mut chars = 0;
+        self.normalize_char(c, |normalized| {
+            changed |= normalized != c;
+            chars += 1;
+        });
+        changed || chars != 1
+    }
+
+    /// Emits the normalized chars of `c`. Case folding comes first, so the diacritics of the
+    /// folded chars are stripped too (e.g. "ΐ" folds into "ΐ", which strips into "ι")
+    #[inline(always)]
+    fn normalize_char(self, c: char, mut emit: impl FnMut(char)) {
+        // Neither table has ASCII chars, so only the Turkic "I" changes
+        if c.is_ascii() && !(self.fold_turkic_i && c == 'I') {
+            return emit(c);
+        }
+        let folded = match (self.fold_case, c) {
+            (true, 'I') if self.fold_turkic_i => Some("ı"),
+            (true, 'İ') if self.fold_turkic_i => Some("i"),
+            (true, c) => fold_case(c),
+            (false, _) => None,
+        };
+        let mut emit_stripped = |c: char| match self.strip_diacritics {
+            true => {
+                if let Some(c) = strip_diacritics(c) {
+                    emit(c)
+                }
+            }
+            false => emit(c),
+        };
+        match folded {
+            Some(folded) => folded.chars().for_each(emit_stripped),
+            None => emit_stripped(c),
+        }
+    }
+}
+
+/// Full case folding of the chars that can't be matched case-insensitively by flipping their
+/// case
+#[inline(always)]
+fn fold_case(c: char) -> Option<&'static str> {
+    CASE_FOLDS
+        .binary_search_by_key(&c, |&(c, _)| c)
+        .ok()
+        .map(|idx| CASE_FOLDS[idx].1)
+}
+
+/// Base letter of a precomposed Latin, Greek or Cyrillic letter, or `None` for combining
+/// diacritical marks, which are dropped. Other chars are returned as is
+#[inline(always)]
+fn strip_diacritics(c: char) -> Option<char> {
+    if ('\u{0300}'..='\u{036F}').contains(&c) {
+        return None;
+    }
+    match DIACRITICS.binary_search_by_key(&c, |&(precomposed, _)| precomposed) {
+        Ok(idx) => Some(DIACRITICS[idx].1),
+        Err(_) => Some(c),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIACRITICS_NORMALIZER: Normalizer = Normalizer {
+        strip_diacritics: true,
+        fold_case: false,
+        fold_turkic_i: false,
+    };
+    const CASE_NORMALIZER: Normalizer = Normalizer {
+        strip_diacritics: false,
+        fold_case: true,
+        fold_turkic_i: false,
+    };
+    const TURKIC_NORMALIZER: Normalizer = Normalizer {
+        strip_diacritics: false,
+        fold_case: true,
+        fold_turkic_i: true,
+    };
+
+    fn normalize(normalizer: Normalizer, haystack: &str) -> Option<(String, Vec<u32>)> {
+        normalizer
+            .normalize_haystack(haystack.as_bytes())
+            .map(|normalized| {
+                let offsets = (0..normalized.as_bytes().len() as u32)
+                    .map(|idx| normalized.original_index(idx))
+                    .collect();
+                (String::from_utf8(normalized.bytes).unwrap(), offsets)
+            })
+    }
+
+    #[test]
+    fn tables_are_sorted() {
+        assert!(DIACRITICS.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        assert!(CASE_FOLDS.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        // ASCII chars skip the tables
+        assert!(DIACRITICS.iter().all(|(c, _)| !c.is_ascii()));
+        assert!(CASE_FOLDS.iter().all(|(c, _)| !c.is_ascii()));
+    }
+
+    #[test]
+    fn skips_ascii_haystacks() {
+        assert!(CASE_NORMALIZER.normalize_haystack(b"ID.md").is_none());
+        assert!(DIACRITICS_NORMALIZER.normalize_haystack(b"ID.md").is_none());
+        assert!(
+            CASE_NORMALIZER
+                .normalize_haystack("\u{212A}ß.md".as_bytes())
+                .is_some()
+        );
+        // Turkic case folding normalizes the ASCII "I" too
+        assert!(TURKIC_NORMALIZER.normalize_haystack(b"ID.md").is_some());
+        assert!(TURKIC_NORMALIZER.normalize_haystack(b"id.md").is_none());
+        // Nothing is normalized unless the config asks for it
+        let normalizer = Normalizer::new(Normalization::Never, CaseLocale::Unicode, true);
+        assert!(
+            normalizer
+                .normalize_haystack("\u{212A}ß.md".as_bytes())
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn smart_case_skips_full_case_folding() {
+        let normalize = |config: &Config| {
+            Normalizer::for_config(config, "strasse").normalize_haystack("Straße".as_bytes())
+        };
+        assert!(normalize(&Config::default()).is_none());
+        assert!(normalize(&Config::default().casing(CaseMatching::Ignore)).is_some());
+        assert!(normalize(&Config::default().case_locale(CaseLocale::Turkic)).is_some());
+        assert!(normalize(&Config::default().normalization(Normalization::Diacritics)).is_none());
+    }
+
+    #[test]
+    fn original_indices_cover_every_byte_of_original_chars() {
+        // "é" is two bytes, stripped into the one byte "e"
+        let normalized = DIACRITICS_NORMALIZER
+            .normalize_haystack("café.md".as_bytes())
+            .unwrap();
+        let mut indices = vec![5, 3, 2, 1, 0];
+        normalized.original_indices("café.md".as_bytes(), &mut indices);
+        assert_eq!(indices, vec![6, 4, 3, 2, 1, 0]);
+
+        // The Kelvin sign is three bytes folded into "k", and "ß" expands into "ss"
+        let haystack = "a\u{212A}ßb";
+        let normalized = CASE_NORMALIZER
+            .normalize_haystack(haystack.as_bytes())
+            .unwrap();
+        assert_eq!(normalized.as_bytes(), b"akssb");
+        let mut indices = vec![4, 3, 2, 1];
+        normalized.original_indices(haystack.as_bytes(), &mut indices);
+        assert_eq!(indices, vec![6, 5, 4, 3, 2, 1]);
+    }
+
+    #[test]
+    fn strips_latin_greek_and_cyrillic_diacritics() {
+        let needle = DIACRITICS_NORMALIZER.normalize_needle("Crème Brûlée ἄλφα йод");
+        assert_eq!(needle, "Creme Brulee αλφα иод");
+        assert_eq!(
+            Normalizer::new(Normalization::Never, CaseLocale::Unicode, true)
+                .normalize_needle("Crème"),
+            Cow::Borrowed("Crème")
+        );
+    }
+
+    #[test]
+    fn folds_case_of_unpaired_chars() {
+        assert_eq!(CASE_NORMALIZER.normalize_needle("Straße"), "Strasse");
+        assert_eq!(CASE_NORMALIZER.normalize_needle("ὀδυσσεύς"), "ὀδυσσεύσ");
+        assert_eq!(CASE_NORMALIZER.normalize_needle("\u{212A}elvin"), "kelvin");
+        assert_eq!(CASE_NORMALIZER.normalize_needle("İ"), "i\u{307}");
+        // Folding comes before stripping diacritics
+        let normalizer = Normalizer::new(Normalization::Diacritics, CaseLocale::Unicode, false);
+        assert_eq!(normalizer.normalize_needle("İΐ"), "iι");
+        // Chars paired with their opposite case are left as is
+        assert_eq!(
+            CASE_NORMALIZER.normalize_needle("ÉΣσ"),
+            Cow::Borrowed("ÉΣσ")
+        );
+    }
+
+    #[test]
+    fn folds_turkic_dotted_and_dotless_i() {
+        assert_eq!(TURKIC_NORMALIZER.normalize_needle("Iİıi"), "ıiıi");
+        // ASCII haystacks are only normalized when they contain "I"
+        assert_eq!(
+            normalize(TURKIC_NORMALIZER, "Izmir"),
+            Some(("ızmir".to_string(), vec![0, 0, 1, 2, 3, 4]))
+        );
+        assert_eq!(
+            normalize(TURKIC_NORMALIZER, "İzmir"),
+            Some(("izmir".to_string(), vec![0, 2, 3, 4, 5]))
+        );
+        assert_eq!(normalize(TURKIC_NORMALIZER, "izmir"), None);
+        // Case-sensitive matching doesn't fold
+        let normalizer = Normalizer::new(Normalization::Never, CaseLocale::Turkic, true);
+        assert_eq!(normalizer.normalize_needle("Iİ"), Cow::Borrowed("Iİ"));
+        assert_eq!(normalize(normalizer, "Izmir"), None);
+    }
+
+    #[test]
+    fn maps_bytes_to_original_offsets() {
+        assert_eq!(
+            normalize(DIACRITICS_NORMALIZER, "Café.md"),
+            Some(("Cafe.md".to_string(), vec![0, 1, 2, 3, 5, 6, 7]))
+        );
+        // Decomposed input drops the combining marks
+        assert_eq!(
+            normalize(DIACRITICS_NORMALIZER, "e\u{301}t"),
+            Some(("et".to_string(), vec![0, 3]))
+        );
+        assert_eq!(
+            normalize(DIACRITICS_NORMALIZER, "日ά"),
+            Some(("日α".to_string(), vec![0, 1, 2, 3, 4]))
+        );
+        // Expansions point to the start of the original char
+        assert_eq!(
+            normalize(CASE_NORMALIZER, "aßb"),
+            Some(("assb".to_string(), vec![0, 1, 1, 3]))
+        );
+    }
+
+    #[test]
+    fn maps_indices_once_per_original_char() {
+        let normalized = CASE_NORMALIZER
+            .normalize_haystack("aßb".as_bytes())
+            .unwrap();
+        let mut indices = vec![3, 2, 1];
+        normalized.original_indices("aßb".as_bytes(), &mut indices);
+        assert_eq!(indices, vec![3, 2, 1]);
+    }
+
+    #[test]
+    fn skips_haystacks_without_changes() {
+        assert_eq!(normalize(DIACRITICS_NORMALIZER, "cafe.md"), None);
+        assert_eq!(normalize(DIACRITICS_NORMALIZER, "日本"), None);
+        assert_eq!(normalize(CASE_NORMALIZER, "Café"), None);
+        assert!(
+            Normalizer::new(Normalization::Never, CaseLocale::Unicode, true)
+                .normalize_haystack("Straße".as_bytes())
+                .is_none()
+        );
+    }
+}
@@ -0,0 +1,344 @@
+//! Generated from the Unicode Character Database
+
+/// Each precomposed Latin, Greek and Cyrillic letter whose canonical decomposition (NFD) is a base
+/// letter followed only by combining marks, with that base letter, sorted by the precomposed
+/// letter
+#[rustfmt::skip]
+pub(super) const DIACRITICS: [(char, char); 788] = [
+    ('\u{00C0}', '\u{0041}'), ('\u{00C1}', '\u{0041}'), ('\u{00C2}', '\u{0041}'),
+    ('\u{00C3}', '\u{0041}'), ('\u{00C4}', '\u{0041}'), ('\u{00C5}', '\u{0041}'),
+    ('\u{00C7}', '\u{0043}'), ('\u{00C8}', '\u{0045}'), ('\u{00C9}', '\u{0045}'),
+    ('\u{00CA}', '\u{0045}'), ('\u{00CB}', '\u{0045}'), ('\u{00CC}', '\u{0049}'),
+    ('\u{00CD}', '\u{0049}'), ('\u{00CE}', '\u{0049}'), ('\u{00CF}', '\u{0049}'),
+    ('\u{00D1}', '\u{004E}'), ('\u{00D2}', '\u{004F}'), ('\u{00D3}', '\u{004F}'),
+    ('\u{00D4}', '\u{004F}'), ('\u{00D5}', '\u{004F}'), ('\u{00D6}', '\u{004F}'),
+    ('\u{00D9}', '\u{0055}'), ('\u{00DA}', '\u{0055}'), ('\u{00DB}', '\u{0055}'),
+    ('\u{00DC}', '\u{0055}'), ('\u{00DD}', '\u{0059}'), ('\u{00E0}', '\u{0061}'),
+    ('\u{00E1}', '\u{0061}'), ('\u{00E2}', '\u{0061}'), ('\u{00E3}', '\u{0061}'),
+    ('\u{00E4}', '\u{0061}'), ('\u{00E5}', '\u{0061}'), ('\u{00E7}', '\u{0063}'),
+    ('\u{00E8}', '\u{0065}'), ('\u{00E9}', '\u{0065}'), ('\u{00EA}', '\u{0065}'),
+    ('\u{00EB}', '\u{0065}'), ('\u{00EC}', '\u{0069}'), ('\u{00ED}', '\u{0069}'),
+    ('\u{00EE}', '\u{0069}'), ('\u{00EF}', '\u{0069}'), ('\u{00F1}', '\u{006E}'),
+    ('\u{00F2}', '\u{006F}'), ('\u{00F3}', '\u{006F}'), ('\u{00F4}', '\u{006F}'),
+    ('\u{00F5}', '\u{006F}'), ('\u{00F6}', '\u{006F}'), ('\u{00F9}', '\u{0075}'),
+    ('\u{00FA}', '\u{0075}'), ('\u{00FB}', '\u{0075}'), ('\u{00FC}', '\u{0075}'),
+    ('\u{00FD}', '\u{0079}'), ('\u{00FF}', '\u{0079}'), ('\u{0100}', '\u{0041}'),
+    ('\u{0101}', '\u{0061}'), ('\u{0102}', '\u{0041}'), ('\u{0103}', '\u{0061}'),
+    ('\u{0104}', '\u{0041}'), ('\u{0105}', '\u{0061}'), ('\u{0106}', '\u{0043}'),
+    ('\u{0107}', '\u{0063}'), ('\u{0108}', '\u{0043}'), ('\u{0109}', '\u{0063}'),
+    ('\u{010A}', '\u{0043}'), ('\u{010B}', '\u{0063}'), ('\u{010C}', '\u{0043}'),
+    ('\u{010D}', '\u{0063}'), ('\u{010E}', '\u{0044}'), ('\u{010F}', '\u{0064}'),
+    ('\u{0112}', '\u{0045}'), ('\u{0113}', '\u{0065}'), ('\u{0114}', '\u{0045}'),
+    ('\u{0115}', '\u{0065}'), ('\u{0116}', '\u{0045}'), ('\u{0117}', '\u{0065}'),
+    ('\u{0118}', '\u{0045}'), ('\u{0119}', '\u{0065}'), ('\u{011A}', '\u{0045}'),
+    ('\u{011B}', '\u{0065}'), ('\u{011C}', '\u{0047}'), ('\u{011D}', '\u{0067}'),
+    ('\u{011E}', '\u{0047}'), ('\u{011F}', '\u{0067}'), ('\u{0120}', '\u{0047}'),
+    ('\u{0121}', '\u{0067}'), ('\u{0122}', '\u{0047}'), ('\u{0123}', '\u{0067}'),
+    ('\u{0124}', '\u{0048}'), ('\u{0125}', '\u{0068}'), ('\u{0128}', '\u{0049}'),
+    ('\u{0129}', '\u{0069}'), ('\u{012A}', '\u{0049}'), ('\u{012B}', '\u{0069}'),
+    ('\u{012C}', '\u{0049}'), ('\u{012D}', '\u{0069}'), ('\u{012E}', '\u{0049}'),
+    ('\u{012F}', '\u{0069}'), ('\u{0130}', '\u{0049}'), ('\u{0134}', '\u{004A}'),
+    ('\u{0135}', '\u{006A}'), ('\u{0136}', '\u{004B}'), ('\u{0137}', '\u{006B}'),
+    ('\u{0139}', '\u{004C}'), ('\u{013A}', '\u{006C}'), ('\u{013B}', '\u{004C}'),
+    ('\u{013C}', '\u{006C}'), ('\u{013D}', '\u{004C}'), ('\u{013E}', '\u{006C}'),
+    ('\u{0143}', '\u{004E}'), ('\u{0144}', '\u{006E}'), ('\u{0145}', '\u{004E}'),
+    ('\u{0146}', '\u{006E}'), ('\u{0147}', '\u{004E}'), ('\u{0148}', '\u{006E}'),
+    ('\u{014C}', '\u{004F}'), ('\u{014D}', '\u{006F}'), ('\u{014E}', '\u{004F}'),
+    ('\u{014F}', '\u{006F}'), ('\u{0150}', '\u{004F}'), ('\u{0151}', '\u{006F}'),
+    ('\u{0154}', '\u{0052}'), ('\u{0155}', '\u{0072}'), ('\u{0156}', '\u{0052}'),
+    ('\u{0157}', '\u{0072}'), ('\u{0158}', '\u{0052}'), ('\u{0159}', '\u{0072}'),
+    ('\u{015A}', '\u{0053}'), ('\u{015B}', '\u{0073}'), ('\u{015C}', '\u{0053}'),
+    ('\u{015D}', '\u{0073}'), ('\u{015E}', '\u{0053}'), ('\u{015F}', '\u{0073}'),
+    ('\u{0160}', '\u{0053}'), ('\u{0161}', '\u{0073}'), ('\u{0162}', '\u{0054}'),
+    ('\u{0163}', '\u{0074}'), ('\u{0164}', '\u{0054}'), ('\u{0165}', '\u{0074}'),
+    ('\u{0168}', '\u{0055}'), ('\u{0169}', '\u{0075}'), ('\u{016A}', '\u{0055}'),
+    ('\u{016B}', '\u{0075}'), ('\u{016C}', '\u{0055}'), ('\u{016D}', '\u{0075}'),
+    ('\u{016E}', '\u{0055}'), ('\u{016F}', '\u{0075}'), ('\u{0170}', '\u{0055}'),
+    ('\u{0171}', '\u{0075}'), ('\u{0172}', '\u{0055}'), ('\u{0173}', '\u{0075}'),
+    ('\u{0174}', '\u{0057}'), ('\u{0175}', '\u{0077}'), ('\u{0176}', '\u{0059}'),
+    ('\u{0177}', '\u{0079}'), ('\u{0178}', '\u{0059}'), ('\u{0179}', '\u{005A}'),
+    ('\u{017A}', '\u{007A}'), ('\u{017B}', '\u{005A}'), ('\u{017C}', '\u{007A}'),
+    ('\u{017D}', '\u{005A}'), ('\u{017E}', '\u{007A}'), ('\u{01A0}', '\u{004F}'),
+    ('\u{01A1}', '\u{006F}'), ('\u{01AF}', '\u{0055}'), ('\u{01B0}', '\u{0075}'),
+    ('\u{01CD}', '\u{0041}'), ('\u{01CE}', '\u{0061}'), ('\u{01CF}', '\u{0049}'),
+    ('\u{01D0}', '\u{0069}'), ('\u{01D1}', '\u{004F}'), ('\u{01D2}', '\u{006F}'),
+    ('\u{01D3}', '\u{0055}'), ('\u{01D4}', '\u{0075}'), ('\u{01D5}', '\u{0055}'),
+    ('\u{01D6}', '\u{0075}'), ('\u{01D7}', '\u{0055}'), ('\u{01D8}', '\u{0075}'),
+    ('\u{01D9}', '\u{0055}'), ('\u{01DA}', '\u{0075}'), ('\u{01DB}', '\u{0055}'),
+    ('\u{01DC}', '\u{0075}'), ('\u{01DE}', '\u{0041}'), ('\u{01DF}', '\u{0061}'),
+    ('\u{01E0}', '\u{0041}'), ('\u{01E1}', '\u{0061}'), ('\u{01E2}', '\u{00C6}'),
+    ('\u{01E3}', '\u{00E6}'), ('\u{01E6}', '\u{0047}'), ('\u{01E7}', '\u{0067}'),
+    ('\u{01E8}', '\u{004B}'), ('\u{01E9}', '\u{006B}'), ('\u{01EA}', '\u{004F}'),
+    ('\u{01EB}', '\u{006F}'), ('\u{01EC}', '\u{004F}'), ('\u{01ED}', '\u{006F}'),
+    ('\u{01EE}', '\u{01B7}'), ('\u{01EF}', '\u{0292}'), ('\u{01F0}', '\u{006A}'),
+    ('\u{01F4}', '\u{0047}'), ('\u{01F5}', '\u{0067}'), ('\u{01F8}', '\u{004E}'),
+    ('\u{01F9}', '\u{006E}'), ('\u{01FA}', '\u{0041}'), ('\u{01FB}', '\u{0061}'),
+    ('\u{01FC}', '\u{00C6}'), ('\u{01FD}', '\u{00E6}'), ('\u{01FE}', '\u{00D8}'),
+    ('\u{01FF}', '\u{00F8}'), ('\u{0200}', '\u{0041}'), ('\u{0201}', '\u{0061}'),
+    ('\u{0202}', '\u{0041}'), ('\u{0203}', '\u{0061}'), ('\u{0204}', '\u{0045}'),
+    ('\u{0205}', '\u{0065}'), ('\u{0206}', '\u{0045}'), ('\u{0207}', '\u{0065}'),
+    ('\u{0208}', '\u{0049}'), ('\u{0209}', '\u{0069}'), ('\u{020A}', '\u{0049}'),
+    ('\u{020B}', '\u{0069}'), ('\u{020C}', '\u{004F}'), ('\u{020D}', '\u{006F}'),
+    ('\u{020E}', '\u{004F}'), ('\u{020F}', '\u{006F}'), ('\u{0210}', '\u{0052}'),
+    ('\u{0211}', '\u{0072}'), ('\u{0212}', '\u{0052}'), ('\u{0213}', '\u{0072}'),
+    ('\u{0214}', '\u{0055}'), ('\u{0215}', '\u{0075}'), ('\u{0216}', '\u{0055}'),
+    ('\u{0217}', '\u{0075}'), ('\u{0218}', '\u{0053}'), ('\u{0219}', '\u{0073}'),
+    ('\u{021A}', '\u{0054}'), ('\u{021B}', '\u{0074}'), ('\u{021E}', '\u{0048}'),
+    ('\u{021F}', '\u{0068}'), ('\u{0226}', '\u{0041}'), ('\u{0227}', '\u{0061}'),
+    ('\u{0228}', '\u{0045}'), ('\u{0229}', '\u{0065}'), ('\u{022A}', '\u{004F}'),
+    ('\u{022B}', '\u{006F}'), ('\u{022C}', '\u{004F}'), ('\u{022D}', '\u{006F}'),
+    ('\u{022E}', '\u{004F}'), ('\u{022F}', '\u{006F}'), ('\u{0230}', '\u{004F}'),
+    ('\u{0231}', '\u{006F}'), ('\u{0232}', '\u{0059}'), ('\u{0233}', '\u{0079}'),
+    ('\u{0386}', '\u{0391}'), ('\u{0388}', '\u{0395}'), ('\u{0389}', '\u{0397}'),
+    ('\u{038A}', '\u{0399}'), ('\u{038C}', '\u{039F}'), ('\u{038E}', '\u{03A5}'),
+    ('\u{038F}', '\u{03A9}'), ('\u{0390}', '\u{03B9}'), ('\u{03AA}', '\u{0399}'),
+    ('\u{03AB}', '\u{03A5}'), ('\u{03AC}', '\u{03B1}'), ('\u{03AD}', '\u{03B5}'),
+    ('\u{03AE}', '\u{03B7}'), ('\u{03AF}', '\u{03B9}'), ('\u{03B0}', '\u{03C5}'),
+    ('\u{03CA}', '\u{03B9}'), ('\u{03CB}', '\u{03C5}'), ('\u{03CC}', '\u{03BF}'),
+    ('\u{03CD}', '\u{03C5}'), ('\u{03CE}', '\u{03C9}'), ('\u{03D3}', '\u{03D2}'),
+    ('\u{03D4}', '\u{03D2}'), ('\u{0400}', '\u{0415}'), ('\u{0401}', '\u{0415}'),
+    ('\u{0403}', '\u{0413}'), ('\u{0407}', '\u{0406}'), ('\u{040C}', '\u{041A}'),
+    ('\u{040D}', '\u{0418}'), ('\u{040E}', '\u{0423}'), ('\u{0419}', '\u{0418}'),
+    ('\u{0439}', '\u{0438}'), ('\u{0450}', '\u{0435}'), ('\u{0451}', '\u{0435}'),
+    ('\u{0453}', '\u{0433}'), ('\u{0457}', '\u{0456}'), ('\u{045C}', '\u{043A}'),
+    ('\u{045D}', '\u{0438}'), ('\u{045E}', '\u{0443}'), ('\u{0476}', '\u{0474}'),
+    ('\u{0477}', '\u{0475}'), ('\u{04C1}', '\u{0416}'), ('\u{04C2}', '\u{0436}'),
+    ('\u{04D0}', '\u{0410}'), ('\u{04D1}', '\u{0430}'), ('\u{04D2}', '\u{0410}'),
+    ('\u{04D3}', '\u{0430}'), ('\u{04D6}', '\u{0415}'), ('\u{04D7}', '\u{0435}'),
+    ('\u{04DA}', '\u{04D8}'), ('\u{04DB}', '\u{04D9}'), ('\u{04DC}', '\u{0416}'),
+    ('\u{04DD}', '\u{0436}'), ('\u{04DE}', '\u{0417}'), ('\u{04DF}', '\u{0437}'),
+    ('\u{04E2}', '\u{0418}'), ('\u{04E3}', '\u{0438}'), ('\u{04E4}', '\u{0418}'),
+    ('\u{04E5}', '\u{0438}'), ('\u{04E6}', '\u{041E}'), ('\u{04E7}', '\u{043E}'),
+    ('\u{04EA}', '\u{04E8}'), ('\u{04EB}', '\u{04E9}'), ('\u{04EC}', '\u{042D}'),
+    ('\u{04ED}', '\u{044D}'), ('\u{04EE}', '\u{0423}'), ('\u{04EF}', '\u{0443}'),
+    ('\u{04F0}', '\u{0423}'), ('\u{04F1}', '\u{0443}'), ('\u{04F2}', '\u{0423}'),
+    ('\u{04F3}', '\u{0443}'), ('\u{04F4}', '\u{0427}'), ('\u{04F5}', '\u{0447}'),
+    ('\u{04F8}', '\u{042B}'), ('\u{04F9}', '\u{044B}'), ('\u{1E00}', '\u{0041}'),
+    ('\u{1E01}', '\u{0061}'), ('\u{1E02}', '\u{0042}'), ('\u{1E03}', '\u{0062}'),
+    ('\u{1E04}', '\u{0042}'), ('\u{1E05}', '\u{0062}'), ('\u{1E06}', '\u{0042}'),
+    ('\u{1E07}', '\u{0062}'), ('\u{1E08}', '\u{0043}'), ('\u{1E09}', '\u{0063}'),
+    ('\u{1E0A}', '\u{0044}'), ('\u{1E0B}', '\u{0064}'), ('\u{1E0C}', '\u{0044}'),
+    ('\u{1E0D}', '\u{0064}'), ('\u{1E0E}', '\u{0044}'), ('\u{1E0F}', '\u{0064}'),
+    ('\u{1E10}', '\u{0044}'), ('\u{1E11}', '\u{0064}'), ('\u{1E12}', '\u{0044}'),
+    ('\u{1E13}', '\u{0064}'), ('\u{1E14}', '\u{0045}'), ('\u{1E15}', '\u{0065}'),
+    ('\u{1E16}', '\u{0045}'), ('\u{1E17}', '\u{0065}'), ('\u{1E18}', '\u{0045}'),
+    ('\u{1E19}', '\u{0065}'), ('\u{1E1A}', '\u{0045}'), ('\u{1E1B}', '\u{0065}'),
+    ('\u{1E1C}', '\u{0045}'), ('\u{1E1D}', '\u{0065}'), ('\u{1E1E}', '\u{0046}'),
+    ('\u{1E1F}', '\u{0066}'), ('\u{1E20}', '\u{0047}'), ('\u{1E21}', '\u{0067}'),
+    ('\u{1E22}', '\u{0048}'), ('\u{1E23}', '\u{0068}'), ('\u{1E24}', '\u{0048}'),
+    ('\u{1E25}', '\u{0068}'), ('\u{1E26}', '\u{0048}'), ('\u{1E27}', '\u{0068}'),
+    ('\u{1E28}', '\u{0048}'), ('\u{1E29}', '\u{0068}'), ('\u{1E2A}', '\u{0048}'),
+    ('\u{1E2B}', '\u{0068}'), ('\u{1E2C}', '\u{0049}'), ('\u{1E2D}', '\u{0069}'),
+    ('\u{1E2E}', '\u{0049}'), ('\u{1E2F}', '\u{0069}'), ('\u{1E30}', '\u{004B}'),
+    ('\u{1E31}', '\u{006B}'), ('\u{1E32}', '\u{004B}'), ('\u{1E33}', '\u{006B}'),
+    ('\u{1E34}', '\u{004B}'), ('\u{1E35}', '\u{006B}'), ('\u{1E36}', '\u{004C}'),
+    ('\u{1E37}', '\u{006C}'), ('\u{1E38}', '\u{004C}'), ('\u{1E39}', '\u{006C}'),
+    ('\u{1E3A}', '\u{004C}'), ('\u{1E3B}', '\u{006C}'), ('\u{1E3C}', '\u{004C}'),
+    ('\u{1E3D}', '\u{006C}'), ('\u{1E3E}', '\u{004D}'), ('\u{1E3F}', '\u{006D}'),
+    ('\u{1E40}', '\u{004D}'), ('\u{1E41}', '\u{006D}'), ('\u{1E42}', '\u{004D}'),
+    ('\u{1E43}', '\u{006D}'), ('\u{1E44}', '\u{004E}'), ('\u{1E45}', '\u{006E}'),
+    ('\u{1E46}', '\u{004E}'), ('\u{1E47}', '\u{006E}'), ('\u{1E48}', '\u{004E}'),
+    ('\u{1E49}', '\u{006E}'), ('\u{1E4A}', '\u{004E}'), ('\u{1E4B}', '\u{006E}'),
+    ('\u{1E4C}', '\u{004F}'), ('\u{1E4D}', '\u{006F}'), ('\u{1E4E}', '\u{004F}'),
+    ('\u{1E4F}', '\u{006F}'), ('\u{1E50}', '\u{004F}'), ('\u{1E51}', '\u{006F}'),
+    ('\u{1E52}', '\u{004F}'), ('\u{1E53}', '\u{006F}'), ('\u{1E54}', '\u{0050}'),
+    ('\u{1E55}', '\u{0070}'), ('\u{1E56}', '\u{0050}'), ('\u{1E57}', '\u{0070}'),
+    ('\u{1E58}', '\u{0052}'), ('\u{1E59}', '\u{0072}'), ('\u{1E5A}', '\u{0052}'),
+    ('\u{1E5B}', '\u{0072}'), ('\u{1E5C}', '\u{0052}'), ('\u{1E5D}', '\u{0072}'),
+    ('\u{1E5E}', '\u{0052}'), ('\u{1E5F}', '\u{0072}'), ('\u{1E60}', '\u{0053}'),
+    ('\u{1E61}', '\u{0073}'), ('\u{1E62}', '\u{0053}'), ('\u{1E63}', '\u{0073}'),
+    ('\u{1E64}', '\u{0053}'), ('\u{1E65}', '\u{0073}'), ('\u{1E66}', '\u{0053}'),
+    ('\u{1E67}', '\u{0073}'), ('\u{1E68}', '\u{0053}'), ('\u{1E69}', '\u{0073}'),
+    ('\u{1E6A}', '\u{0054}'), ('\u{1E6B}', '\u{0074}'), ('\u{1E6C}', '\u{0054}'),
+    ('\u{1E6D}', '\u{0074}'), ('\u{1E6E}', '\u{0054}'), ('\u{1E6F}', '\u{0074}'),
+    ('\u{1E70}', '\u{0054}'), ('\u{1E71}', '\u{0074}'), ('\u{1E72}', '\u{0055}'),
+    ('\u{1E73}', '\u{0075}'), ('\u{1E74}', '\u{0055}'), ('\u{1E75}', '\u{0075}'),
+    ('\u{1E76}', '\u{0055}'), ('\u{1E77}', '\u{0075}'), ('\u{1E78}', '\u{0055}'),
+    ('\u{1E79}', '\u{0075}'), ('\u{1E7A}', '\u{0055}'), ('\u{1E7B}', '\u{0075}'),
+    ('\u{1E7C}', '\u{0056}'), ('\u{1E7D}', '\u{0076}'), ('\u{1E7E}', '\u{0056}'),
+    ('\u{1E7F}', '\u{0076}'), ('\u{1E80}', '\u{0057}'), ('\u{1E81}', '\u{0077}'),
+    ('\u{1E82}', '\u{0057}'), ('\u{1E83}', '\u{0077}'), ('\u{1E84}', '\u{0057}'),
+    ('\u{1E85}', '\u{0077}'), ('\u{1E86}', '\u{0057}'), ('\u{1E87}', '\u{0077}'),
+    ('\u{1E88}', '\u{0057}'), ('\u{1E89}', '\u{0077}'), ('\u{1E8A}', '\u{0058}'),
+    ('\u{1E8B}', '\u{0078}'), ('\u{1E8C}', '\u{0058}'), ('\u{1E8D}', '\u{0078}'),
+    ('\u{1E8E}', '\u{0059}'), ('\u{1E8F}', '\u{0079}'), ('\u{1E90}', '\u{005A}'),
+    ('\u{1E91}', '\u{007A}'), ('\u{1E92}', '\u{005A}'), ('\u{1E93}', '\u{007A}'),
+    ('\u{1E94}', '\u{005A}'), ('\u{1E95}', '\u{007A}'), ('\u{1E96}', '\u{0068}'),
+    ('\u{1E97}', '\u{0074}'), ('\u{1E98}', '\u{0077}'), ('\u{1E99}', '\u{0079}'),
+    ('\u{1E9B}', '\u{017F}'), ('\u{1EA0}', '\u{0041}'), ('\u{1EA1}', '\u{0061}'),
+    ('\u{1EA2}', '\u{0041}'), ('\u{1EA3}', '\u{0061}'), ('\u{1EA4}', '\u{0041}'),
+    ('\u{1EA5}', '\u{0061}'), ('\u{1EA6}', '\u{0041}'), ('\u{1EA7}', '\u{0061}'),
+    ('\u{1EA8}', '\u{0041}'), ('\u{1EA9}', '\u{0061}'), ('\u{1EAA}', '\u{0041}'),
+    ('\u{1EAB}', '\u{0061}'), ('\u{1EAC}', '\u{0041}'), ('\u{1EAD}', '\u{0061}'),
+    ('\u{1EAE}', '\u{0041}'), ('\u{1EAF}', '\u{0061}'), ('\u{1EB0}', '\u{0041}'),
+    ('\u{1EB1}', '\u{0061}'), ('\u{1EB2}', '\u{0041}'), ('\u{1EB3}', '\u{0061}'),
+    ('\u{1EB4}', '\u{0041}'), ('\u{1EB5}', '\u{0061}'), ('\u{1EB6}', '\u{0041}'),
+    ('\u{1EB7}', '\u{0061}'), ('\u{1EB8}', '\u{0045}'), ('\u{1EB9}', '\u{0065}'),
+    ('\u{1EBA}', '\u{0045}'), ('\u{1EBB}', '\u{0065}'), ('\u{1EBC}', '\u{0045}'),
+    ('\u{1EBD}', '\u{0065}'), ('\u{1EBE}', '\u{0045}'), ('\u{1EBF}', '\u{0065}'),
+    ('\u{1EC0}', '\u{0045}'), ('\u{1EC1}', '\u{0065}'), ('\u{1EC2}', '\u{0045}'),
+    ('\u{1EC3}', '\u{0065}'), ('\u{1EC4}', '\u{0045}'), ('\u{1EC5}', '\u{0065}'),
+    ('\u{1EC6}', '\u{0045}'), ('\u{1EC7}', '\u{0065}'), ('\u{1EC8}', '\u{0049}'),
+    ('\u{1EC9}', '\u{0069}'), ('\u{1ECA}', '\u{0049}'), ('\u{1ECB}', '\u{0069}'),
+    ('\u{1ECC}', '\u{004F}'), ('\u{1ECD}', '\u{006F}'), ('\u{1ECE}', '\u{004F}'),
+    ('\u{1ECF}', '\u{006F}'), ('\u{1ED0}', '\u{004F}'), ('\u{1ED1}', '\u{006F}'),
+    ('\u{1ED2}', '\u{004F}'), ('\u{1ED3}', '\u{006F}'), ('\u{1ED4}', '\u{004F}'),
+    ('\u{1ED5}', '\u{006F}'), ('\u{1ED6}', '\u{004F}'), ('\u{1ED7}', '\u{006F}'),
+    ('\u{1ED8}', '\u{004F}'), ('\u{1ED9}', '\u{006F}'), ('\u{1EDA}', '\u{004F}'),
+    ('\u{1EDB}', '\u{006F}'), ('\u{1EDC}', '\u{004F}'), ('\u{1EDD}', '\u{006F}'),
+    ('\u{1EDE}', '\u{004F}'), ('\u{1EDF}', '\u{006F}'), ('\u{1EE0}', '\u{004F}'),
+    ('\u{1EE1}', '\u{006F}'), ('\u{1EE2}', '\u{004F}'), ('\u{1EE3}', '\u{006F}'),
+    ('\u{1EE4}', '\u{0055}'), ('\u{1EE5}', '\u{0075}'), ('\u{1EE6}', '\u{0055}'),
+    ('\u{1EE7}', '\u{0075}'), ('\u{1EE8}', '\u{0055}'), ('\u{1EE9}', '\u{0075}'),
+    ('\u{1EEA}', '\u{0055}'), ('\u{1EEB}', '\u{0075}'), ('\u{1EEC}', '\u{0055}'),
+    ('\u{1EED}', '\u{0075}'), ('\u{1EEE}', '\u{0055}'), ('\u{1EEF}', '\u{0075}'),
+    ('\u{1EF0}', '\u{0055}'), ('\u{1EF1}', '\u{0075}'), ('\u{1EF2}', '\u{0059}'),
+    ('\u{1EF3}', '\u{0079}'), ('\u{1EF4}', '\u{0059}'), ('\u{1EF5}', '\u{0079}'),
+    ('\u{1EF6}', '\u{0059}'), ('\u{1EF7}', '\u{0079}'), ('\u{1EF8}', '\u{0059}'),
+    ('\u{1EF9}', '\u{0079}'), ('\u{1F00}', '\u{03B1}'), ('\u{1F01}', '\u{03B1}'),
+    ('\u{1F02}', '\u{03B1}'), ('\u{1F03}', '\u{03B1}'), ('\u{1F04}', '\u{03B1}'),
+    ('\u{1F05}', '\u{03B1}'), ('\u{1F06}', '\u{03B1}'), ('\u{1F07}', '\u{03B1}'),
+    ('\u{1F08}', '\u{0391}'), ('\u{1F09}', '\u{0391}'), ('\u{1F0A}', '\u{0391}'),
+    ('\u{1F0B}', '\u{0391}'), ('\u{1F0C}', '\u{0391}'), ('\u{1F0D}', '\u{0391}'),
+    ('\u{1F0E}', '\u{0391}'), ('\u{1F0F}', '\u{0391}'), ('\u{1F10}', '\u{03B5}'),
+    ('\u{1F11}', '\u{03B5}'), ('\u{1F12}', '\u{03B5}'), ('\u{1F13}', '\u{03B5}'),
+    ('\u{1F14}', '\u{03B5}'), ('\u{1F15}', '\u{03B5}'), ('\u{1F18}', '\u{0395}'),
+    ('\u{1F19}', '\u{0395}'), ('\u{1F1A}', '\u{0395}'), ('\u{1F1B}', '\u{0395}'),
+    ('\u{1F1C}', '\u{0395}'), ('\u{1F1D}', '\u{0395}'), ('\u{1F20}', '\u{03B7}'),
+    ('\u{1F21}', '\u{03B7}'), ('\u{1F22}', '\u{03B7}'), ('\u{1F23}', '\u{03B7}'),
+    ('\u{1F24}', '\u{03B7}'), ('\u{1F25}', '\u{03B7}'), ('\u{1F26}', '\u{03B7}'),
+    ('\u{1F27}', '\u{03B7}'), ('\u{1F28}', '\u{0397}'), ('\u{1F29}', '\u{0397}'),
+    ('\u{1F2A}', '\u{0397}'), ('\u{1F2B}', '\u{0397}'), ('\u{1F2C}', '\u{0397}'),
+    ('\u{1F2D}', '\u{0397}'), ('\u{1F2E}', '\u{0397}'), ('\u{1F2F}', '\u{0397}'),
+    ('\u{1F30}', '\u{03B9}'), ('\u{1F31}', '\u{03B9}'), ('\u{1F32}', '\u{03B9}'),
+    ('\u{1F33}', '\u{03B9}'), ('\u{1F34}', '\u{03B9}'), ('\u{1F35}', '\u{03B9}'),
+    ('\u{1F36}', '\u{03B9}'), ('\u{1F37}', '\u{03B9}'), ('\u{1F38}', '\u{0399}'),
+    ('\u{1F39}', '\u{0399}'), ('\u{1F3A}', '\u{0399}'), ('\u{1F3B}', '\u{0399}'),
+    ('\u{1F3C}', '\u{0399}'), ('\u{1F3D}', '\u{0399}'), ('\u{1F3E}', '\u{0399}'),
+    ('\u{1F3F}', '\u{0399}'), ('\u{1F40}', '\u{03BF}'), ('\u{1F41}', '\u{03BF}'),
+    ('\u{1F42}', '\u{03BF}'), ('\u{1F43}', '\u{03BF}'), ('\u{1F44}', '\u{03BF}'),
+    ('\u{1F45}', '\u{03BF}'), ('\u{1F48}', '\u{039F}'), ('\u{1F49}', '\u{039F}'),
+    ('\u{1F4A}', '\u{039F}'), ('\u{1F4B}', '\u{039F}'), ('\u{1F4C}', '\u{039F}'),
+    ('\u{1F4D}', '\u{039F}'), ('\u{1F50}', '\u{03C5}'), ('\u{1F51}', '\u{03C5}'),
+    ('\u{1F52}', '\u{03C5}'), ('\u{1F53}', '\u{03C5}'), ('\u{1F54}', '\u{03C5}'),
+    ('\u{1F55}', '\u{03C5}'), ('\u{1F56}', '\u{03C5}'), ('\u{1F57}', '\u{03C5}'),
+    ('\u{1F59}', '\u{03A5}'), ('\u{1F5B}', '\u{03A5}'), ('\u{1F5D}', '\u{03A5}'),
+    ('\u{1F5F}', '\u{03A5}'), ('\u{1F60}', '\u{03C9}'), ('\u{1F61}', '\u{03C9}'),
+    ('\u{1F62}', '\u{03C9}'), ('\u{1F63}', '\u{03C9}'), ('\u{1F64}', '\u{03C9}'),
+    ('\u{1F65}', '\u{03C9}'), ('\u{1F66}', '\u{03C9}'), ('\u{1F67}', '\u{03C9}'),
+    ('\u{1F68}', '\u{03A9}'), ('\u{1F69}', '\u{03A9}'), ('\u{1F6A}', '\u{03A9}'),
+    ('\u{1F6B}', '\u{03A9}'), ('\u{1F6C}', '\u{03A9}'), ('\u{1F6D}', '\u{03A9}'),
+    ('\u{1F6E}', '\u{03A9}'), ('\u{1F6F}', '\u{03A9}'), ('\u{1F70}', '\u{03B1}'),
+    ('\u{1F71}', '\u{03B1}'), ('\u{1F72}', '\u{03B5}'), ('\u{1F73}', '\u{03B5}'),
+    ('\u{1F74}', '\u{03B7}'), ('\u{1F75}', '\u{03B7}'), ('\u{1F76}', '\u{03B9}'),
+    ('\u{1F77}', '\u{03B9}'), ('\u{1F78}', '\u{03BF}'), ('\u{1F79}', '\u{03BF}'),
+    ('\u{1F7A}', '\u{03C5}'), ('\u{1F7B}', '\u{03C5}'), ('\u{1F7C}', '\u{03C9}'),
+    ('\u{1F7D}', '\u{03C9}'), ('\u{1F80}', '\u{03B1}'), ('\u{1F81}', '\u{03B1}'),
+    ('\u{1F82}', '\u{03B1}'), ('\u{1F83}', '\u{03B1}'), ('\u{1F84}', '\u{03B1}'),
+    ('\u{1F85}', '\u{03B1}'), ('\u{1F86}', '\u{03B1}'), ('\u{1F87}', '\u{03B1}'),
+    ('\u{1F88}', '\u{0391}'), ('\u{1F89}', '\u{0391}'), ('\u{1F8A}', '\u{0391}'),
+    ('\u{1F8B}', '\u{0391}'), ('\u{1F8C}', '\u{0391}'), ('\u{1F8D}', '\u{0391}'),
+    ('\u{1F8E}', '\u{0391}'), ('\u{1F8F}', '\u{0391}'), ('\u{1F90}', '\u{03B7}'),
+    ('\u{1F91}', '\u{03B7}'), ('\u{1F92}', '\u{03B7}'), ('\u{1F93}', '\u{03B7}'),
+    ('\u{1F94}', '\u{03B7}'), ('\u{1F95}', '\u{03B7}'), ('\u{1F96}', '\u{03B7}'),
+    ('\u{1F97}', '\u{03B7}'), ('\u{1F98}', '\u{0397}'), ('\u{1F99}', '\u{0397}'),
+    ('\u{1F9A}', '\u{0397}'), ('\u{1F9B}', '\u{0397}'), ('\u{1F9C}', '\u{0397}'),
+    ('\u{1F9D}', '\u{0397}'), ('\u{1F9E}', '\u{0397}'), ('\u{1F9F}', '\u{0397}'),
+    ('\u{1FA0}', '\u{03C9}'), ('\u{1FA1}', '\u{03C9}'), ('\u{1FA2}', '\u{03C9}'),
+    ('\u{1FA3}', '\u{03C9}'), ('\u{1FA4}', '\u{03C9}'), ('\u{1FA5}', '\u{03C9}'),
+    ('\u{1FA6}', '\u{03C9}'), ('\u{1FA7}', '\u{03C9}'), ('\u{1FA8}', '\u{03A9}'),
+    ('\u{1FA9}', '\u{03A9}'), ('\u{1FAA}', '\u{03A9}'), ('\u{1FAB}', '\u{03A9}'),
+    ('\u{1FAC}', '\u{03A9}'), ('\u{1FAD}', '\u{03A9}'), ('\u{1FAE}', '\u{03A9}'),
+    ('\u{1FAF}', '\u{03A9}'), ('\u{1FB0}', '\u{03B1}'), ('\u{1FB1}', '\u{03B1}'),
+    ('\u{1FB2}', '\u{03B1}'), ('\u{1FB3}', '\u{03B1}'), ('\u{1FB4}', '\u{03B1}'),
+    ('\u{1FB6}', '\u{03B1}'), ('\u{1FB7}', '\u{03B1}'), ('\u{1FB8}', '\u{0391}'),
+    ('\u{1FB9}', '\u{0391}'), ('\u{1FBA}', '\u{0391}'), ('\u{1FBB}', '\u{0391}'),
+    ('\u{1FBC}', '\u{0391}'), ('\u{1FC2}', '\u{03B7}'), ('\u{1FC3}', '\u{03B7}'),
+    ('\u{1FC4}', '\u{03B7}'), ('\u{1FC6}', '\u{03B7}'), ('\u{1FC7}', '\u{03B7}'),
+    ('\u{1FC8}', '\u{0395}'), ('\u{1FC9}', '\u{0395}'), ('\u{1FCA}', '\u{0397}'),
+    ('\u{1FCB}', '\u{0397}'), ('\u{1FCC}', '\u{0397}'), ('\u{1FD0}', '\u{03B9}'),
+    ('\u{1FD1}', '\u{03B9}'), ('\u{1FD2}', '\u{03B9}'), ('\u{1FD3}', '\u{03B9}'),
+    ('\u{1FD6}', '\u{03B9}'), ('\u{1FD7}', '\u{03B9}'), ('\u{1FD8}', '\u{0399}'),
+    ('\u{1FD9}', '\u{0399}'), ('\u{1FDA}', '\u{0399}'), ('\u{1FDB}', '\u{0399}'),
+    ('\u{1FE0}', '\u{03C5}'), ('\u{1FE1}', '\u{03C5}'), ('\u{1FE2}', '\u{03C5}'),
+    ('\u{1FE3}', '\u{03C5}'), ('\u{1FE4}', '\u{03C1}'), ('\u{1FE5}', '\u{03C1}'),
+    ('\u{1FE6}', '\u{03C5}'), ('\u{1FE7}', '\u{03C5}'), ('\u{1FE8}', '\u{03A5}'),
+    ('\u{1FE9}', '\u{03A5}'), ('\u{1FEA}', '\u{03A5}'), ('\u{1FEB}', '\u{03A5}'),
+    ('\u{1FEC}', '\u{03A1}'), ('\u{1FF2}', '\u{03C9}'), ('\u{1FF3}', '\u{03C9}'),
+    ('\u{1FF4}', '\u{03C9}'), ('\u{1FF6}', '\u{03C9}'), ('\u{1FF7}', '\u{03C9}'),
+    ('\u{1FF8}', '\u{039F}'), ('\u{1FF9}', '\u{039F}'), ('\u{1FFA}', '\u{03A9}'),
+    ('\u{1FFB}', '\u{03A9}'), ('\u{1FFC}', '\u{03A9}'),
+];
+
+/// Each char whose full case folding (`CaseFolding.txt` with the `C` and `F` statuses) isn't
+/// its opposite case of the same UTF-8 length, with its case folding, sorted by the char. The
+/// other chars are matched case-insensitively by flipping their case instead
+#[rustfmt::skip]
+pub(super) const CASE_FOLDS: [(char, &str); 153] = [
+    ('\u{00B5}', "\u{03BC}"), ('\u{00DF}', "ss"), ('\u{0130}', "i\u{0307}"),
+    ('\u{0149}', "\u{02BC}n"), ('\u{017F}', "s"), ('\u{01C5}', "\u{01C6}"),
+    ('\u{01C8}', "\u{01C9}"), ('\u{01CB}', "\u{01CC}"), ('\u{01F0}', "j\u{030C}"),
+    ('\u{01F2}', "\u{01F3}"), ('\u{023A}', "\u{2C65}"), ('\u{023E}', "\u{2C66}"),
+    ('\u{0345}', "\u{03B9}"), ('\u{0390}', "\u{03B9}\u{0308}\u{0301}"),
+    ('\u{03B0}', "\u{03C5}\u{0308}\u{0301}"), ('\u{03C2}', "\u{03C3}"), ('\u{03D0}', "\u{03B2}"),
+    ('\u{03D1}', "\u{03B8}"), ('\u{03D5}', "\u{03C6}"), ('\u{03D6}', "\u{03C0}"),
+    ('\u{03F0}', "\u{03BA}"), ('\u{03F1}', "\u{03C1}"), ('\u{03F5}', "\u{03B5}"),
+    ('\u{0587}', "\u{0565}\u{0582}"), ('\u{1C80}', "\u{0432}"), ('\u{1C81}', "\u{0434}"),
+    ('\u{1C82}', "\u{043E}"), ('\u{1C83}', "\u{0441}"), ('\u{1C84}', "\u{0442}"),
+    ('\u{1C85}', "\u{0442}"), ('\u{1C86}', "\u{044A}"), ('\u{1C87}', "\u{0463}"),
+    ('\u{1C88}', "\u{A64B}"), ('\u{1E96}', "h\u{0331}"), ('\u{1E97}', "t\u{0308}"),
+    ('\u{1E98}', "w\u{030A}"), ('\u{1E99}', "y\u{030A}"),
+    ('\u{1E9A}', "a\u{02BE}"), ('\u{1E9B}', "\u{1E61}"), ('\u{1E9E}', "ss"),
+    ('\u{1F50}', "\u{03C5}\u{0313}"), ('\u{1F52}', "\u{03C5}\u{0313}\u{0300}"),
+    ('\u{1F54}', "\u{03C5}\u{0313}\u{0301}"), ('\u{1F56}', "\u{03C5}\u{0313}\u{0342}"),
+    ('\u{1F80}', "\u{1F00}\u{03B9}"), ('\u{1F81}', "\u{1F01}\u{03B9}"),
+    ('\u{1F82}', "\u{1F02}\u{03B9}"), ('\u{1F83}', "\u{1F03}\u{03B9}"),
+    ('\u{1F84}', "\u{1F04}\u{03B9}"), ('\u{1F85}', "\u{1F05}\u{03B9}"),
+    ('\u{1F86}', "\u{1F06}\u{03B9}"), ('\u{1F87}', "\u{1F07}\u{03B9}"),
+    ('\u{1F88}', "\u{1F00}\u{03B9}"), ('\u{1F89}', "\u{1F01}\u{03B9}"),
+    ('\u{1F8A}', "\u{1F02}\u{03B9}"), ('\u{1F8B}', "\u{1F03}\u{03B9}"),
+    ('\u{1F8C}', "\u{1F04}\u{03B9}"), ('\u{1F8D}', "\u{1F05}\u{03B9}"),
+    ('\u{1F8E}', "\u{1F06}\u{03B9}"), ('\u{1F8F}', "\u{1F07}\u{03B9}"),
+    ('\u{1F90}', "\u{1F20}\u{03B9}"), ('\u{1F91}', "\u{1F21}\u{03B9}"),
+    ('\u{1F92}', "\u{1F22}\u{03B9}"), ('\u{1F93}', "\u{1F23}\u{03B9}"),
+    ('\u{1F94}', "\u{1F24}\u{03B9}"), ('\u{1F95}', "\u{1F25}\u{03B9}"),
+    ('\u{1F96}', "\u{1F26}\u{03B9}"), ('\u{1F97}', "\u{1F27}\u{03B9}"),
+    ('\u{1F98}', "\u{1F20}\u{03B9}"), ('\u{1F99}', "\u{1F21}\u{03B9}"),
+    ('\u{1F9A}', "\u{1F22}\u{03B9}"), ('\u{1F9B}', "\u{1F23}\u{03B9}"),
+    ('\u{1F9C}', "\u{1F24}\u{03B9}"), ('\u{1F9D}', "\u{1F25}\u{03B9}"),
+    ('\u{1F9E}', "\u{1F26}\u{03B9}"), ('\u{1F9F}', "\u{1F27}\u{03B9}"),
+    ('\u{1FA0}', "\u{1F60}\u{03B9}"), ('\u{1FA1}', "\u{1F61}\u{03B9}"),
+    ('\u{1FA2}', "\u{1F62}\u{03B9}"), ('\u{1FA3}', "\u{1F63}\u{03B9}"),
+    ('\u{1FA4}', "\u{1F64}\u{03B9}"), ('\u{1FA5}', "\u{1F65}\u{03B9}"),
+    ('\u{1FA6}', "\u{1F66}\u{03B9}"), ('\u{1FA7}', "\u{1F67}\u{03B9}"),
+    ('\u{1FA8}', "\u{1F60}\u{03B9}"), ('\u{1FA9}', "\u{1F61}\u{03B9}"),
+    ('\u{1FAA}', "\u{1F62}\u{03B9}"), ('\u{1FAB}', "\u{1F63}\u{03B9}"),
+    ('\u{1FAC}', "\u{1F64}\u{03B9}"), ('\u{1FAD}', "\u{1F65}\u{03B9}"),
+    ('\u{1FAE}', "\u{1F66}\u{03B9}"), ('\u{1FAF}', "\u{1F67}\u{03B9}"),
+    ('\u{1FB2}', "\u{1F70}\u{03B9}"), ('\u{1FB3}', "\u{03B1}\u{03B9}"),
+    ('\u{1FB4}', "\u{03AC}\u{03B9}"), ('\u{1FB6}', "\u{03B1}\u{0342}"),
+    ('\u{1FB7}', "\u{03B1}\u{0342}\u{03B9}"), ('\u{1FBC}', "\u{03B1}\u{03B9}"),
+    ('\u{1FBE}', "\u{03B9}"), ('\u{1FC2}', "\u{1F74}\u{03B9}"), ('\u{1FC3}', "\u{03B7}\u{03B9}"),
+    ('\u{1FC4}', "\u{03AE}\u{03B9}"), ('\u{1FC6}', "\u{03B7}\u{0342}"),
+    ('\u{1FC7}', "\u{03B7}\u{0342}\u{03B9}"), ('\u{1FCC}', "\u{03B7}\u{03B9}"),
+    ('\u{1FD2}', "\u{03B9}\u{0308}\u{0300}"), ('\u{1FD3}', "\u{03B9}\u{0308}\u{0301}"),
+    ('\u{1FD6}', "\u{03B9}\u{0342}"), ('\u{1FD7}', "\u{03B9}\u{0308}\u{0342}"),
+    ('\u{1FE2}', "\u{03C5}\u{0308}\u{0300}"), ('\u{1FE3}', "\u{03C5}\u{0308}\u{0301}"),
+    ('\u{1FE4}', "\u{03C1}\u{0313}"), ('\u{1FE6}', "\u{03C5}\u{0342}"),
+    ('\u{1FE7}', "\u{03C5}\u{0308}\u{0342}"), ('\u{1FF2}', "\u{1F7C}\u{03B9}"),
+    ('\u{1FF3}', "\u{03C9}\u{03B9}"), ('\u{1FF4}', "\u{03CE}\u{03B9}"),
+    ('\u{1FF6}', "\u{03C9}\u{0342}"), ('\u{1FF7}', "\u{03C9}\u{0342}\u{03B9}"),
+    ('\u{1FFC}', "\u{03C9}\u{03B9}"), ('\u{2126}', "\u{03C9}"), ('\u{212A}', "k"),
+    ('\u{212B}', "\u{00E5}"), ('\u{2C62}', "\u{026B}"), ('\u{2C64}', "\u{027D}"),
+    ('\u{2C6D}', "\u{0251}"), ('\u{2C6E}', "\u{0271}"), ('\u{2C6F}', "\u{0250}"),
+    ('\u{2C70}', "\u{0252}"), ('\u{2C7E}', "\u{023F}"), ('\u{2C7F}', "\u{0240}"),
+    ('\u{A78D}', "\u{0265}"), ('\u{A7AA}', "\u{0266}"), ('\u{A7AB}', "\u{025C}"),
+    ('\u{A7AC}', "\u{0261}"), ('\u{A7AD}', "\u{026C}"), ('\u{A7AE}', "\u{026A}"),
+    ('\u{A7B0}', "\u{029E}"), ('\u{A7B1}', "\u{0287}"), ('\u{A7B2}', "\u{029D}"),
+    ('\u{A7C5}', "\u{0282}"), ('\u{FB00}', "ff"), ('\u{FB01}', "fi"),
+    ('\u{FB02}', "fl"), ('\u{FB03}', "ffi"),
+    ('\u{FB04}', "ffl"), ('\u{FB05}', "st"),
+    ('\u{FB06}', "st"), ('\u{FB13}', "\u{0574}\u{0576}"),
+    ('\u{FB14}', "\u{0574}\u{0565}"), ('\u{FB15}', "\u{0574}\u{056B}"),
+    ('\u{FB16}', "\u{057E}\u{0576}"), ('\u{FB17}', "\u{0574}\u{056D}"),
+];
@@ -1,7 +1,7 @@
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
-use crate::{CaseMatching, Config, Matching, Scoring, UnicodeMatching};
+use crate::{CaseMatching, Config, Field, Matching, Scoring, UnicodeMatching};
 
 /// A single pattern to match, parsed from syntax like `!^foo`
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -11,6 +11,17 @@ pub struct Pattern {
     pub pattern: String,
     /// Haystacks matching this atom are excluded
     pub negated: bool,
+    /// Alternative to the previous pattern, forming an OR-group with it, e.g. `bar` in
+    /// `foo | bar`. Ignored on the first pattern, and in a [`crate::Query`], which uses
+    /// [`crate::Query::Or`] instead
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub or: bool,
+    /// Name of the [`crate::Field`] this pattern is matched against, e.g. `name` in
+    /// `name:foo`, compared case-insensitively. Matched against every field when `None` or
+    /// when it isn't one of the matcher's fields, and ignored when matching without fields
+    /// (see [`crate::Matcher::match_list_fields`])
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub field: Option<String>,
     /// Text to match with the syntax stripped, e.g. `foo`
     pub needle: String,
     /// Configuration for this pattern
@@ -44,6 +55,8 @@ impl Pattern {
         Self {
             pattern: needle.to_string(),
             negated: false,
+            or: false,
+            field: None,
             needle: needle.to_string(),
             config,
         }
@@ -55,6 +68,19 @@ impl Pattern {
         self
     }
 
+    /// Sets whether this pattern is an alternative to the previous pattern, forming an
+    /// OR-group with it
+    pub fn or(mut self, or: bool) -> Self {
+        self.or = or;
+        self
+    }
+
+    /// Sets the name of the field this pattern is matched against (see [`Pattern::field`])
+    pub fn field(mut self, field: Option<&str>) -> Self {
+        self.field = field.map(str::to_string);
+        self
+    }
+
     /// Overrides [`crate::Config::matching`] for this pattern (see [`PatternConfig::matching`])
     pub fn matching(mut self, matching: Option<Matching>) -> Self {
         self.config = self.config.matching(matching);
@@ -85,28 +111,49 @@ impl Pattern {
         self
     }
 
+    /// Overrides [`crate::Config::min_score`] for this pattern (see [`PatternConfig::min_score`])
+    pub fn min_score(mut self, min_score: Option<u16>) -> Self {
+        self.config = self.config.min_score(min_score);
+        self
+    }
+
     /// Parses a single query atom, where special syntax changes the matching mode:
     ///
     /// `foo` - `None` (defers to [`crate::Config::matching`])
     /// `^foo` - [`Matching::Prefix`]
     /// `foo$` - [`Matching::Suffix`]
     /// `'foo` - [`Matching::Substring`]
+    /// `'foo'` - [`Matching::Word`]
     /// `^foo$` - [`Matching::Exact`]
+    /// `=*.rs` - [`Matching::Glob`], e.g. `=src/**/mod.rs`
     /// `!foo` - negated, [`Matching::Substring`] unless combined with the syntax above
+    /// `name:foo` - matched only against the field `name` (see [`Pattern::field`]), combining
+    /// with the syntax above, e.g. `!path:^src`. Only recognized by
+    /// [`Pattern::parse_with_fields`] for one of its fields
+    /// `%fo+b[a-z]r` - `Matching::Regex`, requiring the `regex` feature, and matched as is
+    /// otherwise. Combines with negation and field prefixes, e.g. `!path:%\.rs$`, while the
+    /// regex's own `^` and `$` anchor it. Regexes which can match the empty string, e.g. `%a*`
+    /// or `%^$`, score 0 on the haystacks they match, like an empty needle
     ///
+    /// Any other `name:` prefix is part of the needle, so `TODO:fix` and `C:\Users` are
+    /// matched as is. [`crate::Matcher::check_fields`] reports the prefixes shaped like a
+    /// field name, e.g. `nmae:foo`, for callers to warn about typos.
     /// Any special character can be escaped with a backslash, e.g. `\!foo`, `\^foo`,
-    /// `foo\$` or `\'foo` match the literal leading/trailing character, and `foo\ bar`
-    /// matches the literal space.
+    /// `foo\$` or `\'foo` match the literal leading/trailing character, `foo\ bar`
+    /// matches the literal space, `\|` matches a literal `|` (see [`Pattern::parse_query`]),
+    /// `\(` and `\)` match literal parentheses (see [`crate::Query::parse`]), `foo\:bar`
+    /// matches a literal `:`, `\=foo` matches a literal `=`, and `\%foo` matches a literal
+    /// `%`. Within a glob, escaping a wildcard, e.g. `=\*.rs`, matches it literally. Within a
+    /// regex, escapes are passed through to the regex, apart from `\%` and escaped whitespace.
     pub fn parse(atom: &str) -> Self {
-        // Tokenize chars, marking whether they're esacped or not
-        let mut tokens: Vec<(char, bool)> = Vec::with_capacity(atom.len());
-        let mut chars = atom.chars();
-        while let Some(c) = chars.next() {
-            match if c == '\\' { chars.next() } else { None } {
-                Some(escaped) => tokens.push((escaped, true)),
-                None => tokens.push((c, false)),
-            }
-        }
+        Self::parse_with_fields(atom, &[])
+    }
+
+    /// Parses a single query atom (see [`Pattern::parse`]), scoping it to one of the `fields`
+    /// when prefixed with its name, e.g. `path:^src` with a field named `path`. Names are
+    /// compared case-insensitively.
+    pub fn parse_with_fields(atom: &str, fields: &[Field]) -> Self {
+        let tokens = tokenize(atom);
 
         // Pops the first/last token if it's the unescaped operator `op`
         fn strip_first(tokens: &mut &[(char, bool)], op: char) -> bool {
@@ -130,13 +177,32 @@ impl Pattern {
 
         let mut rest = tokens.as_slice();
         let negated = strip_first(&mut rest, '!');
-        let prefix = strip_first(&mut rest, '^');
-        let substring = !prefix && strip_first(&mut rest, '\'');
-        let suffix = strip_last(&mut rest, '$');
+        let field = parse_field(&mut rest, fields);
+        #[cfg(feature = "regex")]
+        if let Some(needle) = parse_regex(rest) {
+            return Self {
+                pattern: atom.to_string(),
+                negated,
+                or: false,
+                field,
+                needle,
+                config: PatternConfig::default().matching(Some(Matching::Regex)),
+            };
+        }
+        let glob = strip_first(&mut rest, '=');
+        let prefix = !glob && strip_first(&mut rest, '^');
+        let substring = !glob && !prefix && strip_first(&mut rest, '\'');
+        let suffix = !glob && strip_last(&mut rest, '$');
+        let word = substring && !suffix && strip_last(&mut rest, '\'');
 
         // Escaped special characters collapse to the literal character; the backslash is
         // kept before anything else (including another backslash)
-        let is_special = |c: char| matches!(c, '!' | '^' | '\'' | '$') || c.is_whitespace();
+        let is_special = |c: char| {
+            matches!(
+                c,
+                '!' | '^' | '\'' | '$' | '=' | '|' | '(' | ')' | ':' | '%'
+            ) || c.is_whitespace()
+        };
         let mut needle = String::with_capacity(atom.len());
         for &(c, escaped) in rest {
             if escaped && !is_special(c) {
@@ -146,6 +212,8 @@ impl Pattern {
         }
 
         let matching = match (prefix, suffix, substring) {
+            _ if glob => Some(Matching::Glob),
+            _ if word => Some(Matching::Word),
             (true, true, _) => Some(Matching::Exact),
             (true, false, _) => Some(Matching::Prefix),
             (false, true, _) => Some(Matching::Suffix),
@@ -159,6 +227,8 @@ impl Pattern {
         Self {
             pattern: atom.to_string(),
             negated,
+            or: false,
+            field,
             needle,
             config: PatternConfig::default().matching(matching),
         }
@@ -169,6 +239,11 @@ impl Pattern {
     /// Escape a literal space with a backslash, e.g. `foo\ bar` is a single atom.
     /// Atoms with an empty needle, e.g. `!` or `^$`, are dropped.
     ///
+    /// A standalone `|` joins the atoms on either side into an OR-group, which matches when any
+    /// of its atoms match, scoring by the best of them. For example, `^core go$ | rb$ | py$`
+    /// matches haystacks starting with `core` and ending with `go`, `rb` or `py`. Escape it as
+    /// `\|` to match a literal `|`.
+    ///
     /// The returned patterns carry only the [`Matching`] mode derived from the syntax. Any
     /// other per-pattern override is left as `None` and inherits the matcher's [`Config`].
     /// Set other [`PatternConfig`] fields on the results to override per-pattern.
@@ -188,38 +263,130 @@ impl Pattern {
     /// let mut matcher = Matcher::from_patterns(&patterns, &Config::default());
     /// ```
     pub fn parse_query(query: &str) -> Vec<Pattern> {
-        let mut patterns = vec![];
-        let mut start: Option<usize> = None;
-        let mut escaped = false;
+        Self::parse_query_with_fields(query, &[])
+    }
 
-        let mut push = |atom: &str| {
-            let pattern = Self::parse(atom);
+    /// Parses a query of whitespace separated atoms (see [`Pattern::parse_query`]), scoping
+    /// atoms prefixed with the name of one of the `fields` (see [`Pattern::parse_with_fields`])
+    pub fn parse_query_with_fields(query: &str, fields: &[Field]) -> Vec<Pattern> {
+        let mut patterns: Vec<Pattern> = vec![];
+        let mut or = false;
+        for atom in split_atoms(query) {
+            // A dangling `|`, at the start or end of the query, is ignored
+            if atom == "|" {
+                or = !patterns.is_empty();
+                continue;
+            }
+            let pattern = Self::parse_with_fields(atom, fields).or(or);
             if !pattern.needle.is_empty() {
                 patterns.push(pattern);
+                or = false;
             }
-        };
+        }
+        patterns
+    }
+}
 
-        for (i, c) in query.char_indices() {
-            if escaped {
-                // Previous char was an escaping backslash; keep this char in the atom
-                escaped = false;
-            } else if c == '\\' {
-                start.get_or_insert(i);
-                escaped = true;
-            } else if c.is_whitespace() {
-                if let Some(s) = start.take() {
-                    push(&query[s..i]);
-                }
-            } else if start.is_none() {
-                start = Some(i);
-            }
+/// Tokenizes the chars of an atom, marking whether they're escaped or not
+fn tokenize(atom: &str) -> Vec<(char, bool)> {
+    let mut tokens: Vec<(char, bool)> = Vec::with_capacity(atom.len());
+    let mut chars = atom.chars();
+    while let Some(c) = chars.next() {
+        match if c == '\\' { chars.next() } else { None } {
+            Some(escaped) => tokens.push((escaped, true)),
+            None => tokens.push((c, false)),
         }
-        if let Some(s) = start {
-            push(&query[s..]);
+    }
+    tokens
+}
+
+/// Returns the `name:` prefix of an atom shaped like a field name, e.g. `nmae` in `nmae:foo`,
+/// when it names none of the `fields`, so the atom was parsed as a needle (see
+/// [`crate::Matcher::check_fields`]). Prefixes followed by `:`, `/` or an escape, e.g. in
+/// `std::fmt`, `http://foo` or `C:\Users`, are left alone
+pub(crate) fn unknown_field_prefix(atom: &str, fields: &[Field]) -> Option<String> {
+    let tokens = tokenize(atom);
+    let mut rest = tokens.as_slice();
+    if let [('!', false), after @ ..] = rest {
+        rest = after;
+    }
+    let colon = rest.iter().position(|&token| token == (':', false))?;
+    let (name, after) = (&rest[..colon], &rest[colon + 1..]);
+    let is_ident =
+        |&(c, escaped): &(char, bool)| !escaped && (c.is_alphanumeric() || c == '_' || c == '-');
+    let ident = match name.first() {
+        Some(&(first, _)) => !first.is_numeric() && name.iter().all(is_ident),
+        None => false,
+    };
+    if !ident || matches!(after.first(), Some((':' | '/', _) | (_, true))) {
+        return None;
+    }
+    let name = name.iter().map(|&(c, _)| c).collect::<String>();
+    (!fields.iter().any(|field| field.is_named(&name))).then_some(name)
+}
+
+/// Pops a leading `name:` prefix naming one of the `fields` (see
+/// [`Pattern::parse_with_fields`]), returning the name
+fn parse_field(tokens: &mut &[(char, bool)], fields: &[Field]) -> Option<String> {
+    let colon = tokens.iter().position(|&token| token == (':', false))?;
+    let (name, rest) = (&tokens[..colon], &tokens[colon + 1..]);
+    if name.iter().any(|&(_, escaped)| escaped) {
+        return None;
+    }
+    let name = name.iter().map(|&(c, _)| c).collect::<String>();
+    if !fields.iter().any(|field| field.is_named(&name)) {
+        return None;
+    }
+    *tokens = rest;
+    Some(name)
+}
+
+/// Parses a `%regex` atom (see [`Pattern::parse`]), returning the regex
+#[cfg(feature = "regex")]
+fn parse_regex(tokens: &[(char, bool)]) -> Option<String> {
+    let [('%', false), body @ ..] = tokens else {
+        return None;
+    };
+    // A lone `%` is matched literally
+    if body.is_empty() {
+        return None;
+    }
+    // Escaped percent signs and whitespace collapse to the literal character, while the
+    // backslash is kept before anything else for the regex to interpret
+    let mut regex = String::with_capacity(body.len());
+    for &(c, escaped) in body {
+        if escaped && c != '%' && !c.is_whitespace() {
+            regex.push('\\');
         }
+        regex.push(c);
+    }
+    Some(regex)
+}
 
-        patterns
+/// Splits a query into its whitespace separated atoms, keeping escaped whitespace
+pub(crate) fn split_atoms(query: &str) -> Vec<&str> {
+    let mut atoms = vec![];
+    let mut start: Option<usize> = None;
+    let mut escaped = false;
+    for (i, c) in query.char_indices() {
+        if escaped {
+            // Previous char was an escaping backslash; keep this char in the atom
+            escaped = false;
+        } else if c == '\\' {
+            start.get_or_insert(i);
+            escaped = true;
+        } else if c.is_whitespace() {
+            if let Some(s) = start.take() {
+                atoms.push(&query[s..i]);
+            }
+        } else if start.is_none() {
+            start = Some(i);
+        }
+    }
+    if let Some(s) = start {
+        atoms.push(&query[s..]);
     }
+    atoms
 }
 
 /// Per-pattern overrides for the matcher's [`Config`]. Every field is optional and falls
@@ -241,16 +408,22 @@ pub struct PatternConfig {
     pub matching: Option<Matching>,
     /// Per-pattern override for [`crate::Config::scoring`]; `None` inherits it.
     pub scoring: Option<Scoring>,
+    /// Filters out the haystacks where this pattern scores below it, whatever the other
+    /// patterns score. `None` leaves filtering to [`crate::Config::min_score`], which a single
+    /// pattern inherits while multiple patterns are checked against it by their combined score.
+    pub min_score: Option<u16>,
 }
 
 impl PatternConfig {
     /// Resolves this pattern's overrides against the matcher's [`Config`], using the
-    /// matcher's value for any field left as `None`. The returned config's `sort` is
-    /// always the matcher's, as result ordering isn't a per-pattern concern.
+    /// matcher's value for any field left as `None`. The returned config's `sort` and
+    /// `field_scoring` are always the matcher's, as result ordering and combining field
+    /// scores aren't per-pattern concerns.
     pub fn resolve(&self, config: &Config) -> Config {
         Config {
             max_typos: self.max_typos.or(config.max_typos),
             casing: self.casing.unwrap_or(config.casing),
+            case_locale: config.case_locale,
             unicode: self.unicode.unwrap_or(config.unicode),
             matching: self.matching.unwrap_or(config.matching),
             scoring: self
@@ -258,6 +431,10 @@ impl PatternConfig {
                 .clone()
                 .unwrap_or_else(|| config.scoring.clone()),
             sort: config.sort,
+            field_scoring: config.field_scoring,
+            min_score: self.min_score.unwrap_or(config.min_score),
+            path_mode: config.path_mode,
+            normalization: config.normalization,
         }
     }
 
@@ -290,6 +467,12 @@ impl PatternConfig {
         self.scoring = scoring;
         self
     }
+
+    /// Sets the minimum score of a match
+    pub fn min_score(mut self, min_score: Option<u16>) -> Self {
+        self.min_score = min_score;
+        self
+    }
 }
 
 #[cfg(test)]
@@ -311,6 +494,7 @@ mod tests {
         assert_parse("foo$", "foo", Some(Matching::Suffix), false);
         assert_parse("'foo", "foo", Some(Matching::Substring), false);
         assert_parse("^foo$", "foo", Some(Matching::Exact), false);
+        assert_parse("'foo'", "foo", Some(Matching::Word), false);
     }
 
     #[test]
@@ -321,6 +505,7 @@ mod tests {
         assert_parse("!foo$", "foo", Some(Matching::Suffix), true);
         assert_parse("!'foo", "foo", Some(Matching::Substring), true);
         assert_parse("!^foo$", "foo", Some(Matching::Exact), true);
+        assert_parse("!'foo'", "foo", Some(Matching::Word), true);
     }
 
     #[test]
@@ -332,6 +517,8 @@ mod tests {
         assert_parse("foo\\ bar", "foo bar", None, false);
         assert_parse("!\\^foo", "^foo", Some(Matching::Substring), true);
         assert_parse("!\\!foo", "!foo", Some(Matching::Substring), true);
+        assert_parse("'foo\\'", "foo'", Some(Matching::Substring), false);
+        assert_parse("foo'", "foo'", None, false);
     }
 
     #[test]
@@ -344,6 +531,118 @@ mod tests {
         assert_parse("a\\\\\\ b", "a\\\\ b", None, false);
     }
 
+    fn fields() -> [Field; 3] {
+        [
+            Field::new("name"),
+            Field::new("Path"),
+            Field::new("file_name-2"),
+        ]
+    }
+
+    #[test]
+    fn parse_field_prefix() {
+        let pattern = Pattern::parse_with_fields("name:foo", &fields());
+        assert_eq!(pattern.field.as_deref(), Some("name"));
+        assert_eq!(pattern.needle, "foo");
+
+        let pattern = Pattern::parse_with_fields("!path:^src/", &fields());
+        assert_eq!(pattern.field.as_deref(), Some("path"));
+        assert_eq!(pattern.needle, "src/");
+        assert_eq!(pattern.config.matching, Some(Matching::Prefix));
+        assert!(pattern.negated);
+
+        let pattern = Pattern::parse_with_fields("file_name-2:a:b", &fields());
+        assert_eq!(pattern.field.as_deref(), Some("file_name-2"));
+        assert_eq!(pattern.needle, "a:b");
+
+        // An empty needle is dropped by `parse_query_with_fields`
+        assert_eq!(Pattern::parse_with_fields("name:", &fields()).needle, "");
+        assert!(
+            Pattern::parse_query_with_fields("name: foo", &fields())
+                .iter()
+                .all(|p| p.field.is_none())
+        );
+    }
+
+    #[test]
+    fn parse_keeps_prefixes_of_unknown_fields() {
+        for (atom, needle) in [
+            ("TODO:fix", "TODO:fix"),
+            ("rs:42", "rs:42"),
+            ("C:\\Users", "C:\\Users"),
+            ("std::fmt", "std::fmt"),
+            ("http://foo", "http://foo"),
+            (":foo", ":foo"),
+            ("^name:foo", "name:foo"),
+            ("name\\:foo", "name:foo"),
+            ("na\\me:foo", "na\\me:foo"),
+        ] {
+            let pattern = Pattern::parse_with_fields(atom, &fields());
+            assert_eq!(pattern.field, None, "atom: {atom:?}");
+            assert_eq!(pattern.needle, needle, "atom: {atom:?}");
+        }
+        // Without fields, every prefix is part of the needle
+        let pattern = Pattern::parse("name:foo");
+        assert_eq!(pattern.field, None);
+        assert_eq!(pattern.needle, "name:foo");
+        assert_parse("!path:^src", "path:^src", Some(Matching::Substring), true);
+    }
+
+    #[test]
+    fn parse_glob() {
+        assert_parse("=*.rs", "*.rs", Some(Matching::Glob), false);
+        assert_parse(
+            "!=src/**/mod.rs",
+            "src/**/mod.rs",
+            Some(Matching::Glob),
+            true,
+        );
+        // Other syntax is part of the glob, while wildcards keep their escapes
+        assert_parse("=^foo$", "^foo$", Some(Matching::Glob), false);
+        assert_parse("=\\*.rs", "\\*.rs", Some(Matching::Glob), false);
+        assert_parse("\\=foo", "=foo", None, false);
+        assert_parse("^=foo", "=foo", Some(Matching::Prefix), false);
+        let pattern = Pattern::parse_with_fields("path:=*.rs", &fields());
+        assert_eq!(pattern.field.as_deref(), Some("path"));
+        assert_eq!(pattern.needle, "*.rs");
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn parse_regex() {
+        assert_parse("%fo+b[a-z]r", "fo+b[a-z]r", Some(Matching::Regex), false);
+        assert_parse("!%^foo$", "^foo$", Some(Matching::Regex), true);
+        // Escapes are kept for the regex, apart from `%` and whitespace
+        assert_parse(
+            "%a\\.b\\%c\\ d\\\\",
+            "a\\.b%c d\\\\",
+            Some(Matching::Regex),
+            false,
+        );
+        let pattern = Pattern::parse_with_fields("path:%\\.rs$", &fields());
+        assert_eq!(pattern.field.as_deref(), Some("path"));
+        assert_eq!(pattern.needle, "\\.rs$");
+        assert_eq!(pattern.config.matching, Some(Matching::Regex));
+
+        // Slashes are plain text, so paths keep their meaning
+        assert_parse("/usr/lib/", "/usr/lib/", None, false);
+        // Not a regex without an unescaped leading `%` and a body
+        assert_parse("%", "%", None, false);
+        assert_parse("\\%foo", "%foo", None, false);
+        assert_parse("100%", "100%", None, false);
+        assert_parse("^%foo", "%foo", Some(Matching::Prefix), false);
+    }
+
+    #[test]
+    fn parse_escaped_percent_without_regex_feature() {
+        // Escaping `%` parses the same with or without the `regex` feature
+        assert_parse("\\%foo", "%foo", None, false);
+        assert_parse("50\\%", "50%", None, false);
+        assert_parse("^\\%foo", "%foo", Some(Matching::Prefix), false);
+        #[cfg(not(feature = "regex"))]
+        assert_parse("%foo", "%foo", None, false);
+    }
+
     #[test]
     fn parse_query_splits_atoms() {
         let patterns = Pattern::parse_query("foo !^bar");
@@ -374,6 +673,39 @@ mod tests {
         assert_eq!(patterns[1].needle, "bar");
     }
 
+    #[test]
+    fn parse_query_or_groups() {
+        let patterns = Pattern::parse_query("^core go$ | rb$ | py$ !test");
+        let ors = patterns.iter().map(|p| (p.needle.as_str(), p.or));
+        assert_eq!(
+            ors.collect::<Vec<_>>(),
+            vec![
+                ("core", false),
+                ("go", false),
+                ("rb", true),
+                ("py", true),
+                ("test", false)
+            ]
+        );
+
+        // Dangling pipes are ignored, and a pipe before a dropped atom carries over
+        let patterns = Pattern::parse_query("| foo | ! bar |");
+        let ors = patterns.iter().map(|p| (p.needle.as_str(), p.or));
+        assert_eq!(ors.collect::<Vec<_>>(), vec![("foo", false), ("bar", true)]);
+    }
+
+    #[test]
+    fn parse_query_escaped_pipe_is_literal() {
+        let patterns = Pattern::parse_query("foo \\| bar a|b");
+        let ors = patterns.iter().map(|p| (p.needle.as_str(), p.or));
+        assert_eq!(
+            ors.collect::<Vec<_>>(),
+            vec![("foo", false), ("|", false), ("bar", false), ("a|b", false)]
+        );
+        assert_parse("\\|", "|", None, false);
+        assert_parse("!\\|", "|", Some(Matching::Substring), true);
+    }
+
     #[test]
     fn parse_query_drops_empty_atoms() {
         assert!(Pattern::parse_query("").is_empty());
@@ -8,7 +8,10 @@ mod unicode_typos;
 pub(crate) use ascii::find_last_char_pos;
 pub(crate) use load::{can_overread, load_window};
 
-use super::{UnicodeChar, backend::Backend, case_needle, case_needle_unicode};
+use super::{
+    UnicodeChar, backend::Backend, case_needle, case_needle_unicode, typo_unit_count,
+    typo_unit_ends,
+};
 
 #[derive(Debug, Clone, Copy)]
 pub(crate) struct PathState<M> {
@@ -20,6 +23,11 @@ pub(crate) struct PathState<M> {
 pub(crate) struct Prefilter<B: Backend> {
     needle_ascii: Vec<(B::Chunk, B::Chunk)>,
     needle_unicode: Vec<UnicodeChar>,
+    /// Needle char index at which the typo unit of each needle char ends on the unicode path,
+    /// see [`typo_unit_ends`]
+    typo_unit_ends: Vec<usize>,
+    /// Number of typo units in the needle on the unicode path
+    typo_units: usize,
     paths: Vec<PathState<B::Mask>>,
 }
 
@@ -27,16 +35,20 @@ impl<B: Backend> Prefilter<B> {
     /// # Safety
     /// The backend's target features must be enabled.
     #[inline(always)]
-    pub unsafe fn new(needle: &str, case_sensitive: bool) -> Self {
+    pub unsafe fn new(needle: &str, case_sensitive: bool, graphemes: bool) -> Self {
         let needle_ascii = case_needle(needle.as_bytes(), case_sensitive)
             .into_iter()
             .map(|(c1, c2)| unsafe { (B::splat(c1), B::splat(c2)) })
             .collect();
         let needle_unicode = case_needle_unicode(needle, case_sensitive);
+        let typo_unit_ends = typo_unit_ends(needle, graphemes);
+        let typo_units = typo_unit_count(needle, &typo_unit_ends);
 
         Self {
             needle_ascii,
             needle_unicode,
+            typo_unit_ends,
+            typo_units,
             paths: Vec::new(),
         }
     }
@@ -50,4 +62,24 @@ impl<B: Backend> Prefilter<B> {
     unsafe fn unicode_needle_unchecked(&self, idx: usize) -> &UnicodeChar {
         unsafe { self.needle_unicode.get_unchecked(idx) }
     }
+
+    /// Index of the first needle char of the last `units` typo units
+    #[inline(always)]
+    fn last_typo_units_start(&self, units: usize) -> usize {
+        let mut start = self.needle_unicode.len();
+        for _ in 0..units {
+            let end = start;
+            start -= 1;
+            while start > 0 && skip_typo_unit(&self.typo_unit_ends, start - 1) == end {
+                start -= 1;
+            }
+        }
+        start
+    }
+}
+
+/// Needle index after skipping the rest of the typo unit of the needle char at `idx`
+#[inline(always)]
+fn skip_typo_unit(typo_unit_ends: &[usize], idx: usize) -> usize {
+    typo_unit_ends.get(idx).copied().unwrap_or(idx + 1)
 }
@@ -1,4 +1,4 @@
-use super::{PathState, Prefilter};
+use super::{PathState, Prefilter, skip_typo_unit};
 use crate::prefilter::backend::{Backend, BitMaskOps};
 
 impl<B: Backend> Prefilter<B> {
@@ -11,8 +11,12 @@ impl<B: Backend> Prefilter<B> {
         unsafe { self.match_haystack_unicode_many_typos_impl(haystack, max_typos as usize) }
     }
 
+    /// Every needle char is its own typo unit on the 1 and 2 typo paths, since a path can't
+    /// compare its progress with the next one when the typos skip several chars. Larger typo
+    /// units go through [`Self::match_haystack_unicode_many_typos`] instead
     #[inline(always)]
     pub unsafe fn match_haystack_unicode_1_typo(&self, haystack: &[u8]) -> (bool, usize, usize) {
+        debug_assert!(self.typo_unit_ends.is_empty());
         let len = haystack.len();
         let needle_len = self.needle_unicode.len();
         if needle_len <= 1 {
@@ -144,6 +148,7 @@ impl<B: Backend> Prefilter<B> {
 
     #[inline(always)]
     pub unsafe fn match_haystack_unicode_2_typos(&self, haystack: &[u8]) -> (bool, usize, usize) {
+        debug_assert!(self.typo_unit_ends.is_empty());
         let len = haystack.len();
         let needle_len = self.needle_unicode.len();
         if needle_len <= 2 {
@@ -346,7 +351,7 @@ impl<B: Backend> Prefilter<B> {
     ) -> (bool, usize, usize) {
         let len = haystack.len();
         let needle_len = self.needle_unicode.len();
-        if needle_len <= max_typos {
+        if self.typo_units <= max_typos {
             return (true, 0, len);
         }
         if len == 0 {
@@ -368,6 +373,7 @@ impl<B: Backend> Prefilter<B> {
 
         let paths = self.paths.as_mut_ptr();
         let needle = self.needle_unicode.as_slice();
+        let typo_unit_ends = self.typo_unit_ends.as_slice();
         let mut match_start_pos = usize::MAX;
 
         for start in (0..len).step_by(B::LANES) {
@@ -390,9 +396,9 @@ impl<B: Backend> Prefilter<B> {
                     unsafe {
                         let prev = *paths.add(path_idx - 1);
                         let path = paths.add(path_idx);
-                        let candidate_needle_idx = prev.needle_idx + 1;
+                        let candidate_needle_idx = skip_typo_unit(typo_unit_ends, prev.needle_idx);
                         if candidate_needle_idx > (*path).needle_idx {
-                            // previous path is on the second last char
+                            // previous path is on the last typo unit
                             // and since this path allows typos, we've matched
                             if candidate_needle_idx == needle_len {
                                 return self.found_with_unicode_typos(
@@ -403,7 +409,7 @@ impl<B: Backend> Prefilter<B> {
                             }
 
                             // previous path caught up to or passed this path
-                            // skip the next needle char
+                            // skip the rest of the typo unit
                             (*path).needle_idx = candidate_needle_idx;
                             (*path).needle_mask = Self::unicode_char_mask(
                                 start,
@@ -483,8 +489,7 @@ impl<B: Backend> Prefilter<B> {
     #[inline(always)]
     unsafe fn find_end_pos_with_unicode_typos(&self, haystack: &[u8], max_typos: usize) -> usize {
         let len = haystack.len();
-        let needle_len = self.needle_unicode.len();
-        let first = needle_len - 1 - max_typos;
+        let first = self.last_typo_units_start(max_typos + 1);
 
         let mut start = len.saturating_sub(B::LANES);
         loop {
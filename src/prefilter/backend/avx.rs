@@ -19,7 +19,7 @@ pub struct PrefilterAVX {
 
 impl Kernel for PrefilterAVX {
     #[inline(always)]
-    fn new(needle: &str, case_sensitive: bool) -> Self {
+    fn new(needle: &str, case_sensitive: bool, graphemes: bool) -> Self {
         let needle_cases = case_needle(needle.as_bytes(), case_sensitive);
         let needle_len = needle_cases.len();
         let mut needle_simd = needle_cases
@@ -39,7 +39,7 @@ impl Kernel for PrefilterAVX {
         }
 
         Self {
-            inner: unsafe { Prefilter::new(needle, case_sensitive) },
+            inner: unsafe { Prefilter::new(needle, case_sensitive, graphemes) },
             needle_len,
             needle_simd,
         }
@@ -4,7 +4,8 @@
 //!
 //! The ASCII prefilter proves that an ordered alignment exists after deleting
 //! at most `max_typos` needle bytes. The Unicode typo paths use UTF-8 scalar
-//! values as the typo unit, while still returning byte windows. This can still
+//! values as the typo unit, or extended grapheme clusters with
+//! `UnicodeMatching::Graphemes`, while still returning byte windows. This can still
 //! produce score-level false positives, but it cannot reject a haystack that
 //! Smith-Waterman could accept.
 //!
@@ -67,39 +68,67 @@ pub(crate) fn case_needle(needle: &[u8], case_sensitive: bool) -> Vec<(u8, u8)>
 /// Returns a vector of pairs of Unicode characters, where the first is the original and the second
 /// is the opposite case. When the case flipping results in multiple characters, the case flipped version
 /// is ignored. This happens in extremely rare cases, with by far the most common case being
-/// the German `ß` -> `SS`.
+/// the German `ß` -> `SS`. For the fuzzy and literal matching modes with
+/// [`crate::CaseMatching::Ignore`], such characters have already been replaced by their full case
+/// folding in the needle and haystack.
 pub(crate) fn case_needle_unicode(needle: &str, case_sensitive: bool) -> Vec<UnicodeChar> {
     needle
         .chars()
-        .map(|c| {
-            let len = c.len_utf8();
-            let opposite_case = (if !case_sensitive && c.is_uppercase() {
-                let mut lower = c.to_lowercase();
-                let lower_char = lower.next().unwrap_or(c);
-
-                // ignore cases where there's multiple variations
-                (lower.next().is_none() && lower_char.len_utf8() == len).then_some(lower_char)
-            } else if !case_sensitive && c.is_lowercase() {
-                let mut upper = c.to_uppercase();
-                let upper_char = upper.next().unwrap_or(c);
-
-                // ignore cases where there's multiple variations
-                (upper.next().is_none() && upper_char.len_utf8() == len).then_some(upper_char)
-            } else {
-                None
-            })
-            .unwrap_or(c);
-
-            UnicodeChar::new(c, opposite_case)
-        })
+        .map(|c| UnicodeChar::new(c, opposite_case(c, case_sensitive)))
         .collect()
 }
 
+/// Returns the opposite case of the character, or the character itself when case sensitive or
+/// when flipping the case results in multiple characters or a different UTF-8 length
+pub(crate) fn opposite_case(c: char, case_sensitive: bool) -> char {
+    let len = c.len_utf8();
+    (if !case_sensitive && c.is_uppercase() {
+        let mut lower = c.to_lowercase();
+        let lower_char = lower.next().unwrap_or(c);
+
+        // ignore cases where there's multiple variations
+        (lower.next().is_none() && lower_char.len_utf8() == len).then_some(lower_char)
+    } else if !case_sensitive && c.is_lowercase() {
+        let mut upper = c.to_uppercase();
+        let upper_char = upper.next().unwrap_or(c);
+
+        // ignore cases where there's multiple variations
+        (upper.next().is_none() && upper_char.len_utf8() == len).then_some(upper_char)
+    } else {
+        None
+    })
+    .unwrap_or(c)
+}
+
+/// Needle char index at which the typo unit of each needle char ends, so a typo skips the rest
+/// of the unit. Empty when every char is its own typo unit, which is always the case unless
+/// `graphemes` is set (see `UnicodeMatching::Graphemes`)
+pub(crate) fn typo_unit_ends(needle: &str, graphemes: bool) -> Vec<usize> {
+    #[cfg(feature = "graphemes")]
+    if graphemes {
+        let ends = crate::graphemes::char_ends(needle);
+        if ends.iter().enumerate().any(|(idx, &end)| end != idx + 1) {
+            return ends;
+        }
+    }
+    #[cfg(not(feature = "graphemes"))]
+    let _ = (needle, graphemes);
+    vec![]
+}
+
+/// Number of typo units in the needle, given their [`typo_unit_ends`]
+pub(crate) fn typo_unit_count(needle: &str, typo_unit_ends: &[usize]) -> usize {
+    match typo_unit_ends.is_empty() {
+        true => needle.chars().count(),
+        false => typo_unit_ends.chunk_by(|a, b| a == b).count(),
+    }
+}
+
 pub(crate) type Window = (bool, usize, usize);
 
 /// Ordered prefiltering kernel which allows score-level false positives.
 pub(crate) trait Kernel: Clone + std::fmt::Debug + 'static {
-    fn new(needle: &str, case_sensitive: bool) -> Self;
+    fn new(needle: &str, case_sensitive: bool, graphemes: bool) -> Self;
     fn is_available() -> bool;
 
     fn match_haystack(&self, haystack: &[u8]) -> Window;
@@ -114,8 +143,8 @@ pub(crate) trait Kernel: Clone + std::fmt::Debug + 'static {
 
 impl<B: Backend> Kernel for Prefilter<B> {
     #[inline(always)]
-    fn new(needle: &str, case_sensitive: bool) -> Self {
-        unsafe { Self::new(needle, case_sensitive) }
+    fn new(needle: &str, case_sensitive: bool, graphemes: bool) -> Self {
+        unsafe { Self::new(needle, case_sensitive, graphemes) }
     }
 
     #[inline(always)]
@@ -397,6 +426,32 @@ mod tests {
         );
     }
 
+    #[cfg(feature = "graphemes")]
+    #[test]
+    fn unicode_typo_prefilter_deletes_whole_graphemes() {
+        let typos = |needle: &str, haystack: &str, max_typos: u16, graphemes: bool| {
+            unicode_result_generic_typo_units(needle, haystack, max_typos, false, graphemes).0
+        };
+        // "👍🏽" is a thumbs up with a skin tone modifier, "🇫🇷" and "🇩🇪" are pairs of regional
+        // indicators, and "é" is an "e" with a combining acute accent
+        assert!(typos("a👍🏽b", "ab", 1, true));
+        assert!(!typos("a👍🏽b", "ab", 1, false));
+        assert!(typos("a👍🏽b", "a👍b", 1, true));
+        assert!(typos("🇫🇷🇩🇪ab", "ab", 2, true));
+        assert!(!typos("🇫🇷🇩🇪ab", "ab", 2, false));
+        assert!(!typos("🇫🇷🇩🇪ab", "b", 2, true));
+        assert!(typos("🇫🇷🇩🇪e\u{301}ab", "ab", 3, true));
+        assert!(!typos("🇫🇷🇩🇪e\u{301}ab", "ab", 3, false));
+        assert!(!typos("🇫🇷🇩🇪e\u{301}ab", "b", 3, true));
+        // A path which matched part of a grapheme doesn't hide one which skipped all of it
+        assert!(typos("👍b🏽🇫🇫👍", "🇫be🇫👍", 2, true));
+        // The window still covers the last grapheme that could match
+        assert_eq!(
+            unicode_result_generic_typo_units("ab👍🏽", "xab👍🏽x", 1, false, true),
+            (true, 1, 11)
+        );
+    }
+
     #[test]
     fn unicode_prefilter_respects_case_setting() {
         assert_eq!(unicode_result_generic("É", "é", false), (true, 0, 2));
@@ -598,10 +653,25 @@ mod tests {
         haystack: &str,
         max_typos: u16,
         case_sensitive: bool,
+    ) -> (bool, usize, usize) {
+        unicode_result_generic_typo_units(needle, haystack, max_typos, case_sensitive, false)
+    }
+
+    fn unicode_result_generic_typo_units(
+        needle: &str,
+        haystack: &str,
+        max_typos: u16,
+        case_sensitive: bool,
+        graphemes: bool,
     ) -> (bool, usize, usize) {
         let haystack = haystack.as_bytes();
-        let scalar_result =
-            kernel_result_unicode::<PrefilterScalar>(needle, haystack, max_typos, case_sensitive);
+        let scalar_result = kernel_result_unicode::<PrefilterScalar>(
+            needle,
+            haystack,
+            max_typos,
+            case_sensitive,
+            graphemes,
+        );
 
         #[cfg(target_arch = "x86_64")]
         {
@@ -613,6 +683,7 @@ mod tests {
                     haystack,
                     max_typos,
                     case_sensitive,
+                    graphemes,
                 );
                 assert_same_result(avx_result, scalar_result, "AVX2 unicode mismatch");
             }
@@ -623,6 +694,7 @@ mod tests {
                     haystack,
                     max_typos,
                     case_sensitive,
+                    graphemes,
                 );
                 assert_same_result(sse_result, scalar_result, "SSE unicode mismatch");
             }
@@ -633,6 +705,7 @@ mod tests {
                     haystack,
                     max_typos,
                     case_sensitive,
+                    graphemes,
                 );
                 assert_same_result(avx512_result, scalar_result, "AVX-512 unicode mismatch");
             }
@@ -648,6 +721,7 @@ mod tests {
                     haystack,
                     max_typos,
                     case_sensitive,
+                    graphemes,
                 );
                 assert_same_result(neon_result, scalar_result, "NEON unicode mismatch");
             }
@@ -662,7 +736,7 @@ mod tests {
         max_typos: u16,
         case_sensitive: bool,
     ) -> Window {
-        let mut prefilter = P::new(needle, case_sensitive);
+        let mut prefilter = P::new(needle, case_sensitive, false);
         match max_typos {
             0 => prefilter.match_haystack(haystack),
             1 => prefilter.match_haystack_1_typo(haystack),
@@ -676,10 +750,13 @@ mod tests {
         haystack: &[u8],
         max_typos: u16,
         case_sensitive: bool,
+        graphemes: bool,
     ) -> Window {
-        let mut prefilter = P::new(needle, case_sensitive);
+        let mut prefilter = P::new(needle, case_sensitive, graphemes);
         match max_typos {
             0 => prefilter.match_haystack_unicode(haystack),
+            // Like the matcher, grapheme typo units always go through the many typos path
+            _ if graphemes => prefilter.match_haystack_unicode_many_typos(haystack, max_typos),
             1 => prefilter.match_haystack_unicode_1_typo(haystack),
             2 => prefilter.match_haystack_unicode_2_typos(haystack),
             _ => prefilter.match_haystack_unicode_many_typos(haystack, max_typos),
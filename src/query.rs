@@ -0,0 +1,386 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::pattern::split_atoms;
+use crate::{Field, Pattern};
+
+/// A boolean query over [`Pattern`]s, parsed from syntax like `(foo | bar) !test` with
+/// [`Query::parse`] and matched with [`crate::Matcher::from_query_ast`].
+///
+/// Each [`Pattern`] keeps its own [`crate::PatternConfig`], so per-pattern overrides apply
+/// as with [`crate::Matcher::from_patterns`]. [`Pattern::or`] is ignored, in favor of
+/// [`Query::Or`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Query {
+    /// Matches when the pattern matches, or when it doesn't if [`Pattern::negated`]
+    Atom(Pattern),
+    /// Matches when the query doesn't, scoring 0
+    Not(Box<Query>),
+    /// Matches when every query matches, scoring the sum of their scores. Matches every
+    /// haystack when empty
+    And(Vec<Query>),
+    /// Matches when any query matches, scoring the best of them. Matches every haystack
+    /// when empty
+    Or(Vec<Query>),
+}
+
+impl Default for Query {
+    /// Matches every haystack
+    fn default() -> Self {
+        Query::And(vec![])
+    }
+}
+
+impl From<Pattern> for Query {
+    fn from(pattern: Pattern) -> Self {
+        Query::Atom(pattern)
+    }
+}
+
+impl Query {
+    /// Parses a query of whitespace separated atoms (see [`Pattern::parse`]), like
+    /// [`Pattern::parse_query`], with parentheses for grouping:
+    ///
+    /// `foo bar` - [`Query::And`], matching both `foo` and `bar`
+    /// `foo | bar` - [`Query::Or`], matching either `foo` or `bar`
+    /// `(foo bar)` - groups the atoms, e.g. `(foo bar) | baz`
+    /// `!(foo bar)` - [`Query::Not`], excluding haystacks matching the group
+    ///
+    /// A `|` binds tighter than whitespace, so `foo bar | baz` is `foo (bar | baz)`.
+    /// Parentheses may be attached to the atoms they enclose, e.g. `!(foo|bar baz)` is a
+    /// negated group of the atoms `foo|bar` and `baz`, while parentheses balanced within an
+    /// atom are kept, e.g. `(%(a|b) c)` is a group of the regex `%(a|b)` and `c`. Escape them as
+    /// `\(` and `\)` to match literal parentheses.
+    ///
+    /// Parsing never fails, so that incomplete queries can be matched while typing: a missing
+    /// `)` closes at the end of the query, while unmatched `)`, dangling `|`, empty groups and
+    /// atoms with an empty needle are dropped.
+    ///
+    /// ```
+    /// use frizbee::{Config, Matcher, Query};
+    ///
+    /// let query = Query::parse("(src | lib) rs$ !(test | bench)");
+    /// let mut matcher = Matcher::from_query_ast(&query, &Config::default());
+    /// let matches = matcher.match_list(&["src/main.rs", "lib/test.rs", "docs/index.md"]);
+    /// assert_eq!(matches.len(), 1);
+    /// ```
+    pub fn parse(query: &str) -> Self {
+        Self::parse_with_fields(query, &[])
+    }
+
+    /// Parses a query (see [`Query::parse`]), scoping atoms prefixed with the name of one of
+    /// the `fields` (see [`Pattern::parse_with_fields`])
+    pub fn parse_with_fields(query: &str, fields: &[Field]) -> Self {
+        let mut tokens = vec![];
+        for atom in split_atoms(query) {
+            tokenize(atom, &mut tokens);
+        }
+        let mut tokens = tokens.into_iter();
+        Self::all(parse_group(&mut tokens, fields, false))
+    }
+
+    /// Builds a query from a list of [`Pattern`]s, as matched by
+    /// [`crate::Matcher::from_patterns`], where [`Pattern::or`] joins a pattern into an
+    /// OR-group with the previous one
+    pub fn from_patterns(patterns: &[Pattern]) -> Self {
+        let groups = patterns.chunk_by(|_, next| next.or).map(|group| {
+            let mut alternatives = group
+                .iter()
+                .map(|pattern| Query::Atom(pattern.clone().or(false)))
+                .collect::<Vec<_>>();
+            match alternatives.len() {
+                1 => alternatives.pop().unwrap(),
+                _ => Query::Or(alternatives),
+            }
+        });
+        Self::all(groups.collect())
+    }
+
+    /// Returns the patterns at the leaves of the query, from left to right
+    pub fn patterns(&self) -> Vec<&Pattern> {
+        let mut patterns = vec![];
+        self.collect_patterns(&mut patterns);
+        patterns
+    }
+
+    fn collect_patterns<'a>(&'a self, patterns: &mut Vec<&'a Pattern>) {
+        match self {
+            Query::Atom(pattern) => patterns.push(pattern),
+            Query::Not(query) => query.collect_patterns(patterns),
+            Query::And(queries) | Query::Or(queries) => {
+                for query in queries {
+                    query.collect_patterns(patterns);
+                }
+            }
+        }
+    }
+
+    /// Replaces each pattern at the leaves of the query. For example, setting the max typos
+    /// based on needle length:
+    ///
+    /// ```
+    /// use frizbee::{Config, Matcher, Query};
+    ///
+    /// let query = Query::parse("(foo | longerneedle) !bar").map_patterns(|pattern| {
+    ///     let max_typos = (pattern.needle.len() / 4) as u16;
+    ///     pattern.max_typos(Some(max_typos))
+    /// });
+    /// let mut matcher = Matcher::from_query_ast(&query, &Config::default());
+    /// ```
+    pub fn map_patterns(self, mut f: impl FnMut(Pattern) -> Pattern) -> Self {
+        self.map_patterns_with(&mut f)
+    }
+
+    fn map_patterns_with(self, f: &mut impl FnMut(Pattern) -> Pattern) -> Self {
+        match self {
+            Query::Atom(pattern) => Query::Atom(f(pattern)),
+            Query::Not(query) => Query::Not(Box::new(query.map_patterns_with(f))),
+            Query::And(queries) => Query::And(
+                queries
+                    .into_iter()
+                    .map(|q| q.map_patterns_with(f))
+                    .collect(),
+            ),
+            Query::Or(queries) => Query::Or(
+                queries
+                    .into_iter()
+                    .map(|q| q.map_patterns_with(f))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Joins the queries with [`Query::And`], unwrapping a single query
+    fn all(mut queries: Vec<Query>) -> Self {
+        match queries.len() {
+            1 => queries.pop().unwrap(),
+            _ => Query::And(queries),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Token<'a> {
+    /// `(`, or `!(` when negated
+    Open {
+        negated: bool,
+    },
+    Close,
+    Or,
+    Atom(&'a str),
+}
+
+/// Splits a whitespace separated atom into its leading `(` and `!(`, trailing unbalanced `)`
+/// and the atom in between, if any
+fn tokenize<'a>(mut atom: &'a str, tokens: &mut Vec<Token<'a>>) {
+    loop {
+        if let Some(rest) = atom.strip_prefix("!(") {
+            tokens.push(Token::Open { negated: true });
+            atom = rest;
+        } else if let Some(rest) = atom.strip_prefix('(') {
+            tokens.push(Token::Open { negated: false });
+            atom = rest;
+        } else {
+            break;
+        }
+    }
+
+    // Only the `)` left unbalanced by the atom close groups, so that e.g. the regex `%(a|b)`
+    // keeps its own. A paren preceded by an odd number of backslashes is escaped
+    let mut unbalanced = 0isize;
+    let mut escaped = false;
+    for c in atom.chars() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '(' => unbalanced -= 1,
+            ')' => unbalanced += 1,
+            _ => {}
+        }
+    }
+    let mut closes = 0;
+    while unbalanced > 0
+        && let Some(rest) = atom.strip_suffix(')')
+    {
+        let backslashes = rest.len() - rest.trim_end_matches('\\').len();
+        if backslashes % 2 == 1 {
+            break;
+        }
+        atom = rest;
+        closes += 1;
+        unbalanced -= 1;
+    }
+
+    match atom {
+        "" => {}
+        "|" => tokens.push(Token::Or),
+        atom => tokens.push(Token::Atom(atom)),
+    }
+    tokens.extend((0..closes).map(|_| Token::Close));
+}
+
+/// Parses the queries of a group until its closing `)`, or the end of the query
+fn parse_group<'a>(
+    tokens: &mut impl Iterator<Item = Token<'a>>,
+    fields: &[Field],
+    nested: bool,
+) -> Vec<Query> {
+    let mut queries: Vec<Query> = vec![];
+    let mut or = false;
+    while let Some(token) = tokens.next() {
+        let query = match token {
+            Token::Close if nested => break,
+            // Unmatched `)` at the top level
+            Token::Close => continue,
+            Token::Or => {
+                or = !queries.is_empty();
+                continue;
+            }
+            Token::Open { negated } => {
+                let group = parse_group(tokens, fields, true);
+                if group.is_empty() {
+                    continue;
+                }
+                let group = Query::all(group);
+                match negated {
+                    true => Query::Not(Box::new(group)),
+                    false => group,
+                }
+            }
+            Token::Atom(atom) => {
+                let pattern = Pattern::parse_with_fields(atom, fields);
+                if pattern.needle.is_empty() {
+                    continue;
+                }
+                Query::Atom(pattern)
+            }
+        };
+
+        match queries.last_mut() {
+            Some(Query::Or(alternatives)) if or => alternatives.push(query),
+            Some(previous) if or => {
+                let previous = std::mem::take(previous);
+                *queries.last_mut().unwrap() = Query::Or(vec![previous, query]);
+            }
+            _ => queries.push(query),
+        }
+        or = false;
+    }
+    queries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn atom(atom: &str) -> Query {
+        Query::Atom(Pattern::parse(atom))
+    }
+
+    fn not(query: Query) -> Query {
+        Query::Not(Box::new(query))
+    }
+
+    #[test]
+    fn parse_matches_parse_query_without_parens() {
+        for query in [
+            "",
+            "foo",
+            "foo !^bar",
+            "^core go$ | rb$ | py$ !test",
+            "| a | ! b |",
+        ] {
+            assert_eq!(
+                Query::parse(query),
+                Query::from_patterns(&Pattern::parse_query(query)),
+                "query: {query:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn parse_groups() {
+        assert_eq!(
+            Query::parse("(foo | bar) !test"),
+            Query::And(vec![
+                Query::Or(vec![atom("foo"), atom("bar")]),
+                atom("!test")
+            ])
+        );
+        assert_eq!(
+            Query::parse("foo | (bar baz)"),
+            Query::Or(vec![
+                atom("foo"),
+                Query::And(vec![atom("bar"), atom("baz")])
+            ])
+        );
+        assert_eq!(
+            Query::parse("!(foo|bar baz$) qux"),
+            Query::And(vec![
+                not(Query::And(vec![atom("foo|bar"), atom("baz$")])),
+                atom("qux")
+            ])
+        );
+        assert_eq!(
+            Query::parse("((foo)) ( bar )"),
+            Query::And(vec![atom("foo"), atom("bar")])
+        );
+    }
+
+    #[test]
+    fn parse_is_lenient() {
+        // Missing `)` closes at the end, unmatched `)` and empty groups are dropped
+        assert_eq!(
+            Query::parse("(foo | bar"),
+            Query::Or(vec![atom("foo"), atom("bar")])
+        );
+        assert_eq!(
+            Query::parse("foo) () !() | bar"),
+            Query::Or(vec![atom("foo"), atom("bar")])
+        );
+        assert_eq!(Query::parse("( ) !"), Query::default());
+    }
+
+    #[test]
+    fn parse_escaped_parens_are_literal() {
+        let query = Query::parse("\\(foo bar\\) baz\\\\)");
+        let needles = query
+            .patterns()
+            .iter()
+            .map(|p| p.needle.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(needles, vec!["(foo", "bar)", "baz\\\\"]);
+    }
+
+    #[test]
+    fn parse_keeps_parens_balanced_within_atoms() {
+        assert_eq!(Query::parse("%(a|b)"), atom("%(a|b)"));
+        assert_eq!(
+            Query::parse("(%(a|b) foo)"),
+            Query::And(vec![atom("%(a|b)"), atom("foo")])
+        );
+        assert_eq!(
+            Query::parse("(%(a|b)) | (foo(bar))"),
+            Query::Or(vec![atom("%(a|b)"), atom("foo(bar)")])
+        );
+        assert_eq!(
+            Query::parse("!(%a\\) foo)"),
+            not(Query::And(vec![atom("%a\\)"), atom("foo")]))
+        );
+    }
+
+    #[test]
+    fn map_patterns_visits_every_leaf() {
+        let query = Query::parse("(foo | !bar) !(baz qux)").map_patterns(|p| p.max_typos(Some(0)));
+        let patterns = query.patterns();
+        assert_eq!(
+            patterns
+                .iter()
+                .map(|p| p.needle.as_str())
+                .collect::<Vec<_>>(),
+            vec!["foo", "bar", "baz", "qux"]
+        );
+        assert!(patterns.iter().all(|p| p.config.max_typos == Some(0)));
+    }
+}
@@ -1,4 +1,7 @@
-use super::MAX_HAYSTACK_LEN;
+use super::{
+    MAX_HAYSTACK_LEN, ascii_delimiter_mask, chunk_delimiters, chunk_prefix_bonus,
+    consecutive_match_bonus,
+};
 use crate::smith_waterman::{
     SmithWaterman,
     backend::{Backend, BytesVec, MaskVec, ScoreVec},
@@ -7,14 +10,15 @@ use crate::smith_waterman::{
 
 impl<B: Backend> SmithWaterman<B> {
     #[inline(always)]
-    pub(crate) fn score_haystack(&mut self, haystack: &[u8], include_prefix: bool) -> u16 {
+    pub(crate) fn score_haystack(&mut self, haystack: &[u8], haystack_start_pos: usize) -> u16 {
         if haystack.len() > MAX_HAYSTACK_LEN {
             return match_greedy(
                 self.needle.as_bytes(),
                 haystack,
                 &self.scoring,
+                self.path_mode.as_ref(),
                 self.case_sensitive,
-                include_prefix,
+                haystack_start_pos,
             )
             .map(|(score, _)| score)
             .unwrap_or(0);
@@ -29,6 +33,7 @@ impl<B: Backend> SmithWaterman<B> {
         // needed between calls
         let score_matrix = &mut self.score_matrix;
         let match_masks = &mut self.match_masks;
+        let consecutive_run_bonuses = &mut self.consecutive_run_bonuses;
 
         unsafe {
             // Constants
@@ -43,16 +48,35 @@ impl<B: Backend> SmithWaterman<B> {
             let mismatch_penalty = B::Score::splat(scoring.mismatch_penalty);
             let matching_case_bonus = B::Score::splat(scoring.matching_case_bonus);
             let capitalization_bonus = B::Score::splat(scoring.capitalization_bonus);
-            let delimiter_bonus = B::Score::splat(scoring.delimiter_bonus);
+            // In path mode, path separators earn the full delimiter bonus and other delimiters half
+            let (delimiter_bonus, separator_bonus) =
+                scoring.delimiter_bonuses(self.path_mode.as_ref());
+            let delimiter_bonus = B::Score::splat(delimiter_bonus);
+            let separator_bonus = B::Score::splat(separator_bonus);
+            let (separator, alt_separator) = self
+                .path_mode
+                .as_ref()
+                .map_or((b'/', b'/'), |path| path.separators());
+            let separator = B::Bytes::splat(separator);
+            let alt_separator = B::Bytes::splat(alt_separator);
+            let consecutive_bonus = B::Score::splat(scoring.consecutive_bonus);
+
+            if scoring.consecutive_bonus > 0 {
+                for run_bonus in consecutive_run_bonuses
+                    .iter_mut()
+                    .take(self.needle_simd.len() + 1)
+                {
+                    *run_bonus = B::Score::zero();
+                }
+            }
 
             // State
-            // TODO: have prefix bonus scale based on distance
-            let mut prefix_bonus_masked = if include_prefix {
+            let mut prefix_bonus_masked = if haystack_start_pos == 0 {
                 B::Score::first_lane(scoring.prefix_bonus)
             } else {
                 B::Score::zero()
             };
-            let mut prev_chunk_char_is_delimiter_mask = B::Mask::zero();
+            let mut prev_chunk_delimiter_bonus = B::Score::zero();
             let mut prev_chunk_is_lower_mask = B::Mask::zero();
             let mut max_scores = B::Score::zero();
 
@@ -77,30 +101,30 @@ impl<B: Backend> SmithWaterman<B> {
                 let capitalization_bonus_masked = capitalization_mask.and(capitalization_bonus);
                 prev_chunk_is_lower_mask = is_lower_mask;
 
-                // Bonus for matching after a delimiter character. We consider
-                // anything that isn't a digit or a letter and is within ASCII
-                // range to be a delimiter.
-                let is_digit_mask = haystack_chunk
-                    .gt(B::Bytes::splat(b'0' - 1))
-                    .and(haystack_chunk.lt(B::Bytes::splat(b'9' + 1)));
-                let char_is_delimiter_mask = is_letter_mask
-                    .or(is_digit_mask)
-                    .or(haystack_chunk.gt(B::Bytes::splat(127)))
-                    .not();
-                let prev_char_is_delimiter_mask =
-                    char_is_delimiter_mask.shift_right_padded_1(prev_chunk_char_is_delimiter_mask);
-                let delimiter_mask =
-                    B::widen_mask(prev_char_is_delimiter_mask.and(char_is_delimiter_mask.not()));
-                let delimiter_bonus_masked = delimiter_mask.and(delimiter_bonus);
-                prev_chunk_char_is_delimiter_mask = char_is_delimiter_mask;
+                // Bonus for matching a non-delimiter after a delimiter character
+                let (char_is_delimiter_mask, char_delimiter_bonus) = chunk_delimiters::<B>(
+                    haystack_chunk,
+                    || ascii_delimiter_mask::<B>(haystack_chunk, is_letter_mask),
+                    scoring,
+                    (delimiter_bonus, separator_bonus),
+                    (separator, alt_separator),
+                );
+                let delimiter_bonus_masked = B::widen_mask(char_is_delimiter_mask.not())
+                    .and(char_delimiter_bonus.shift_right_padded::<1>(prev_chunk_delimiter_bonus));
+                prev_chunk_delimiter_bonus = char_delimiter_bonus;
 
                 // Match-conditional bonuses (delimiter, capitalization, prefix)
-                let match_and_masked_bonuses = delimiter_bonus_masked
-                    .add(capitalization_bonus_masked)
-                    .add(prefix_bonus_masked)
-                    .add(match_score);
+                let char_bonus = delimiter_bonus_masked.add(capitalization_bonus_masked);
+                let match_and_masked_bonuses = char_bonus.add(prefix_bonus_masked).add(match_score);
+                // Only the first needle char earns the decaying prefix bonus
+                let first_row_bonuses = match_and_masked_bonuses.add(chunk_prefix_bonus::<B>(
+                    scoring,
+                    haystack_start_pos + (col_idx - 1) * B::LANES,
+                ));
 
                 let mut up_gap_mask = B::Score::zero();
+                let mut prev_row_run_bonus = B::Score::zero();
+                let mut prev_chunk_prev_row_run_bonus = B::Score::zero();
                 let mut prev_row_scores = B::Score::zero();
                 let mut row_scores = B::Score::zero();
 
@@ -114,12 +138,38 @@ impl<B: Backend> SmithWaterman<B> {
                         B::widen_mask(exact_case_match_mask.or(flipped_case_match_mask));
                     let exact_case_match_mask = B::widen_mask(exact_case_match_mask);
 
+                    // Bonus for continuing a run of consecutive matches
+                    let consecutive_bonus_masked = if scoring.consecutive_bonus > 0 {
+                        let (extra_bonus, run_bonus) = consecutive_match_bonus::<B>(
+                            match_mask,
+                            char_bonus,
+                            consecutive_bonus,
+                            (up_gap_mask, match_masks.get(row_idx - 1, col_idx - 1)),
+                            (prev_row_run_bonus, prev_chunk_prev_row_run_bonus),
+                            1,
+                        );
+                        prev_chunk_prev_row_run_bonus = std::mem::replace(
+                            consecutive_run_bonuses.get_unchecked_mut(row_idx),
+                            run_bonus,
+                        );
+                        prev_row_run_bonus = run_bonus;
+                        extra_bonus
+                    } else {
+                        B::Score::zero()
+                    };
+
                     // Diagonal - typical match/mismatch, advancing one cell
                     let diag_scores = {
                         let diag = prev_row_scores
                             .shift_right_padded::<1>(score_matrix.get(row_idx - 1, col_idx - 1));
                         // Add bonuses for matches
-                        let diag = diag.add(match_mask.and(match_and_masked_bonuses));
+                        let bonuses = match row_idx {
+                            1 => first_row_bonuses,
+                            _ => match_and_masked_bonuses,
+                        };
+                        let diag = diag
+                            .add(match_mask.and(bonuses))
+                            .add(consecutive_bonus_masked);
                         // Always pay the mismatch penalty
                         let diag = diag.subs(mismatch_penalty);
                         // Reward matching the needle's exact case
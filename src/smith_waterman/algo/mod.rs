@@ -1,13 +1,13 @@
 use crate::smith_waterman::Kernel;
 use crate::{
-    Scoring,
-    prefilter::{case_needle, case_needle_unicode},
+    PathMode, Scoring,
+    prefilter::{case_needle, case_needle_unicode, typo_unit_ends},
     smith_waterman::greedy::match_greedy,
 };
 
 use super::SmithWaterman;
 use super::alignment_iter::Alignment;
-use super::backend::{Backend, BytesVec, ScoreVec};
+use super::backend::{Backend, BytesVec, MaskVec, ScoreVec};
 use super::matrix::Matrix;
 
 mod ascii;
@@ -17,8 +17,133 @@ pub(crate) mod unicode_gap;
 
 pub(crate) const MAX_HAYSTACK_LEN: usize = 1024;
 
+/// Decaying prefix bonus (see [`Scoring::prefix_bonus_decay`]) of the first needle char at each
+/// byte of the chunk starting at byte `chunk_pos` of the haystack. Excludes the start of the
+/// haystack, where every needle char earns the full prefix bonus
+#[inline(always)]
+unsafe fn chunk_prefix_bonus<B: Backend>(scoring: &Scoring, chunk_pos: usize) -> B::Score {
+    unsafe {
+        let mut bonus = B::Score::zero();
+        // The bonus only decreases, so there's nothing to do past the first byte without it
+        if scoring.prefix_bonus_at(chunk_pos.max(1)) == 0 {
+            return bonus;
+        }
+        for pos in (chunk_pos..chunk_pos + B::LANES).rev() {
+            let pos_bonus = match pos {
+                0 => 0,
+                pos => scoring.prefix_bonus_at(pos),
+            };
+            bonus = bonus.shift_right_padded::<1>(B::Score::splat(pos_bonus));
+        }
+        bonus
+    }
+}
+
+/// Mask of the delimiter bytes in the chunk, and the bonus each of them gives to the following
+/// byte. By default, the delimiters are given by `default_delimiter_mask`, with path separators
+/// earning `separator_bonus` on top in path mode. Otherwise, the delimiters are the bytes of
+/// [`Scoring::delimiters`]
+#[inline(always)]
+unsafe fn chunk_delimiters<B: Backend>(
+    haystack_chunk: B::Bytes,
+    default_delimiter_mask: impl FnOnce() -> B::Mask,
+    scoring: &Scoring,
+    (delimiter_bonus, separator_bonus): (B::Score, B::Score),
+    (separator, alt_separator): (B::Bytes, B::Bytes),
+) -> (B::Mask, B::Score) {
+    unsafe {
+        let Some(delimiters) = &scoring.delimiters else {
+            let char_is_delimiter_mask = default_delimiter_mask();
+            let char_is_separator_mask = haystack_chunk
+                .eq(separator)
+                .or(haystack_chunk.eq(alt_separator));
+            let bonus = B::widen_mask(char_is_delimiter_mask)
+                .and(delimiter_bonus)
+                .add(B::widen_mask(char_is_separator_mask).and(separator_bonus));
+            return (char_is_delimiter_mask, bonus);
+        };
+
+        // Delimiters listed more than once earn their largest bonus
+        delimiters.iter().fold(
+            (B::Mask::zero(), B::Score::zero()),
+            |(mask, bonus), &(delimiter, delimiter_bonus)| {
+                let char_is_delimiter_mask = haystack_chunk.eq(B::Bytes::splat(delimiter));
+                let delimiter_bonus =
+                    B::widen_mask(char_is_delimiter_mask).and(B::Score::splat(delimiter_bonus));
+                (mask.or(char_is_delimiter_mask), bonus.max(delimiter_bonus))
+            },
+        )
+    }
+}
+
+/// Mask of the ASCII delimiter bytes in the chunk: anything that isn't a digit or a letter and
+/// is within ASCII range
+#[inline(always)]
+unsafe fn ascii_delimiter_mask<B: Backend>(
+    haystack_chunk: B::Bytes,
+    is_letter_mask: B::Mask,
+) -> B::Mask {
+    unsafe {
+        let is_digit_mask = haystack_chunk
+            .gt(B::Bytes::splat(b'0' - 1))
+            .and(haystack_chunk.lt(B::Bytes::splat(b'9' + 1)));
+        is_letter_mask
+            .or(is_digit_mask)
+            .or(haystack_chunk.gt(B::Bytes::splat(127)))
+            .not()
+    }
+}
+
+/// Extra bonus of the matches in a row continuing a run of consecutive matches (see
+/// [`Scoring::consecutive_bonus`]), alongside the run bonus at each lane of the row. A match
+/// continues a run when the previous row matched the previous char, `prev_char_len` bytes
+/// earlier. The run bonus is the largest char bonus since the start of the run, so it's the
+/// lane's own bonus when it doesn't continue a run
+#[inline(always)]
+unsafe fn consecutive_match_bonus<B: Backend>(
+    match_mask: B::Score,
+    char_bonus: B::Score,
+    consecutive_bonus: B::Score,
+    (prev_row_match_mask, prev_chunk_prev_row_match_mask): (B::Score, B::Score),
+    (prev_row_run_bonus, prev_chunk_prev_row_run_bonus): (B::Score, B::Score),
+    prev_char_len: usize,
+) -> (B::Score, B::Score) {
+    unsafe {
+        let (diag_match_mask, diag_run_bonus) = match prev_char_len {
+            1 => (
+                prev_row_match_mask.shift_right_padded::<1>(prev_chunk_prev_row_match_mask),
+                prev_row_run_bonus.shift_right_padded::<1>(prev_chunk_prev_row_run_bonus),
+            ),
+            2 => (
+                prev_row_match_mask.shift_right_padded::<2>(prev_chunk_prev_row_match_mask),
+                prev_row_run_bonus.shift_right_padded::<2>(prev_chunk_prev_row_run_bonus),
+            ),
+            3 => (
+                prev_row_match_mask.shift_right_padded::<3>(prev_chunk_prev_row_match_mask),
+                prev_row_run_bonus.shift_right_padded::<3>(prev_chunk_prev_row_run_bonus),
+            ),
+            _ => (
+                prev_row_match_mask.shift_right_padded::<4>(prev_chunk_prev_row_match_mask),
+                prev_row_run_bonus.shift_right_padded::<4>(prev_chunk_prev_row_run_bonus),
+            ),
+        };
+        let consecutive_mask = match_mask.and(diag_match_mask);
+        let inherited_bonus = consecutive_mask.and(diag_run_bonus);
+        // The char already earns its own bonus, so only add the difference to the largest one
+        let extra_bonus =
+            consecutive_mask.and(consecutive_bonus.max(inherited_bonus).subs(char_bonus));
+        (extra_bonus, char_bonus.max(inherited_bonus))
+    }
+}
+
 impl<B: Backend> Kernel for SmithWaterman<B> {
-    fn new(needle: &str, scoring: &Scoring, case_sensitive: bool) -> Self {
+    fn new(
+        needle: &str,
+        scoring: &Scoring,
+        case_sensitive: bool,
+        path_mode: Option<PathMode>,
+        graphemes: bool,
+    ) -> Self {
         let needle_simd = case_needle(needle.as_bytes(), case_sensitive)
             .iter()
             .map(|(c1, c2)| unsafe { (B::Bytes::splat(*c1), B::Bytes::splat(*c2)) })
@@ -28,15 +153,22 @@ impl<B: Backend> Kernel for SmithWaterman<B> {
         let unicode_pending_gap_open_masks = (0..(needle_unicode.len() + 1))
             .map(|_| unsafe { B::Score::zero() })
             .collect();
+        let consecutive_run_bonuses = (0..(needle_len + 1))
+            .map(|_| unsafe { B::Score::zero() })
+            .collect();
         Self {
             needle: needle.to_string(),
             needle_simd,
             needle_unicode,
+            typo_unit_ends: typo_unit_ends(needle, graphemes),
             case_sensitive,
             scoring: scoring.clone(),
+            path_mode,
             score_matrix: Matrix::new(needle_len, MAX_HAYSTACK_LEN),
             match_masks: Matrix::new(needle_len, MAX_HAYSTACK_LEN),
             unicode_pending_gap_open_masks,
+            consecutive_run_bonuses,
+            haystack_char_classes: vec![],
             haystack_chunks: 0,
         }
     }
@@ -57,8 +189,9 @@ impl<B: Backend> Kernel for SmithWaterman<B> {
                 self.needle.as_bytes(),
                 haystack,
                 &self.scoring,
+                self.path_mode.as_ref(),
                 self.case_sensitive,
-                haystack_start_pos == 0,
+                haystack_start_pos,
             )
             .map(|(score, mut indices)| {
                 for index in &mut indices {
@@ -71,7 +204,7 @@ impl<B: Backend> Kernel for SmithWaterman<B> {
             .unwrap_or_else(|| (0, vec![]));
         }
 
-        let score = self.score_haystack(haystack, haystack_start_pos == 0);
+        let score = self.score_haystack(haystack, haystack_start_pos);
         if score == 0 {
             return (score, Vec::new());
         }
@@ -105,8 +238,9 @@ impl<B: Backend> Kernel for SmithWaterman<B> {
                 self.needle.as_bytes(),
                 haystack,
                 &self.scoring,
+                self.path_mode.as_ref(),
                 self.case_sensitive,
-                haystack_start_pos == 0,
+                haystack_start_pos,
             )
             .map(|(score, mut indices)| {
                 for index in &mut indices {
@@ -119,7 +253,7 @@ impl<B: Backend> Kernel for SmithWaterman<B> {
             .unwrap_or_else(|| (0, vec![]));
         }
 
-        let score = self.score_haystack_unicode(haystack, haystack_start_pos == 0);
+        let score = self.score_haystack_unicode(haystack, haystack_start_pos);
         if score == 0 {
             return (score, Vec::new());
         }
@@ -154,12 +288,12 @@ impl<B: Backend> Kernel for SmithWaterman<B> {
 
     #[inline(always)]
     fn score_haystack(&mut self, haystack: &[u8], haystack_start_pos: usize) -> u16 {
-        SmithWaterman::score_haystack(self, haystack, haystack_start_pos == 0)
+        SmithWaterman::score_haystack(self, haystack, haystack_start_pos)
     }
 
     #[inline(always)]
     fn score_haystack_unicode(&mut self, haystack: &[u8], haystack_start_pos: usize) -> u16 {
-        SmithWaterman::score_haystack_unicode(self, haystack, haystack_start_pos == 0)
+        SmithWaterman::score_haystack_unicode(self, haystack, haystack_start_pos)
     }
 
     #[cfg(feature = "match_end_col")]
@@ -169,8 +303,9 @@ impl<B: Backend> Kernel for SmithWaterman<B> {
                 self.needle.as_bytes(),
                 haystack,
                 &self.scoring,
+                self.path_mode.as_ref(),
                 self.case_sensitive,
-                true,
+                0,
             )
             .and_then(|(_, indices)| indices.last().copied())
             .unwrap_or(0)
@@ -1,4 +1,8 @@
-use super::MAX_HAYSTACK_LEN;
+use super::{
+    MAX_HAYSTACK_LEN, ascii_delimiter_mask, chunk_delimiters, chunk_prefix_bonus,
+    consecutive_match_bonus,
+};
+use crate::char_class::{CharClass, classify_haystack};
 use crate::smith_waterman::{
     SmithWaterman,
     backend::{Backend, BytesVec, MaskVec, ScoreVec},
@@ -7,14 +11,19 @@ use crate::smith_waterman::{
 
 impl<B: Backend> SmithWaterman<B> {
     #[inline(always)]
-    pub(crate) fn score_haystack_unicode(&mut self, haystack: &[u8], include_prefix: bool) -> u16 {
+    pub(crate) fn score_haystack_unicode(
+        &mut self,
+        haystack: &[u8],
+        haystack_start_pos: usize,
+    ) -> u16 {
         if haystack.len() > MAX_HAYSTACK_LEN {
             return match_greedy(
                 self.needle.as_bytes(),
                 haystack,
                 &self.scoring,
+                self.path_mode.as_ref(),
                 self.case_sensitive,
-                include_prefix,
+                haystack_start_pos,
             )
             .map(|(score, _)| score)
             .unwrap_or(0);
@@ -33,7 +42,17 @@ impl<B: Backend> SmithWaterman<B> {
         // needed between calls
         let score_matrix = &mut self.score_matrix;
         let match_masks = &mut self.match_masks;
+        let consecutive_run_bonuses = &mut self.consecutive_run_bonuses;
         let unicode_pending_gap_open_masks = &mut self.unicode_pending_gap_open_masks;
+        // Non-ASCII haystacks classify each char by its general category for the capitalization
+        // and delimiter bonuses, while ASCII haystacks classify their bytes directly
+        let haystack_char_classes = match haystack.is_ascii() {
+            true => None,
+            false => {
+                classify_haystack(haystack, &mut self.haystack_char_classes);
+                Some(self.haystack_char_classes.as_slice())
+            }
+        };
 
         unsafe {
             // Constants
@@ -48,7 +67,18 @@ impl<B: Backend> SmithWaterman<B> {
             let mismatch_penalty = B::Score::splat(scoring.mismatch_penalty);
             let matching_case_bonus = B::Score::splat(scoring.matching_case_bonus);
             let capitalization_bonus = B::Score::splat(scoring.capitalization_bonus);
-            let delimiter_bonus = B::Score::splat(scoring.delimiter_bonus);
+            // In path mode, path separators earn the full delimiter bonus and other delimiters half
+            let (delimiter_bonus, separator_bonus) =
+                scoring.delimiter_bonuses(self.path_mode.as_ref());
+            let delimiter_bonus = B::Score::splat(delimiter_bonus);
+            let separator_bonus = B::Score::splat(separator_bonus);
+            let (separator, alt_separator) = self
+                .path_mode
+                .as_ref()
+                .map_or((b'/', b'/'), |path| path.separators());
+            let separator = B::Bytes::splat(separator);
+            let alt_separator = B::Bytes::splat(alt_separator);
+            let consecutive_bonus = B::Score::splat(scoring.consecutive_bonus);
 
             let final_row_idx = self.needle_unicode.len();
             let mut max_scores = B::Score::zero();
@@ -59,19 +89,23 @@ impl<B: Backend> SmithWaterman<B> {
             {
                 *pending_gap_open_mask = B::Score::zero();
             }
+            if scoring.consecutive_bonus > 0 {
+                for run_bonus in consecutive_run_bonuses.iter_mut().take(final_row_idx + 1) {
+                    *run_bonus = B::Score::zero();
+                }
+            }
 
             // Like the ASCII scorer, Unicode scoring is haystack-major: each
             // chunk computes its haystack-side bonuses once, then walks the
             // needle rows, UTF-8 continuation bytes are transport lanes so a
             // scalar's score is not penalized by its byte length
             // State
-            // TODO: have prefix bonus scale based on distance
-            let mut prefix_bonus_masked = if include_prefix {
+            let mut prefix_bonus_masked = if haystack_start_pos == 0 {
                 B::Score::first_lane(scoring.prefix_bonus)
             } else {
                 B::Score::zero()
             };
-            let mut prev_chunk_char_is_delimiter_mask = B::Mask::zero();
+            let mut prev_chunk_delimiter_bonus = B::Score::zero();
             let mut prev_chunk_is_lower_mask = B::Mask::zero();
             let mut prev_chunk_continuation_gap_extend_penalty = B::Score::zero();
             let mut prev_chunk_scalar_start_mask = B::Score::zero();
@@ -91,47 +125,67 @@ impl<B: Backend> SmithWaterman<B> {
                 let continuation_gap_extend_penalty =
                     B::widen_mask(continuation_mask).and(gap_extend_penalty);
 
-                // Bonus for matching a capital letter after a lowercase ASCII letter
-                let is_upper_mask = haystack_chunk
-                    .lt(B::Bytes::splat(b'Z' + 1))
-                    .and(haystack_chunk.gt(B::Bytes::splat(b'A' - 1)));
-                let is_lower_mask = haystack_chunk
-                    .lt(B::Bytes::splat(b'z' + 1))
-                    .and(haystack_chunk.gt(B::Bytes::splat(b'a' - 1)));
-                let is_letter_mask = is_upper_mask.or(is_lower_mask);
+                let char_classes = haystack_char_classes.map(|classes| {
+                    B::Bytes::load_partial(classes.as_ptr(), chunk_start, haystack.len())
+                });
+                let char_class_mask =
+                    |classes: B::Bytes, class: CharClass| classes.eq(B::Bytes::splat(class as u8));
+
+                // Bonus for matching a capital letter after a lowercase letter
+                let (is_upper_mask, is_lower_mask) = match char_classes {
+                    Some(classes) => (
+                        char_class_mask(classes, CharClass::Upper),
+                        char_class_mask(classes, CharClass::Lower),
+                    ),
+                    None => (
+                        haystack_chunk
+                            .lt(B::Bytes::splat(b'Z' + 1))
+                            .and(haystack_chunk.gt(B::Bytes::splat(b'A' - 1))),
+                        haystack_chunk
+                            .lt(B::Bytes::splat(b'z' + 1))
+                            .and(haystack_chunk.gt(B::Bytes::splat(b'a' - 1))),
+                    ),
+                };
 
-                // Bonus when an uppercase byte follows a lowercase byte
+                // Bonus when an uppercase char follows a lowercase char
                 let capitalization_mask = B::widen_mask(
                     is_upper_mask.and(is_lower_mask.shift_right_padded_1(prev_chunk_is_lower_mask)),
                 );
                 let capitalization_bonus_masked = capitalization_mask.and(capitalization_bonus);
                 prev_chunk_is_lower_mask = is_lower_mask;
 
-                // Bonus for matching after a delimiter character. We consider
-                // anything that isn't a digit or a letter and is within ASCII
-                // range to be a delimiter.
-                let is_digit_mask = haystack_chunk
-                    .gt(B::Bytes::splat(b'0' - 1))
-                    .and(haystack_chunk.lt(B::Bytes::splat(b'9' + 1)));
-                let char_is_delimiter_mask = is_letter_mask
-                    .or(is_digit_mask)
-                    .or(haystack_chunk.gt(B::Bytes::splat(127)))
-                    .not();
-                let prev_char_is_delimiter_mask =
-                    char_is_delimiter_mask.shift_right_padded_1(prev_chunk_char_is_delimiter_mask);
-                let delimiter_mask =
-                    B::widen_mask(prev_char_is_delimiter_mask.and(char_is_delimiter_mask.not()));
-                let delimiter_bonus_masked = delimiter_mask.and(delimiter_bonus);
-                prev_chunk_char_is_delimiter_mask = char_is_delimiter_mask;
+                // Bonus for matching a non-delimiter after a delimiter character
+                let (char_is_delimiter_mask, char_delimiter_bonus) = chunk_delimiters::<B>(
+                    haystack_chunk,
+                    || match char_classes {
+                        Some(classes) => char_class_mask(classes, CharClass::Delimiter),
+                        None => ascii_delimiter_mask::<B>(
+                            haystack_chunk,
+                            is_upper_mask.or(is_lower_mask),
+                        ),
+                    },
+                    scoring,
+                    (delimiter_bonus, separator_bonus),
+                    (separator, alt_separator),
+                );
+                let delimiter_bonus_masked = B::widen_mask(char_is_delimiter_mask.not())
+                    .and(char_delimiter_bonus.shift_right_padded::<1>(prev_chunk_delimiter_bonus));
+                prev_chunk_delimiter_bonus = char_delimiter_bonus;
 
                 // Match-conditional bonuses (delimiter, capitalization, prefix)
-                let match_and_masked_bonuses = delimiter_bonus_masked
-                    .add(capitalization_bonus_masked)
-                    .add(prefix_bonus_masked)
-                    .add(match_score);
+                let char_bonus = delimiter_bonus_masked.add(capitalization_bonus_masked);
+                let match_and_masked_bonuses = char_bonus.add(prefix_bonus_masked).add(match_score);
                 prefix_bonus_masked = B::Score::zero();
+                // Only the first needle char earns the decaying prefix bonus
+                let first_row_bonuses = match_and_masked_bonuses.add(chunk_prefix_bonus::<B>(
+                    scoring,
+                    haystack_start_pos + chunk_start,
+                ));
 
                 let mut up_gap_mask = B::Score::zero();
+                let mut prev_char_len = 1;
+                let mut prev_row_run_bonus = B::Score::zero();
+                let mut prev_chunk_prev_row_run_bonus = B::Score::zero();
                 let mut prev_row_scores = B::Score::zero();
                 let mut row_scores = B::Score::zero();
 
@@ -161,12 +215,38 @@ impl<B: Backend> SmithWaterman<B> {
                         B::widen_mask(exact_case_match_mask.or(flipped_case_match_mask));
                     let exact_case_match_mask = B::widen_mask(exact_case_match_mask);
 
+                    // Bonus for continuing a run of consecutive matches
+                    let consecutive_bonus_masked = if scoring.consecutive_bonus > 0 {
+                        let (extra_bonus, run_bonus) = consecutive_match_bonus::<B>(
+                            match_mask,
+                            char_bonus,
+                            consecutive_bonus,
+                            (up_gap_mask, match_masks.get(row_idx - 1, col_idx - 1)),
+                            (prev_row_run_bonus, prev_chunk_prev_row_run_bonus),
+                            prev_char_len,
+                        );
+                        prev_chunk_prev_row_run_bonus = std::mem::replace(
+                            consecutive_run_bonuses.get_unchecked_mut(row_idx),
+                            run_bonus,
+                        );
+                        prev_row_run_bonus = run_bonus;
+                        extra_bonus
+                    } else {
+                        B::Score::zero()
+                    };
+
                     // Diagonal - typical match/mismatch, advancing one cell
                     let diag_scores = {
                         let diag = prev_row_scores
                             .shift_right_padded::<1>(score_matrix.get(row_idx - 1, col_idx - 1));
                         // Add bonuses for matches
-                        let diag = diag.add(match_mask.and(match_and_masked_bonuses));
+                        let bonuses = match row_idx {
+                            1 => first_row_bonuses,
+                            _ => match_and_masked_bonuses,
+                        };
+                        let diag = diag
+                            .add(match_mask.and(bonuses))
+                            .add(consecutive_bonus_masked);
                         // Always pay the mismatch penalty
                         let diag = diag.subs(mismatch_penalty);
                         // Reward matching the needle's exact case
@@ -204,6 +284,7 @@ impl<B: Backend> SmithWaterman<B> {
                     prev_row_scores = next_row_scores;
                     row_scores = next_row_scores;
                     up_gap_mask = match_mask;
+                    prev_char_len = needle_char.len;
                 }
 
                 // Last row of the matrix at this chunk
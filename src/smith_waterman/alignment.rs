@@ -10,6 +10,11 @@ impl<B: Backend> SmithWaterman<B> {
         score: u16,
         max_typos: Option<u16>,
     ) -> AlignmentPathIter<'a> {
+        // Only the unicode path has a row per needle char
+        let typo_unit_ends = match unicode_haystack {
+            Some(_) => self.typo_unit_ends.as_slice(),
+            None => &[],
+        };
         AlignmentPathIter::new::<B>(
             &self.score_matrix,
             &self.match_masks,
@@ -17,6 +22,7 @@ impl<B: Backend> SmithWaterman<B> {
             self.haystack_chunks,
             haystack_start_pos,
             unicode_haystack,
+            typo_unit_ends,
             score,
             max_typos,
         )
@@ -29,6 +29,11 @@ pub(crate) struct AlignmentPathIter<'a> {
     col_idx: usize,
     haystack_start_pos: usize,
     unicode_haystack: Option<&'a [u8]>,
+    /// Needle char index at which the typo unit of each needle char ends, so typos are only
+    /// counted once per unit (see [`crate::prefilter::typo_unit_ends`])
+    typo_unit_ends: &'a [usize],
+    /// End of the typo unit of the last counted typo
+    last_typo_unit_end: usize,
     max_typos: Option<u16>,
     typo_count: u16,
     score: u16,
@@ -45,6 +50,7 @@ impl<'a> AlignmentPathIter<'a> {
         haystack_chunks: usize,
         haystack_start_pos: usize,
         unicode_haystack: Option<&'a [u8]>,
+        typo_unit_ends: &'a [usize],
         score: u16,
         max_typos: Option<u16>,
     ) -> Self {
@@ -60,6 +66,8 @@ impl<'a> AlignmentPathIter<'a> {
             col_idx,
             haystack_start_pos,
             unicode_haystack,
+            typo_unit_ends,
+            last_typo_unit_end: usize::MAX,
             max_typos,
             typo_count: 0,
             score,
@@ -103,6 +111,31 @@ impl<'a> AlignmentPathIter<'a> {
             self.match_masks[offset] != 0
         }
     }
+
+    /// Counts a typo on the needle char of the current row, unless its typo unit already has one
+    #[inline(always)]
+    fn add_typo(&mut self) {
+        match self.typo_unit_ends.get(self.row_idx - 1) {
+            Some(&end) if end == self.last_typo_unit_end => {}
+            Some(&end) => {
+                self.last_typo_unit_end = end;
+                self.typo_count += 1;
+            }
+            None => self.typo_count += 1,
+        }
+    }
+
+    /// Typos from the needle chars of the remaining rows, when none of them match
+    #[inline(always)]
+    fn remaining_typos(&self) -> u16 {
+        if self.typo_unit_ends.is_empty() {
+            return self.row_idx as u16;
+        }
+        let ends = &self.typo_unit_ends[..self.row_idx];
+        let units = ends.chunk_by(|a, b| a == b).count();
+        let counted = ends.last() == Some(&self.last_typo_unit_end);
+        (units - counted as usize) as u16
+    }
 }
 
 impl<'a> Iterator for AlignmentPathIter<'a> {
@@ -124,7 +157,7 @@ impl<'a> Iterator for AlignmentPathIter<'a> {
         // Must be moving up only (at left edge), or lost alignment
         if self.col_idx < self.lanes_per_chunk || self.score == 0 {
             if let Some(max_typos) = self.max_typos
-                && (self.typo_count + self.row_idx as u16) > max_typos
+                && (self.typo_count + self.remaining_typos()) > max_typos
             {
                 self.finished = true;
                 return Some(None);
@@ -161,9 +194,9 @@ impl<'a> Iterator for AlignmentPathIter<'a> {
 
         // Match or mismatch (diagonal)
         if diag >= left && diag >= up {
+            self.add_typo();
             self.row_idx -= 1;
             self.col_idx -= 1;
-            self.typo_count += 1;
             self.score = diag;
             Some(Some(Alignment::Mismatch))
         // Skipped character in haystack (left)
@@ -173,7 +206,7 @@ impl<'a> Iterator for AlignmentPathIter<'a> {
             Some(Some(Alignment::Left))
         // Skipped character in needle (up)
         } else {
-            self.typo_count += 1;
+            self.add_typo();
             self.row_idx -= 1;
             self.score = up;
             Some(Some(Alignment::Up))
@@ -4,8 +4,8 @@
 use super::super::Backend;
 use super::super::{BackendScalar8, BackendScalar16U8};
 use super::generator::{ByteCursor, run_generated_inputs, test_bound};
-use crate::Scoring;
 use crate::smith_waterman::{Kernel, SmithWaterman, score_fits_in_u8};
+use crate::{PathMode, Scoring};
 
 /// randomized matcher invocation: which needle, haystack, and config to run
 #[derive(Debug, Clone)]
@@ -120,16 +120,19 @@ fn cases() -> Vec<(&'static str, &'static str)> {
         ("needle", "____________needle____________"),
         ("abcdefghij", "abcdefghij"),
         ("abcdefghijklmnopqrst", "abcdefghijklmnopqrst"),
+        // unicode delimiters and capitalization
+        ("фа", "путь—файл_Файл"),
+        ("É", "ыÉtat"),
     ]
 }
 
 fn score_with<B: Backend>(needle: &str, haystack: &str) -> u16 {
-    let mut matcher = SmithWaterman::<B>::new(needle, &Scoring::default(), false);
-    matcher.score_haystack(haystack.as_bytes(), true)
+    let mut matcher = SmithWaterman::<B>::new(needle, &Scoring::default(), false, None, false);
+    matcher.score_haystack(haystack.as_bytes(), 0)
 }
 
 fn indices_with<B: Backend>(needle: &str, haystack: &str) -> Vec<u32> {
-    let mut matcher = SmithWaterman::<B>::new(needle, &Scoring::default(), false);
+    let mut matcher = SmithWaterman::<B>::new(needle, &Scoring::default(), false, None, false);
     matcher
         .score_haystack_indices(haystack.as_bytes(), 0, None)
         .1
@@ -197,6 +200,152 @@ fn cross_backend_parity_indices() {
     }
 }
 
+fn scoring_scores_with<B: Backend>(
+    scoring: &Scoring,
+    path_mode: Option<PathMode>,
+    needle: &str,
+    haystack: &str,
+) -> (u16, u16) {
+    let mut matcher = SmithWaterman::<B>::new(needle, scoring, false, path_mode, false);
+    (
+        matcher.score_haystack(haystack.as_bytes(), 0),
+        matcher.score_haystack_unicode(haystack.as_bytes(), 0),
+    )
+}
+
+/// Per-row body for the parity tests over a non-default [`Scoring`] or [`PathMode`]
+macro_rules! assert_scoring_row {
+    ($scoring:expr, $path_mode:expr, $needle:expr, $haystack:expr, $want:expr ;
+     $cfg:meta, $label:literal, $backend:ty, $reference:ty, $is_u8:literal) => {
+        #[cfg($cfg)]
+        {
+            let path_mode: Option<PathMode> = $path_mode;
+            if <$backend>::is_available() && (!$is_u8 || score_fits_in_u8($needle.len(), $scoring))
+            {
+                assert_eq!(
+                    scoring_scores_with::<$backend>($scoring, path_mode, $needle, $haystack),
+                    $want,
+                    "{} score mismatch for needle={:?} haystack={:?} scoring={:?} path_mode={:?}",
+                    $label,
+                    $needle,
+                    $haystack,
+                    $scoring,
+                    path_mode
+                );
+            }
+        }
+    };
+}
+
+#[test]
+fn cross_backend_parity_prefix_bonus_decay() {
+    // Decays across several chunks of every backend width
+    let scoring = Scoring {
+        prefix_bonus: 40,
+        prefix_bonus_decay: 1,
+        ..Scoring::default()
+    };
+    let extra = [
+        ("foo", "xfoo"),
+        ("foo", "xxxxxxxxfoo"),
+        ("foo", "f_o_foo"),
+        ("é", "xxé"),
+    ];
+    for (needle, haystack) in cases().into_iter().chain(extra) {
+        let want = scoring_scores_with::<BackendScalar8>(&scoring, None, needle, haystack);
+        for_each_backend!(assert_scoring_row!(&scoring, None, needle, haystack, want));
+    }
+}
+
+#[test]
+fn cross_backend_parity_consecutive_bonus() {
+    // Larger than the delimiter and capitalization bonuses
+    let scoring = Scoring {
+        consecutive_bonus: 6,
+        ..Scoring::default()
+    };
+    // Runs crossing the chunk boundary of every backend width
+    let crossing = [7, 15, 31, 63].map(|len| {
+        (
+            format!("{}_bcd", "x".repeat(len)),
+            format!("{}_béd", "x".repeat(len)),
+        )
+    });
+    let extra = [
+        ("bcd", "b_c_dxbcd"),
+        ("fbar", "foo_bar"),
+        ("fbar", "fooBar"),
+        ("béd", "b_é_dxbéd"),
+        ("ééé", "éxéé_ééé"),
+    ];
+    let owned = crossing
+        .iter()
+        .flat_map(|(ascii, unicode)| [("bcd", ascii.as_str()), ("béd", unicode.as_str())]);
+    for (needle, haystack) in cases().into_iter().chain(extra).chain(owned) {
+        let want = scoring_scores_with::<BackendScalar8>(&scoring, None, needle, haystack);
+        for_each_backend!(assert_scoring_row!(&scoring, None, needle, haystack, want));
+    }
+}
+
+#[test]
+fn cross_backend_parity_delimiter_table() {
+    // Weights each delimiter differently, leaving `_` out
+    let scoring = Scoring {
+        delimiters: Some(vec![(b'/', 12), (b'-', 3), (b' ', 0)]),
+        ..Scoring::default()
+    };
+    let extra = [
+        ("b", "a/b"),
+        ("b", "a-b"),
+        ("b", "a_b"),
+        ("b", "a b"),
+        ("abc", "a/b-c_abc"),
+        ("é", "a/é"),
+        ("éb", "x-é/b_éb"),
+        ("fbb", "foo/bar-baz"),
+        ("fbb", "foo/bär-baz"),
+    ];
+    for (needle, haystack) in cases().into_iter().chain(extra) {
+        let want = scoring_scores_with::<BackendScalar8>(&scoring, None, needle, haystack);
+        for_each_backend!(assert_scoring_row!(&scoring, None, needle, haystack, want));
+    }
+}
+
+#[test]
+fn cross_backend_parity_path_mode() {
+    let path_modes = [
+        PathMode::default(),
+        PathMode {
+            backslash: true,
+            ..PathMode::default()
+        },
+    ];
+    let extra = [
+        ("main", "src/main.rs"),
+        ("main", "src/main_window.rs"),
+        ("main", "src\\main.rs"),
+        ("mr", "src/main.rs"),
+        ("sm", "src\\main_tests/main.rs"),
+        ("é", "src/é.rs"),
+        ("éb", "a\\é_b/éb"),
+        ("fbb", "foo/bär/baz.rs"),
+    ];
+    let scoring = Scoring::default();
+    for path_mode in path_modes {
+        for (needle, haystack) in cases().into_iter().chain(extra) {
+            let want =
+                scoring_scores_with::<BackendScalar8>(&scoring, Some(path_mode), needle, haystack);
+            for_each_backend!(assert_scoring_row!(
+                &scoring,
+                Some(path_mode),
+                needle,
+                haystack,
+                want
+            ));
+        }
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Randomized: every backend must match its lane-matched scalar reference over
 // arbitrary inputs. Runs under proptest normally, and over a small fixed
@@ -204,8 +353,9 @@ fn cross_backend_parity_indices() {
 // ---------------------------------------------------------------------------
 
 fn score_bytes_with<B: Backend>(needle: &str, haystack: &[u8], case_sensitive: bool) -> u16 {
-    let mut matcher = SmithWaterman::<B>::new(needle, &Scoring::default(), case_sensitive);
-    matcher.score_haystack(haystack, true)
+    let mut matcher =
+        SmithWaterman::<B>::new(needle, &Scoring::default(), case_sensitive, None, false);
+    matcher.score_haystack(haystack, 0)
 }
 
 fn indices_bytes_with<B: Backend>(
@@ -214,7 +364,8 @@ fn indices_bytes_with<B: Backend>(
     max_typos: Option<u16>,
     case_sensitive: bool,
 ) -> (u16, Vec<u32>) {
-    let mut matcher = SmithWaterman::<B>::new(needle, &Scoring::default(), case_sensitive);
+    let mut matcher =
+        SmithWaterman::<B>::new(needle, &Scoring::default(), case_sensitive, None, false);
     matcher.score_haystack_indices(haystack, 0, max_typos)
 }
 
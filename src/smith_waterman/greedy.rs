@@ -2,14 +2,15 @@
 //! to find the optimal alignment. Runs in linear time and used for when the Smith Waterman matrix
 //! would balloon in size (due to being N * M)
 
-use crate::{Scoring, prefilter::case_needle};
+use crate::{PathMode, Scoring, prefilter::case_needle};
 
 pub fn match_greedy(
     needle: &[u8],
     haystack: &[u8],
     scoring: &Scoring,
+    path_mode: Option<&PathMode>,
     case_sensitive: bool,
-    include_prefix: bool,
+    haystack_start_pos: usize,
 ) -> Option<(u16, Vec<u32>)> {
     let needle = case_needle(needle, case_sensitive);
     if needle.len() > haystack.len() {
@@ -23,16 +24,16 @@ pub fn match_greedy(
     let mut delimiter_bonus_enabled = false;
     let mut previous_haystack_is_lower = false;
     let mut previous_haystack_is_delimiter = false;
+    let mut previous_haystack_char = 0;
+    let mut run_bonus = 0;
     'outer: for (needle_idx, &(needle_char, flipped_case_needle_char)) in needle.iter().enumerate()
     {
         let haystack_start_idx = haystack_idx;
         while haystack_idx <= (haystack.len() - needle.len() + needle_idx) {
             let haystack_char = haystack[haystack_idx];
-            let haystack_is_digit = haystack_char.is_ascii_digit();
             let haystack_is_upper = haystack_char.is_ascii_uppercase();
             let haystack_is_lower = haystack_char.is_ascii_lowercase();
-            let haystack_is_delimiter = haystack_char.is_ascii()
-                && !(haystack_is_lower || haystack_is_upper || haystack_is_digit);
+            let haystack_is_delimiter = scoring.is_delimiter(haystack_char);
 
             // Only enable delimiter bonus if we've seen a non-delimiter char
             if !haystack_is_delimiter {
@@ -41,6 +42,7 @@ pub fn match_greedy(
 
             if needle_char != haystack_char && flipped_case_needle_char != haystack_char {
                 previous_haystack_is_delimiter = delimiter_bonus_enabled && haystack_is_delimiter;
+                previous_haystack_char = haystack_char;
                 previous_haystack_is_lower = haystack_is_lower;
                 haystack_idx += 1;
                 continue;
@@ -65,17 +67,38 @@ pub fn match_greedy(
             if needle_char == haystack_char {
                 score = score.saturating_add(scoring.matching_case_bonus);
             }
+            let mut char_bonus = 0;
             if haystack_is_upper && previous_haystack_is_lower {
-                score = score.saturating_add(scoring.capitalization_bonus);
+                char_bonus = scoring.capitalization_bonus;
             }
-            if include_prefix && haystack_idx == 0 {
-                score = score.saturating_add(scoring.prefix_bonus);
+            if needle_idx == 0 {
+                score = score
+                    .saturating_add(scoring.prefix_bonus_at(haystack_start_pos + haystack_idx));
             }
             if previous_haystack_is_delimiter && !haystack_is_delimiter {
-                score = score.saturating_add(scoring.delimiter_bonus);
+                char_bonus = char_bonus.saturating_add(
+                    scoring.delimiter_bonus_after(previous_haystack_char, path_mode),
+                );
+            }
+            // Consecutive matches inherit the largest bonus since the start of the run
+            if scoring.consecutive_bonus > 0
+                && needle_idx != 0
+                && haystack_idx == haystack_start_idx
+            {
+                score = score.saturating_add(
+                    scoring
+                        .consecutive_bonus
+                        .max(run_bonus)
+                        .saturating_sub(char_bonus),
+                );
+                run_bonus = run_bonus.max(char_bonus);
+            } else {
+                run_bonus = char_bonus;
             }
+            score = score.saturating_add(char_bonus);
 
             previous_haystack_is_delimiter = delimiter_bonus_enabled && haystack_is_delimiter;
+            previous_haystack_char = haystack_char;
             previous_haystack_is_lower = haystack_is_lower;
 
             indices.push(haystack_idx as u32);
@@ -102,8 +125,9 @@ mod tests {
             needle.as_bytes(),
             haystack.as_bytes(),
             &Scoring::default(),
+            None,
             false,
-            true,
+            0,
         )
         .map(|(score, _)| score)
         .unwrap_or_default()
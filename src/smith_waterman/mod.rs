@@ -48,7 +48,7 @@
 //!
 //! Frizbee previously used inter-sequence parallelism (one needle, $LANES haystacks) but this performed about the same as sequential layout due to requiring interleaving the haystacks and bucketing based on haystack length, while performing worse in parallel due to the required bucketing.
 
-use crate::{Scoring, prefilter::UnicodeChar};
+use crate::{PathMode, Scoring, prefilter::UnicodeChar};
 use backend::Backend;
 #[cfg(target_arch = "x86_64")]
 use backend::{BackendAVX, BackendAVX512, BackendAVX512U8, BackendAVXU8, BackendSSE, BackendSSEU8};
@@ -95,7 +95,8 @@ pub(crate) fn score_fits_in_u8(needle_len: usize, scoring: &Scoring) -> bool {
         .max(scoring.gap_extend_penalty as usize)
         .max(scoring.matching_case_bonus as usize)
         .max(scoring.capitalization_bonus as usize)
-        .max(scoring.delimiter_bonus as usize)
+        .max(scoring.max_delimiter_bonus() as usize)
+        .max(scoring.consecutive_bonus as usize)
         .max(scoring.prefix_bonus as usize);
     if max_constant > u8::MAX as usize {
         return false;
@@ -120,11 +121,20 @@ pub(crate) struct SmithWaterman<B: Backend> {
     needle: String,
     needle_simd: Vec<(B::Bytes, B::Bytes)>,
     needle_unicode: Vec<UnicodeChar>,
+    /// Needle char index at which the typo unit of each needle char ends on the unicode path,
+    /// see [`crate::prefilter::typo_unit_ends`]
+    typo_unit_ends: Vec<usize>,
     case_sensitive: bool,
     scoring: Scoring,
+    path_mode: Option<PathMode>,
     score_matrix: Matrix<B>,
     match_masks: Matrix<B>,
     unicode_pending_gap_open_masks: Vec<B::Score>,
+    /// Run bonus of each row at the previous chunk, see [`Scoring::consecutive_bonus`]
+    consecutive_run_bonuses: Vec<B::Score>,
+    /// [`crate::char_class::CharClass`] of each byte of the haystack on the unicode path, when
+    /// the haystack isn't ASCII
+    haystack_char_classes: Vec<u8>,
     /// Number of LANES-wide chunks (incl. the leading zero column) actually
     /// consumed by the most recent `score_haystack` call. The matrix stride is
     /// always sized for `MAX_HAYSTACK_LEN` for zero-free reuse.
@@ -132,7 +142,13 @@ pub(crate) struct SmithWaterman<B: Backend> {
 }
 
 pub(crate) trait Kernel: Clone + std::fmt::Debug + 'static {
-    fn new(needle: &str, scoring: &Scoring, case_sensitive: bool) -> Self;
+    fn new(
+        needle: &str,
+        scoring: &Scoring,
+        case_sensitive: bool,
+        path_mode: Option<PathMode>,
+        graphemes: bool,
+    ) -> Self;
     fn is_available() -> bool;
     fn score_haystack_indices(
         &mut self,
@@ -161,13 +177,15 @@ mod tests {
     const CHAR_SCORE: u16 = MATCH_SCORE + MATCHING_CASE_BONUS;
 
     fn get_score(needle: &str, haystack: &str) -> u16 {
-        let mut matcher = SmithWaterman::<BackendScalar8>::new(needle, &Scoring::default(), false);
-        matcher.score_haystack(haystack.as_bytes(), true)
+        let mut matcher =
+            SmithWaterman::<BackendScalar8>::new(needle, &Scoring::default(), false, None, false);
+        matcher.score_haystack(haystack.as_bytes(), 0)
     }
 
     fn get_unicode_score(needle: &str, haystack: &str) -> u16 {
-        let mut matcher = SmithWaterman::<BackendScalar8>::new(needle, &Scoring::default(), false);
-        matcher.score_haystack_unicode(haystack.as_bytes(), true)
+        let mut matcher =
+            SmithWaterman::<BackendScalar8>::new(needle, &Scoring::default(), false, None, false);
+        matcher.score_haystack_unicode(haystack.as_bytes(), 0)
     }
 
     fn get_score_typos(needle: &str, haystack: &str, max_typos: u16) -> Option<u16> {
@@ -180,17 +198,23 @@ mod tests {
         max_typos: u16,
         case_sensitive: bool,
     ) -> Option<u16> {
-        let mut matcher =
-            SmithWaterman::<BackendScalar8>::new(needle, &Scoring::default(), case_sensitive);
+        let mut matcher = SmithWaterman::<BackendScalar8>::new(
+            needle,
+            &Scoring::default(),
+            case_sensitive,
+            None,
+            false,
+        );
 
-        let score = matcher.score_haystack(haystack.as_bytes(), true);
+        let score = matcher.score_haystack(haystack.as_bytes(), 0);
         matcher
             .has_alignment_path(score, max_typos)
             .then_some(score)
     }
 
     fn get_indices(needle: &str, haystack: &str) -> Vec<u32> {
-        let mut matcher = SmithWaterman::<BackendScalar8>::new(needle, &Scoring::default(), false);
+        let mut matcher =
+            SmithWaterman::<BackendScalar8>::new(needle, &Scoring::default(), false, None, false);
 
         matcher
             .score_haystack_indices(haystack.as_bytes(), 0, None)
@@ -198,7 +222,8 @@ mod tests {
     }
 
     fn get_unicode_indices(needle: &str, haystack: &str) -> Vec<u32> {
-        let mut matcher = SmithWaterman::<BackendScalar8>::new(needle, &Scoring::default(), false);
+        let mut matcher =
+            SmithWaterman::<BackendScalar8>::new(needle, &Scoring::default(), false, None, false);
 
         matcher
             .score_haystack_unicode_indices(haystack.as_bytes(), 0, None)
@@ -235,7 +260,12 @@ mod tests {
     fn unicode_gap_propagation_counts_skipped_scalars_once() {
         assert_eq!(
             get_unicode_score("éx", "ébx"),
-            get_unicode_score("éx", "é😀x")
+            get_unicode_score("éx", "é𠀀x")
+        );
+        // Symbols such as emoji are delimiters, so the char after one earns the delimiter bonus
+        assert_eq!(
+            get_unicode_score("éx", "é😀x"),
+            get_unicode_score("éx", "ébx") + DELIMITER_BONUS
         );
         assert_eq!(
             get_unicode_score("ab", "aéb"),
@@ -246,9 +276,14 @@ mod tests {
     #[test]
     fn unicode_gap_propagation_handles_adjacent_scalar_end_then_body() {
         assert_eq!(
-            get_unicode_score("ab", "aé😀b"),
+            get_unicode_score("ab", "aé𠀀b"),
             2 * CHAR_SCORE + PREFIX_BONUS - GAP_OPEN_PENALTY - GAP_EXTEND_PENALTY
         );
+        // The char after the emoji delimiter earns the delimiter bonus
+        assert_eq!(
+            get_unicode_score("ab", "aé😀b"),
+            2 * CHAR_SCORE + PREFIX_BONUS + DELIMITER_BONUS - GAP_OPEN_PENALTY - GAP_EXTEND_PENALTY
+        );
     }
 
     #[test]
@@ -363,15 +398,17 @@ mod tests {
 
     #[cfg(feature = "match_end_col")]
     fn get_end_col(needle: &str, haystack: &str) -> u16 {
-        let mut matcher = SmithWaterman::<BackendScalar8>::new(needle, &Scoring::default(), false);
-        matcher.score_haystack(haystack.as_bytes(), true);
+        let mut matcher =
+            SmithWaterman::<BackendScalar8>::new(needle, &Scoring::default(), false, None, false);
+        matcher.score_haystack(haystack.as_bytes(), 0);
         matcher.match_end_col(haystack.as_bytes(), false)
     }
 
     #[cfg(feature = "match_end_col")]
     fn get_end_col_unicode(needle: &str, haystack: &str) -> u16 {
-        let mut matcher = SmithWaterman::<BackendScalar8>::new(needle, &Scoring::default(), false);
-        matcher.score_haystack_unicode(haystack.as_bytes(), true);
+        let mut matcher =
+            SmithWaterman::<BackendScalar8>::new(needle, &Scoring::default(), false, None, false);
+        matcher.score_haystack_unicode(haystack.as_bytes(), 0);
         matcher.match_end_col(haystack.as_bytes(), true)
     }
 
@@ -458,7 +495,8 @@ mod tests {
 
     #[test]
     fn unicode_indices_use_original_byte_offsets() {
-        let mut matcher = SmithWaterman::<BackendScalar8>::new("é", &Scoring::default(), false);
+        let mut matcher =
+            SmithWaterman::<BackendScalar8>::new("é", &Scoring::default(), false, None, false);
 
         assert_eq!(
             matcher
@@ -470,7 +508,8 @@ mod tests {
 
     #[test]
     fn unicode_indices_with_offset_trace_through_multibyte_haystack_gaps() {
-        let mut matcher = SmithWaterman::<BackendScalar8>::new("éx", &Scoring::default(), false);
+        let mut matcher =
+            SmithWaterman::<BackendScalar8>::new("éx", &Scoring::default(), false, None, false);
 
         assert_eq!(
             matcher
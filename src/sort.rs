@@ -54,6 +54,7 @@ mod test {
                 score: rng.random::<u16>(),
                 index,
                 exact: rng.random_bool(0.5),
+                field: 0,
                 #[cfg(feature = "match_end_col")]
                 end_col: 0,
             })
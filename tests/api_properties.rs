@@ -224,6 +224,7 @@ fn mtch(score: u16, index: u32) -> Match {
         score,
         index,
         exact: false,
+        field: 0,
         #[cfg(feature = "match_end_col")]
         end_col: 0,
     }